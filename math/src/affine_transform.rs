@@ -1,4 +1,4 @@
-use crate::{Mat4, Quat, Vec3, Vec4};
+use crate::{Identity, Mat4, Quat, Vec3, Vec4, Zero};
 
 #[allow(dead_code)]
 #[derive(Clone, Copy)]
@@ -8,7 +8,62 @@ pub struct AffineTransform {
     pub scalar: Vec3<f32>,
 }
 
+impl Identity for AffineTransform {
+    const IDENTITY: Self = Self {
+        position: Vec3::ZERO,
+        orientation: Quat::IDENTITY,
+        scalar: Vec3::new(1.0, 1.0, 1.0),
+    };
+}
+
 impl AffineTransform {
+    #[inline]
+    pub const fn is_uniform_scale(&self) -> bool {
+        let s = self.scalar;
+        s.x() == s.y() && s.y() == s.z()
+    }
+    // the transform that applies other first, then self. self's scalar has to be
+    // uniform, otherwise the result would need a shear that a
+    // position/orientation/scalar triple can't hold
+    pub const fn compose(&self, other: &Self) -> Self {
+        debug_assert!(self.is_uniform_scale());
+        Self {
+            position: self.transform_point(other.position),
+            orientation: self.orientation.mul(other.orientation),
+            scalar: self.scalar.scaled_nonuniform(other.scalar),
+        }
+    }
+    // None if the scalar is 0 or non-uniform, the inverse of a non-uniform scale
+    // followed by a rotation is a shear, see compose
+    pub const fn inverse(&self) -> Option<Self> {
+        let s = self.scalar;
+        if s.x() == 0.0 || !self.is_uniform_scale() {
+            return None;
+        }
+
+        let scalar = Vec3::new(1.0 / s.x(), 1.0 / s.y(), 1.0 / s.z());
+        let orientation = self.orientation.inverse();
+        let position = orientation
+            .rotate_vec(self.position)
+            .scaled_nonuniform(scalar)
+            .scaled(-1.0);
+
+        Some(Self {
+            position,
+            orientation,
+            scalar,
+        })
+    }
+    #[inline]
+    pub const fn transform_point(&self, p: Vec3<f32>) -> Vec3<f32> {
+        self.transform_vector(p).add(self.position)
+    }
+    // ignores the translation
+    #[inline]
+    pub const fn transform_vector(&self, v: Vec3<f32>) -> Vec3<f32> {
+        self.orientation
+            .rotate_vec(v.scaled_nonuniform(self.scalar))
+    }
     #[inline]
     pub const fn move_global(&mut self, offset: Vec3<f32>) {
         self.position.add_assign(offset);
@@ -49,10 +104,77 @@ impl AffineTransform {
         Mat4::scaling(Vec4::from_vec3(self.scalar, 1.0))
     }
     pub const fn as_mat4(&self) -> Mat4<f32> {
-        let t = self.get_translation_matrix();
-        let r = self.get_rotation_matrix();
-        let s = self.get_scaling_matrix();
+        Mat4::from_trs(self.position, self.orientation, self.scalar)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AffineTransform;
+    use crate::{Identity, Quat, Vec3, Vec4};
+
+    fn assert_close(a: Vec3<f32>, b: Vec3<f32>) {
+        assert!(a.sub(b).length() < 1e-5, "{a} != {b}");
+    }
+
+    #[test]
+    fn matrix_matches_transform_point() {
+        let t = AffineTransform {
+            position: Vec3::new(1.0, -2.0, 3.0),
+            orientation: Quat::unit_from_angle_axis(60f32.to_radians(), Vec3::new(1.0, 1.0, 0.0)),
+            scalar: Vec3::new(2.0, 0.5, 3.0),
+        };
+        let m = t.as_mat4();
+
+        for p in [Vec3::new(1.0, 0.0, 0.0), Vec3::new(-4.0, 5.0, 0.5)] {
+            let mp = m.mul_vec4(Vec4::from_vec3(p, 1.0));
+            assert_close(Vec3::new(mp.x(), mp.y(), mp.z()), t.transform_point(p));
+
+            let mv = m.mul_vec4(Vec4::from_vec3(p, 0.0));
+            assert_close(Vec3::new(mv.x(), mv.y(), mv.z()), t.transform_vector(p));
+        }
+    }
+
+    #[test]
+    fn compose_and_inverse() {
+        let a = AffineTransform {
+            position: Vec3::new(1.0, -2.0, 3.0),
+            orientation: Quat::unit_from_angle_axis(60f32.to_radians(), Vec3::new(1.0, 1.0, 0.0)),
+            scalar: Vec3::new(2.0, 2.0, 2.0),
+        };
+        let b = AffineTransform {
+            position: Vec3::new(0.0, 4.0, 0.0),
+            orientation: Quat::unit_from_angle_axis(90f32.to_radians(), Vec3::new(0.0, 0.0, 1.0)),
+            scalar: Vec3::new(1.0, 3.0, 0.5),
+        };
+        let p = Vec3::new(2.0, 1.0, -1.0);
+
+        assert_close(
+            a.compose(&b).transform_point(p),
+            a.transform_point(b.transform_point(p)),
+        );
+
+        let inv = a.inverse().unwrap();
+        assert_close(inv.transform_point(a.transform_point(p)), p);
+        assert_close(a.compose(&inv).transform_point(p), p);
+
+        let flat = AffineTransform {
+            scalar: Vec3::new(1.0, 0.0, 1.0),
+            ..AffineTransform::IDENTITY
+        };
+        assert!(flat.inverse().is_none());
+        assert!(b.inverse().is_none());
+        assert_eq!(AffineTransform::IDENTITY.transform_point(p), p);
+    }
 
-        t.mul(&r).mul(&s)
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn compose_non_uniform() {
+        let stretched = AffineTransform {
+            scalar: Vec3::new(1.0, 2.0, 1.0),
+            ..AffineTransform::IDENTITY
+        };
+        stretched.compose(&AffineTransform::IDENTITY);
     }
 }
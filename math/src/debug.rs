@@ -0,0 +1,49 @@
+use std::fmt::{Debug, Formatter, Result, Write};
+
+// Debug for a single vector component that calls out NaN and infinity.
+// floats are the only scalars whose Debug output is NaN, inf or -inf, so the output is checked
+// instead of requiring T: Float. that keeps Debug for every T: Debug
+pub(crate) struct Component<T>(pub T);
+
+impl<T: Debug> Debug for Component<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        // precision and flags never change how NaN or inf print, so a plain {:?} is enough to
+        // tell. the value itself goes through the caller's formatter to keep {:.2?} and {:#?}
+        let mut check = NonFiniteCheck {
+            start: [0; 4],
+            len: 0,
+        };
+        write!(check, "{:?}", self.0)?;
+
+        Debug::fmt(&self.0, f)?;
+        if check.is_non_finite() {
+            f.write_str(" (non-finite)")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// discards the output and keeps the first few bytes written
+struct NonFiniteCheck {
+    start: [u8; 4],
+    len: usize,
+}
+
+impl NonFiniteCheck {
+    fn is_non_finite(&self) -> bool {
+        self.len <= self.start.len() && matches!(&self.start[..self.len], b"NaN" | b"inf" | b"-inf")
+    }
+}
+
+impl Write for NonFiniteCheck {
+    fn write_str(&mut self, s: &str) -> Result {
+        let end = self.len + s.len();
+        if end <= self.start.len() {
+            self.start[self.len..end].copy_from_slice(s.as_bytes());
+        }
+        self.len = end;
+
+        Ok(())
+    }
+}
@@ -1,9 +1,12 @@
 mod affine_transform;
+mod debug;
 mod mat2;
 mod mat3;
 mod mat4;
 mod quat;
+mod ray;
 mod rigid_transform;
+mod std140;
 mod traits;
 mod vec2;
 mod vec3;
@@ -11,11 +14,13 @@ mod vec4;
 
 pub use affine_transform::AffineTransform;
 pub use mat2::Mat2;
-pub use mat3::Mat3;
+pub use mat3::{Mat3, Mat3Std140};
 pub use mat4::Mat4;
 pub use quat::Quat;
+pub use ray::Ray;
 pub use rigid_transform::RigidTransform;
-pub use traits::{Identity, One, Zero};
+pub use std140::{Std140, std140_align_up};
+pub use traits::{Cross, Dot, Float, Identity, Normalize, One, Zero};
 pub use vec2::Vec2;
 pub use vec3::Vec3;
 pub use vec4::Vec4;
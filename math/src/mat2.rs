@@ -3,9 +3,18 @@ use crate::vec2::Vec2;
 
 #[allow(dead_code)]
 #[repr(transparent)]
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct Mat2<T>([Vec2<T>; 2]);
 
+impl<T: std::fmt::Debug> std::fmt::Debug for Mat2<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mat2")
+            .field("c0", &self.0[0])
+            .field("c1", &self.0[1])
+            .finish()
+    }
+}
+
 impl<T> Mat2<T>
 where
     T: Copy,
@@ -4,9 +4,19 @@ use crate::traits::{Identity, One, Zero};
 
 #[allow(dead_code)]
 #[repr(transparent)]
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct Mat3<T>([Vec3<T>; 3]);
 
+impl<T: std::fmt::Debug> std::fmt::Debug for Mat3<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mat3")
+            .field("c0", &self.0[0])
+            .field("c1", &self.0[1])
+            .field("c2", &self.0[2])
+            .finish()
+    }
+}
+
 impl<T> Mat3<T>
 where
     T: Copy,
@@ -170,6 +180,21 @@ impl Mat3<f32> {
 
         Some(adj)
     }
+    // the inverse-transpose of model's upper-left 3x3, for transforming normals.
+    // a singular model falls back to the cofactor matrix, which only differs from
+    // the inverse-transpose by the 1/det factor that gets normalized away anyway
+    pub const fn normal_matrix(model: &crate::mat4::Mat4<f32>) -> Self {
+        let m = Self::from_cols(
+            Vec3::new(model.c0().x(), model.c0().y(), model.c0().z()),
+            Vec3::new(model.c1().x(), model.c1().y(), model.c1().z()),
+            Vec3::new(model.c2().x(), model.c2().y(), model.c2().z()),
+        );
+
+        match m.inverse() {
+            Some(inv) => inv.transposed(),
+            None => m.adjoint().transposed(),
+        }
+    }
 }
 
 impl<T: std::fmt::Display + Copy> std::fmt::Display for Mat3<T> {
@@ -205,10 +230,82 @@ where
     }
 }
 
+// std140 pads each column of a mat3 to 16 bytes
+#[allow(dead_code)]
+#[repr(transparent)]
+#[derive(Clone)]
+pub struct Mat3Std140<T>([Vec4<T>; 3]);
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Mat3Std140<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mat3Std140")
+            .field("c0", &self.0[0])
+            .field("c1", &self.0[1])
+            .field("c2", &self.0[2])
+            .finish()
+    }
+}
+
+impl<T: Zero + One> Identity for Mat3Std140<T> {
+    const IDENTITY: Self = Self([
+        Vec4::new(T::ONE, T::ZERO, T::ZERO, T::ZERO),
+        Vec4::new(T::ZERO, T::ONE, T::ZERO, T::ZERO),
+        Vec4::new(T::ZERO, T::ZERO, T::ONE, T::ZERO),
+    ]);
+}
+
+impl<T: Zero + One> Default for Mat3Std140<T> {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl<T> std::ops::Index<usize> for Mat3Std140<T> {
+    type Output = Vec4<T>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl<T> Mat3Std140<T>
+where
+    T: Zero + Copy,
+{
+    #[inline]
+    pub const fn from_mat3(m: &Mat3<T>) -> Self {
+        Self([m.c0().into_vec4(), m.c1().into_vec4(), m.c2().into_vec4()])
+    }
+    #[inline]
+    pub const fn to_mat3(&self) -> Mat3<T> {
+        Mat3::from_cols(
+            Vec3::new(self.0[0].x(), self.0[0].y(), self.0[0].z()),
+            Vec3::new(self.0[1].x(), self.0[1].y(), self.0[1].z()),
+            Vec3::new(self.0[2].x(), self.0[2].y(), self.0[2].z()),
+        )
+    }
+    #[inline]
+    pub fn into_2d_arr(self) -> [[T; 4]; 3] {
+        [
+            self.0[0].into_arr(),
+            self.0[1].into_arr(),
+            self.0[2].into_arr(),
+        ]
+    }
+}
+
+impl<T: PartialEq + Copy> PartialEq for Mat3Std140<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0[0] == other.0[0] && self.0[1] == other.0[1] && self.0[2] == other.0[2]
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::Mat3;
+    use super::Mat3Std140;
     use super::Vec3;
+    use crate::traits::Identity;
 
     #[test]
     fn multiplication_scaling() {
@@ -319,6 +416,51 @@ mod test {
             Vec3::new(-2.0, 2.0, 0.5),
             Vec3::new(6.0, -5.0, -1.5),
         );
-        assert_eq!(b.inverse(), Some(r2));
+        assert_eq!(b.inverse(), Some(r2.clone()));
+        assert_eq!(b.mul(&r2), Mat3::IDENTITY);
+    }
+    #[test]
+    fn normal_matrix() {
+        use crate::{Mat4, Vec4};
+
+        // non uniform scale followed by a translation
+        let model = Mat4::from_rows(
+            Vec4::new(2.0, 0.0, 0.0, 5.0),
+            Vec4::new(0.0, 1.0, 0.0, -3.0),
+            Vec4::new(0.0, 0.0, 1.0, 1.0),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        );
+        let n = Mat3::normal_matrix(&model);
+
+        assert_eq!(
+            n,
+            Mat3::from_rows(
+                Vec3::new(0.5, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                Vec3::new(0.0, 0.0, 1.0),
+            )
+        );
+
+        // the normal of the plane x + y = 0 stays perpendicular to its transformed tangent
+        let tangent = model.mul_vec4(Vec4::new(1.0, -1.0, 0.0, 0.0));
+        let normal = n.mul_vec(Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(
+            normal.dot(Vec3::new(tangent.x(), tangent.y(), tangent.z())),
+            0.0
+        );
+    }
+    #[test]
+    fn std140_round_trip() {
+        let m = Mat3::from_rows(
+            Vec3::new(1.0, 3.0, 1.0),
+            Vec3::new(0.0, 3.0, 1.0),
+            Vec3::new(4.0, 2.0, 0.0),
+        );
+
+        let padded = Mat3Std140::from_mat3(&m);
+
+        assert_eq!(std::mem::size_of::<Mat3Std140<f32>>(), 48);
+        assert_eq!(padded[1].w(), 0.0);
+        assert_eq!(padded.to_mat3(), m);
     }
 }
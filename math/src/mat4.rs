@@ -1,12 +1,24 @@
-use crate::traits::{Identity, One, Zero};
+use crate::quat::Quat;
+use crate::traits::{Float, Identity, One, Zero};
 use crate::vec3::Vec3;
 use crate::vec4::Vec4;
 
 #[allow(dead_code)]
 #[repr(transparent)]
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct Mat4<T>([Vec4<T>; 4]);
 
+impl<T: std::fmt::Debug> std::fmt::Debug for Mat4<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mat4")
+            .field("c0", &self.0[0])
+            .field("c1", &self.0[1])
+            .field("c2", &self.0[2])
+            .field("c3", &self.0[3])
+            .finish()
+    }
+}
+
 impl<T> Mat4<T>
 where
     T: Copy,
@@ -49,6 +61,80 @@ impl<T: One + Zero + Copy> Mat4<T> {
     }
 }
 
+#[inline]
+fn sub3<T: Float>(a: Vec3<T>, b: Vec3<T>) -> Vec3<T> {
+    Vec3::new(a.x() - b.x(), a.y() - b.y(), a.z() - b.z())
+}
+
+#[inline]
+fn dot3<T: Float>(a: Vec3<T>, b: Vec3<T>) -> T {
+    a.x() * b.x() + a.y() * b.y() + a.z() * b.z()
+}
+
+#[inline]
+fn cross3<T: Float>(a: Vec3<T>, b: Vec3<T>) -> Vec3<T> {
+    Vec3::new(
+        a.y() * b.z() - a.z() * b.y(),
+        a.z() * b.x() - a.x() * b.z(),
+        a.x() * b.y() - a.y() * b.x(),
+    )
+}
+
+#[inline]
+fn normalized3<T: Float>(a: Vec3<T>) -> Vec3<T> {
+    let l = dot3(a, a).sqrt();
+    if l == T::ZERO {
+        return a;
+    }
+    Vec3::new(a.x() / l, a.y() / l, a.z() / l)
+}
+
+impl<T: Float> Mat4<T> {
+    // Right handed perspective projection looking down -z. Produces Vulkan clip space,
+    // depth goes from 0 at the near plane to 1 at the far plane and y points down.
+    // fov_y is in radians.
+    pub fn perspective(fov_y: T, aspect_ratio: T, near: T, far: T) -> Self {
+        let two = T::ONE + T::ONE;
+        let f = T::ONE / (fov_y / two).tan();
+
+        Self::from_cols(
+            Vec4::new(f / aspect_ratio, T::ZERO, T::ZERO, T::ZERO),
+            Vec4::new(T::ZERO, -f, T::ZERO, T::ZERO),
+            Vec4::new(T::ZERO, T::ZERO, far / (near - far), -T::ONE),
+            Vec4::new(T::ZERO, T::ZERO, near * far / (near - far), T::ZERO),
+        )
+    }
+
+    // counter clockwise rotation of angle radians around axis. axis does not need to be normalized
+    pub fn rotation(axis: Vec3<T>, angle: T) -> Self {
+        let a = normalized3(axis);
+        let (x, y, z) = (a.x(), a.y(), a.z());
+        let (s, c) = angle.sin_cos();
+        let t = T::ONE - c;
+
+        Self::from_cols(
+            Vec4::new(t * x * x + c, t * x * y + s * z, t * x * z - s * y, T::ZERO),
+            Vec4::new(t * x * y - s * z, t * y * y + c, t * y * z + s * x, T::ZERO),
+            Vec4::new(t * x * z + s * y, t * y * z - s * x, t * z * z + c, T::ZERO),
+            Vec4::new(T::ZERO, T::ZERO, T::ZERO, T::ONE),
+        )
+    }
+
+    // Right handed view matrix. The camera looks down -z with +y as up.
+    pub fn look_at(eye: Vec3<T>, target: Vec3<T>, up: Vec3<T>) -> Self {
+        let f = normalized3(sub3(target, eye));
+        let s = normalized3(cross3(f, up));
+        let u = cross3(s, f);
+
+        Self::from_rows(
+            Vec4::new(s.x(), s.y(), s.z(), -dot3(s, eye)),
+            Vec4::new(u.x(), u.y(), u.z(), -dot3(u, eye)),
+            Vec4::new(-f.x(), -f.y(), -f.z(), dot3(f, eye)),
+            Vec4::new(T::ZERO, T::ZERO, T::ZERO, T::ONE),
+        )
+    }
+}
+
 impl<T: Zero> Zero for Mat4<T> {
     const ZERO: Self = Self::from_cols(Vec4::ZERO, Vec4::ZERO, Vec4::ZERO, Vec4::ZERO);
 }
@@ -127,6 +213,25 @@ impl<T> Mat4<T> {
 }
 
 impl Mat4<f32> {
+    // same as translation * rotation * scaling, without the two matrix multiplies
+    pub const fn from_trs(translation: Vec3<f32>, rotation: Quat, scale: Vec3<f32>) -> Self {
+        let r = rotation.as_mat3();
+
+        Self::from_cols(
+            Vec4::from_vec3(r.c0().scaled(scale.x()), 0.0),
+            Vec4::from_vec3(r.c1().scaled(scale.y()), 0.0),
+            Vec4::from_vec3(r.c2().scaled(scale.z()), 0.0),
+            Vec4::from_vec3(translation, 1.0),
+        )
+    }
+    pub const fn mul_vec4(&self, v: Vec4<f32>) -> Vec4<f32> {
+        Vec4::new(
+            self.r0().dot(&v),
+            self.r1().dot(&v),
+            self.r2().dot(&v),
+            self.r3().dot(&v),
+        )
+    }
     pub const fn mul(&self, rhs: &Self) -> Mat4<f32> {
         let (r0, r1, r2, r3) = (self.r0(), self.r1(), self.r2(), self.r3());
 
@@ -159,6 +264,120 @@ impl Mat4<f32> {
     }
 }
 
+impl Mat4<f32> {
+    // 2x2 sub determinants of the top two rows (s) and the bottom two rows (c)
+    const fn sub_factors(&self) -> ([f32; 6], [f32; 6]) {
+        let (r0, r1, r2, r3) = (self.r0(), self.r1(), self.r2(), self.r3());
+
+        let s = [
+            r0.x() * r1.y() - r1.x() * r0.y(),
+            r0.x() * r1.z() - r1.x() * r0.z(),
+            r0.x() * r1.w() - r1.x() * r0.w(),
+            r0.y() * r1.z() - r1.y() * r0.z(),
+            r0.y() * r1.w() - r1.y() * r0.w(),
+            r0.z() * r1.w() - r1.z() * r0.w(),
+        ];
+        let c = [
+            r2.x() * r3.y() - r3.x() * r2.y(),
+            r2.x() * r3.z() - r3.x() * r2.z(),
+            r2.x() * r3.w() - r3.x() * r2.w(),
+            r2.y() * r3.z() - r3.y() * r2.z(),
+            r2.y() * r3.w() - r3.y() * r2.w(),
+            r2.z() * r3.w() - r3.z() * r2.w(),
+        ];
+
+        (s, c)
+    }
+    const fn determinant_from(s: &[f32; 6], c: &[f32; 6]) -> f32 {
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+    }
+    pub const fn determinant(&self) -> f32 {
+        let (s, c) = self.sub_factors();
+        Self::determinant_from(&s, &c)
+    }
+    const fn max_abs_entry(&self) -> f32 {
+        let mut max = 0.0f32;
+        let mut i = 0;
+        while i < 4 {
+            let c = &self.0[i];
+            max = max.max(c.x().abs()).max(c.y().abs());
+            max = max.max(c.z().abs()).max(c.w().abs());
+            i += 1;
+        }
+        max
+    }
+    // None when the matrix is singular or close to it. the determinant scales with the fourth
+    // power of the entries, so the tolerance does too and small matrices still invert
+    pub const fn inverse(&self) -> Option<Self> {
+        let (s, c) = self.sub_factors();
+        let det = Self::determinant_from(&s, &c);
+        let scale = self.max_abs_entry();
+        let scale = scale * scale;
+        if !det.is_finite() || det.abs() <= f32::EPSILON * scale * scale {
+            return None;
+        }
+        let d = 1.0 / det;
+
+        let (r0, r1, r2, r3) = (self.r0(), self.r1(), self.r2(), self.r3());
+
+        Some(Self::from_rows(
+            Vec4::new(
+                (r1.y() * c[5] - r1.z() * c[4] + r1.w() * c[3]) * d,
+                (-r0.y() * c[5] + r0.z() * c[4] - r0.w() * c[3]) * d,
+                (r3.y() * s[5] - r3.z() * s[4] + r3.w() * s[3]) * d,
+                (-r2.y() * s[5] + r2.z() * s[4] - r2.w() * s[3]) * d,
+            ),
+            Vec4::new(
+                (-r1.x() * c[5] + r1.z() * c[2] - r1.w() * c[1]) * d,
+                (r0.x() * c[5] - r0.z() * c[2] + r0.w() * c[1]) * d,
+                (-r3.x() * s[5] + r3.z() * s[2] - r3.w() * s[1]) * d,
+                (r2.x() * s[5] - r2.z() * s[2] + r2.w() * s[1]) * d,
+            ),
+            Vec4::new(
+                (r1.x() * c[4] - r1.y() * c[2] + r1.w() * c[0]) * d,
+                (-r0.x() * c[4] + r0.y() * c[2] - r0.w() * c[0]) * d,
+                (r3.x() * s[4] - r3.y() * s[2] + r3.w() * s[0]) * d,
+                (-r2.x() * s[4] + r2.y() * s[2] - r2.w() * s[0]) * d,
+            ),
+            Vec4::new(
+                (-r1.x() * c[3] + r1.y() * c[1] - r1.z() * c[0]) * d,
+                (r0.x() * c[3] - r0.y() * c[1] + r0.z() * c[0]) * d,
+                (-r3.x() * s[3] + r3.y() * s[1] - r3.z() * s[0]) * d,
+                (r2.x() * s[3] - r2.y() * s[1] + r2.z() * s[0]) * d,
+            ),
+        ))
+    }
+}
+
+// the const mul and mul_vec4 only exist for f32, these work for any Float
+impl<T: Float> std::ops::Mul<Vec4<T>> for Mat4<T> {
+    type Output = Vec4<T>;
+
+    fn mul(self, rhs: Vec4<T>) -> Vec4<T> {
+        let dot =
+            |r: Vec4<T>| r.x() * rhs.x() + r.y() * rhs.y() + r.z() * rhs.z() + r.w() * rhs.w();
+        Vec4::new(
+            dot(self.r0()),
+            dot(self.r1()),
+            dot(self.r2()),
+            dot(self.r3()),
+        )
+    }
+}
+
+impl<T: Float> std::ops::Mul for Mat4<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::from_cols(
+            self.clone() * rhs.c0(),
+            self.clone() * rhs.c1(),
+            self.clone() * rhs.c2(),
+            self * rhs.c3(),
+        )
+    }
+}
+
 impl<T: std::fmt::Display + Copy> std::fmt::Display for Mat4<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "[")?;
@@ -188,8 +407,170 @@ impl<T: PartialEq + Copy> PartialEq for Mat4<T> {
 #[cfg(test)]
 mod test {
     use crate::mat4::Mat4;
+    use crate::vec3::Vec3;
     use crate::vec4::Vec4;
 
+    fn transform_f64(m: &Mat4<f64>, v: Vec4<f64>) -> Vec4<f64> {
+        let dot = |r: Vec4<f64>| r.x() * v.x() + r.y() * v.y() + r.z() * v.z() + r.w() * v.w();
+        Vec4::new(dot(m.r0()), dot(m.r1()), dot(m.r2()), dot(m.r3()))
+    }
+
+    #[test]
+    fn perspective_f64() {
+        const EPS: f64 = 1e-12;
+        let (near, far) = (0.1, 1000.0);
+        let p = Mat4::<f64>::perspective(std::f64::consts::FRAC_PI_2, 2.0, near, far);
+
+        let n = transform_f64(&p, Vec4::new(0.0, 0.0, -near, 1.0));
+        assert!((n.z() / n.w()).abs() < EPS);
+
+        let f = transform_f64(&p, Vec4::new(0.0, 0.0, -far, 1.0));
+        assert!((f.z() / f.w() - 1.0).abs() < EPS);
+
+        // the top edge of the frustum maps to y = -1, the right edge to x = 1
+        let top = transform_f64(&p, Vec4::new(0.0, 1.0, -1.0, 1.0));
+        assert!((top.y() / top.w() + 1.0).abs() < EPS);
+        let right = transform_f64(&p, Vec4::new(2.0, 0.0, -1.0, 1.0));
+        assert!((right.x() / right.w() - 1.0).abs() < EPS);
+    }
+
+    #[test]
+    fn from_trs() {
+        use crate::quat::Quat;
+
+        let t = Vec3::new(1.0, -2.0, 3.0);
+        let r = Quat::unit_from_angle_axis(60f32.to_radians(), Vec3::new(1.0, 1.0, 0.0));
+        let s = Vec3::new(2.0, 0.5, 3.0);
+
+        let manual = Mat4::translation(t)
+            .mul(&r.as_mat4())
+            .mul(&Mat4::scaling(Vec4::from_vec3(s, 1.0)));
+        let trs = Mat4::from_trs(t, r, s);
+
+        for (a, b) in trs.into_2d_arr().iter().zip(manual.into_2d_arr().iter()) {
+            for (a, b) in a.iter().zip(b) {
+                assert!((a - b).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn perspective_f32() {
+        let (near, far) = (0.5, 200.0);
+        let p = Mat4::perspective(60f32.to_radians(), 16.0 / 9.0, near, far);
+
+        let transform = |v: Vec4<f32>| p.mul_vec4(v);
+
+        let n = transform(Vec4::new(0.3, -0.2, -near, 1.0));
+        assert!((n.z() / n.w()).abs() < 1e-6);
+
+        let f = transform(Vec4::new(10.0, 5.0, -far, 1.0));
+        assert!((f.z() / f.w() - 1.0).abs() < 1e-6);
+
+        // halfway in view space is much closer to 1 than 0.5
+        let mid = transform(Vec4::new(0.0, 0.0, -(near + far) / 2.0, 1.0));
+        assert!(mid.z() / mid.w() > 0.99);
+    }
+
+    #[test]
+    fn look_at_f64() {
+        let v = Mat4::<f64>::look_at(
+            Vec3::new(0.0, 0.0, 5.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+
+        let origin = transform_f64(&v, Vec4::new(0.0, 0.0, 0.0, 1.0));
+        assert_eq!(origin, Vec4::new(0.0, 0.0, -5.0, 1.0));
+
+        let up = transform_f64(&v, Vec4::new(0.0, 1.0, 5.0, 1.0));
+        assert_eq!(up, Vec4::new(0.0, 1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn rotation() {
+        use crate::quat::Quat;
+
+        let axis = Vec3::new(1.0, 2.0, -0.5);
+        let m = Mat4::<f32>::rotation(axis, 0.8).into_2d_arr();
+        let q = Quat::unit_from_angle_axis(0.8, axis)
+            .into_mat4()
+            .into_2d_arr();
+        for (a, b) in m.iter().flatten().zip(q.iter().flatten()) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn transforms_f64() {
+        const EPS: f64 = 1e-12;
+
+        // far enough from the origin that f32 can't hold the offsets below
+        let eye = Vec3::new(1.0e9, 0.0, 5.0);
+        let target = Vec3::new(1.0e9, 0.0, 0.0);
+        let v = Mat4::<f64>::look_at(eye, target, Vec3::new(0.0, 1.0, 0.0));
+        let model = Mat4::translation(Vec3::new(1.0e9 + 1.0e-3, 0.0, 0.0))
+            * Mat4::rotation(Vec3::new(0.0, 0.0, 1.0), std::f64::consts::FRAC_PI_2);
+
+        // the rotation turns +x into +y, then the model is moved next to the camera target
+        let p = (v * model) * Vec4::new(1.0, 0.0, 0.0, 1.0);
+        assert!((p.x() - 1.0e-3).abs() < 1e-6);
+        assert!((p.y() - 1.0).abs() < EPS);
+        assert!((p.z() + 5.0).abs() < EPS);
+        assert_eq!(p.w(), 1.0);
+    }
+
+    #[test]
+    fn inverse() {
+        use crate::quat::Quat;
+        use crate::traits::Identity;
+
+        let s = Mat4::scaling(Vec4::new(2.0, 3.0, 4.0, 1.0));
+        assert_eq!(s.determinant(), 24.0);
+
+        let r = Quat::unit_from_angle_axis(0.7, Vec3::new(0.0, 0.6, 0.8)).into_mat4();
+        let m = Mat4::translation(Vec3::new(1.0, -2.0, 3.0)).mul(&r).mul(&s);
+
+        let product = m.mul(&m.inverse().unwrap()).into_2d_arr();
+        let identity = Mat4::<f32>::IDENTITY.into_2d_arr();
+        for (a, b) in product.iter().flatten().zip(identity.iter().flatten()) {
+            assert!((a - b).abs() < 1e-5, "{:?}", product);
+        }
+
+        let singular = Mat4::scaling(Vec4::new(1.0, 0.0, 1.0, 1.0));
+        assert_eq!(singular.determinant(), 0.0);
+        assert!(singular.inverse().is_none());
+
+        let nearly_singular = Mat4::scaling(Vec4::new(1.0, 1e-8, 1.0, 1.0));
+        assert!(nearly_singular.determinant() != 0.0);
+        assert!(nearly_singular.inverse().is_none());
+
+        // det is 1e-12 but the matrix is perfectly well conditioned
+        let small = r.mul(&Mat4::scaling(Vec4::new(0.001, 0.001, 0.001, 0.001)));
+        let inverse = small.inverse().unwrap();
+        let product = small.mul(&inverse).into_2d_arr();
+        assert!((inverse.mul(&r).into_2d_arr()[0][0] - 1000.0).abs() < 1e-1);
+        for (a, b) in product.iter().flatten().zip(identity.iter().flatten()) {
+            assert!((a - b).abs() < 1e-5, "{:?}", product);
+        }
+    }
+
+    #[test]
+    fn multiplication_vec4() {
+        use crate::traits::Identity;
+
+        let point = Vec4::new(1.0, 2.0, 3.0, 1.0);
+        assert_eq!(Mat4::IDENTITY.mul_vec4(point), point);
+
+        let t = Mat4::translation(Vec3::new(10.0, -4.0, 0.5));
+        assert_eq!(t.mul_vec4(point), Vec4::new(11.0, -2.0, 3.5, 1.0));
+
+        // directions have w = 0 and ignore translation
+        let direction = Vec4::new(1.0, 2.0, 3.0, 0.0);
+        assert_eq!(t.clone() * direction, direction);
+        assert_eq!(t * point, Vec4::new(11.0, -2.0, 3.5, 1.0));
+    }
+
     #[test]
     fn multiplication_scaling() {
         let s = Mat4::scaling(Vec4::new(2.0, 3.0, 4.0, 5.0));
@@ -190,6 +190,45 @@ impl Quat {
     pub const fn mul_assign(&mut self, rhs: Self) {
         *self = self.mul(rhs);
     }
+    #[inline]
+    pub const fn dot(&self, other: &Self) -> f32 {
+        self.w() * other.w() + self.v.dot(other.v)
+    }
+    // the smallest rotation between two unit quaternions in radians. q and -q give 0.
+    pub fn angle_between(&self, other: &Self) -> f32 {
+        2.0 * self.dot(other).abs().min(1.0).acos()
+    }
+    // cheaper than slerp but the angular speed isn't constant. takes the shortest path
+    pub fn nlerp(&self, other: Self, t: f32) -> Self {
+        let end = if self.dot(&other) < 0.0 {
+            other.scaled(-1.0)
+        } else {
+            other
+        };
+
+        self.scaled(1.0 - t).added(&end.scaled(t)).normalized()
+    }
+    // takes the shortest path between two unit quaternions
+    pub fn slerp(&self, other: Self, t: f32) -> Self {
+        let mut cos = self.dot(&other);
+        let mut end = other;
+        if cos < 0.0 {
+            end = end.scaled(-1.0);
+            cos = -cos;
+        }
+
+        // sin(theta) approaches 0, so fall back to a normalized linear interpolation
+        if cos > 0.9995 {
+            return self.nlerp(end, t);
+        }
+
+        let theta = cos.acos();
+        let sin = theta.sin();
+        let a = ((1.0 - t) * theta).sin() / sin;
+        let b = (t * theta).sin() / sin;
+
+        self.scaled(a).added(&end.scaled(b))
+    }
 }
 
 impl std::fmt::Display for Quat {
@@ -251,10 +290,65 @@ mod tests {
         assert_eq!(q.rotate_vec(p), Vec3::new(0.0, 1.0, 0.0));
     }
 
+    #[test]
+    fn slerp() {
+        let axis = Vec3::new(0.0, 1.0, 0.0);
+        let a = Quat::unit_from_angle_axis(0.0, axis);
+        let b = Quat::unit_from_angle_axis(90f32.to_radians(), axis);
+
+        assert_eq!(a.slerp(b, 0.0), a);
+
+        let halfway = a.slerp(b, 0.5);
+        let expected = Quat::unit_from_angle_axis(45f32.to_radians(), axis);
+        assert!((halfway.w() - expected.w()).abs() < 1e-6);
+        assert!((halfway.y() - expected.y()).abs() < 1e-6);
+
+        let end = a.slerp(b, 1.0);
+        assert!((end.w() - b.w()).abs() < 1e-6);
+        assert!((end.y() - b.y()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn nlerp() {
+        let axis = Vec3::new(0.0, 1.0, 0.0);
+        let a = Quat::unit_from_angle_axis(0.0, axis);
+        let b = Quat::unit_from_angle_axis(90f32.to_radians(), axis);
+
+        assert_eq!(a.nlerp(b, 0.0), a);
+
+        // the halfway point of nlerp and slerp agree, only the speed differs
+        let halfway = a.nlerp(b, 0.5);
+        let expected = Quat::unit_from_angle_axis(45f32.to_radians(), axis);
+        assert!((halfway.w() - expected.w()).abs() < 1e-6);
+        assert!((halfway.y() - expected.y()).abs() < 1e-6);
+        assert!((halfway.length() - 1.0).abs() < 1e-6);
+
+        let end = a.nlerp(b, 1.0);
+        assert!((end.w() - b.w()).abs() < 1e-6);
+        assert!((end.y() - b.y()).abs() < 1e-6);
+
+        // -b is the same rotation, so the result must not swing the long way around
+        let flipped = a.nlerp(b.scaled(-1.0), 0.5);
+        assert!(flipped.angle_between(&expected) < 1e-3);
+    }
+
     #[test]
     fn conversion_to_matrix() {
         // let q = Quaternion::unit_from_angle_axis(0.5, Vec3::new(1.0, 0.0, 0.0));
 
         // assert_eq!(q.into_mat4(), m);
     }
+
+    #[test]
+    fn angle_between() {
+        let axis = Vec3::new(0.0, 1.0, 0.0);
+        let a = Quat::unit_from_angle_axis(0.3, axis);
+        let b = Quat::unit_from_angle_axis(0.3 + std::f32::consts::FRAC_PI_2, axis);
+
+        assert!((a.dot(&a) - 1.0).abs() < 1e-6);
+        assert!(a.angle_between(&a).abs() < 1e-3);
+        assert!(a.angle_between(&a.scaled(-1.0)).abs() < 1e-3);
+        assert!((a.angle_between(&b) - std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+        assert!((b.angle_between(&a) - std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+    }
 }
@@ -0,0 +1,35 @@
+use crate::Vec3;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Ray {
+    pub origin: Vec3<f32>,
+    // always normalized
+    pub direction: Vec3<f32>,
+}
+
+impl Ray {
+    #[inline]
+    pub fn new(origin: Vec3<f32>, direction: Vec3<f32>) -> Self {
+        Self {
+            origin,
+            direction: direction.normalized(),
+        }
+    }
+    #[inline]
+    pub const fn at(&self, t: f32) -> Vec3<f32> {
+        self.origin.add(self.direction.scaled(t))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Ray, Vec3};
+
+    #[test]
+    fn at1() {
+        let r = Ray::new(Vec3::new(1.0, 2.0, 3.0), Vec3::new(0.0, 0.0, 2.0));
+
+        assert_eq!(r.direction, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(r.at(4.0), Vec3::new(1.0, 2.0, 7.0));
+    }
+}
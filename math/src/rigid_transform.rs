@@ -1,13 +1,17 @@
 use crate::{Identity, Mat4, Quat, Vec3, Zero};
 
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct RigidTransform {
     pub position: Vec3<f32>,
     // TODO: orientation should be private
     pub orientation: Quat,
 }
 
+impl Identity for RigidTransform {
+    const IDENTITY: Self = Self::new(Vec3::ZERO, Quat::IDENTITY);
+}
+
 impl RigidTransform {
     #[inline]
     pub const fn new(position: Vec3<f32>, orientation: Quat) -> Self {
@@ -16,10 +20,34 @@ impl RigidTransform {
             orientation,
         }
     }
+    // negates the position and inverts the orientation separately. this is NOT the
+    // inverse transform, see inverse
     #[inline]
     pub const fn inv(&self) -> Self {
         Self::new(self.position.scaled(-1.0), self.orientation.inverse())
     }
+    pub const fn inverse(&self) -> Self {
+        let orientation = self.orientation.inverse();
+        let position = orientation.rotate_vec(self.position).scaled(-1.0);
+
+        Self::new(position, orientation)
+    }
+    // the transform that applies other first, then self
+    pub const fn compose(&self, other: &Self) -> Self {
+        Self::new(
+            self.transform_point(other.position),
+            self.orientation.mul(other.orientation),
+        )
+    }
+    #[inline]
+    pub const fn transform_point(&self, p: Vec3<f32>) -> Vec3<f32> {
+        self.orientation.rotate_vec(p).add(self.position)
+    }
+    // ignores the translation
+    #[inline]
+    pub const fn transform_vector(&self, v: Vec3<f32>) -> Vec3<f32> {
+        self.orientation.rotate_vec(v)
+    }
     #[inline]
     pub const fn translate_global(&mut self, offset: Vec3<f32>) {
         self.position.add_assign(offset);
@@ -51,7 +79,7 @@ impl RigidTransform {
         let t = self.get_translation_matrix();
         let r = self.get_rotation_matrix();
 
-        r.mul(&t)
+        t.mul(&r)
     }
 }
 
@@ -66,10 +94,7 @@ impl PartialEq for RigidTransform {
 
 impl Default for RigidTransform {
     fn default() -> Self {
-        Self {
-            position: Vec3::ZERO,
-            orientation: Quat::IDENTITY,
-        }
+        Self::IDENTITY
     }
 }
 
@@ -80,4 +105,59 @@ trait HasRigidTransform {
     fn position_and_orientation(&self) -> &RigidTransform;
 }
 
-// TODO: add tests for all of these methods and finalize their designs
+// TODO: add tests for the remaining methods and finalize their designs
+#[cfg(test)]
+mod tests {
+    use super::RigidTransform;
+    use crate::{Identity, Quat, Vec3, Vec4};
+
+    fn assert_close(a: Vec3<f32>, b: Vec3<f32>) {
+        assert!(a.sub(b).length() < 1e-5, "{a} != {b}");
+    }
+
+    fn transform() -> RigidTransform {
+        RigidTransform::new(
+            Vec3::new(1.0, -2.0, 3.0),
+            Quat::unit_from_angle_axis(60f32.to_radians(), Vec3::new(1.0, 1.0, 0.0)),
+        )
+    }
+
+    #[test]
+    fn matrix_matches_transform_point() {
+        let t = transform();
+        let m = t.as_mat4();
+
+        for p in [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(-4.0, 5.0, 0.5),
+        ] {
+            let mp = m.mul_vec4(Vec4::from_vec3(p, 1.0));
+            assert_close(Vec3::new(mp.x(), mp.y(), mp.z()), t.transform_point(p));
+
+            let mv = m.mul_vec4(Vec4::from_vec3(p, 0.0));
+            assert_close(Vec3::new(mv.x(), mv.y(), mv.z()), t.transform_vector(p));
+        }
+    }
+
+    #[test]
+    fn compose_and_inverse() {
+        let a = transform();
+        let b = RigidTransform::new(
+            Vec3::new(0.0, 4.0, 0.0),
+            Quat::unit_from_angle_axis(90f32.to_radians(), Vec3::new(0.0, 0.0, 1.0)),
+        );
+        let p = Vec3::new(2.0, 1.0, -1.0);
+
+        assert_close(
+            a.compose(&b).transform_point(p),
+            a.transform_point(b.transform_point(p)),
+        );
+
+        let round_trip = a.compose(&a.inverse());
+        assert_close(round_trip.transform_point(p), p);
+        assert_close(a.inverse().transform_point(a.transform_point(p)), p);
+
+        assert_eq!(RigidTransform::IDENTITY.transform_point(p), p);
+    }
+}
@@ -0,0 +1,85 @@
+use crate::mat2::Mat2;
+use crate::mat3::{Mat3, Mat3Std140};
+use crate::mat4::Mat4;
+use crate::vec2::Vec2;
+use crate::vec3::Vec3;
+use crate::vec4::Vec4;
+
+// the size and base alignment a type has inside a std140 uniform block.
+// the consts can be used in const asserts against reflected offsets
+pub trait Std140 {
+    const STD140_SIZE: usize;
+    const STD140_ALIGN: usize;
+
+    fn std140_size() -> usize {
+        Self::STD140_SIZE
+    }
+    fn std140_align() -> usize {
+        Self::STD140_ALIGN
+    }
+}
+
+// rounds offset up to the next multiple of align
+pub const fn std140_align_up(offset: usize, align: usize) -> usize {
+    offset.div_ceil(align) * align
+}
+
+macro_rules! impl_std140 {
+    ($t:ty, $size:expr, $align:expr) => {
+        impl Std140 for $t {
+            const STD140_SIZE: usize = $size;
+            const STD140_ALIGN: usize = $align;
+        }
+    };
+}
+
+impl_std140!(f32, 4, 4);
+impl_std140!(i32, 4, 4);
+impl_std140!(u32, 4, 4);
+impl_std140!(Vec2<f32>, 8, 8);
+// a vec3 is 12 bytes but aligns like a vec4, so a following scalar can fill the gap
+impl_std140!(Vec3<f32>, 12, 16);
+impl_std140!(Vec4<f32>, 16, 16);
+// matrices are arrays of column vectors, and array strides round up to 16
+impl_std140!(Mat2<f32>, 32, 16);
+impl_std140!(Mat3<f32>, 48, 16);
+impl_std140!(Mat3Std140<f32>, 48, 16);
+impl_std140!(Mat4<f32>, 64, 16);
+
+// every element of an array is padded to a multiple of 16
+impl<T: Std140, const N: usize> Std140 for [T; N] {
+    const STD140_SIZE: usize = std140_align_up(T::STD140_SIZE, 16) * N;
+    const STD140_ALIGN: usize = std140_align_up(T::STD140_ALIGN, 16);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn std140_rules() {
+        assert_eq!(Vec3::<f32>::std140_align(), 16);
+        assert_eq!(Vec3::<f32>::std140_size(), 12);
+        assert_eq!(Mat4::<f32>::std140_size(), 64);
+        assert_eq!(Mat3::<f32>::std140_size(), 48);
+        assert_eq!(
+            Mat3Std140::<f32>::STD140_SIZE,
+            std::mem::size_of::<Mat3Std140<f32>>()
+        );
+
+        // float[4] has a stride of 16, not 4
+        assert_eq!(<[f32; 4]>::std140_size(), 64);
+        assert_eq!(<[f32; 4]>::std140_align(), 16);
+
+        // struct { vec3 a; float b; vec2 c; }: b packs into a's padding, c aligns to 16
+        let b = std140_align_up(Vec3::<f32>::STD140_SIZE, f32::STD140_ALIGN);
+        let c = std140_align_up(b + f32::STD140_SIZE, Vec2::<f32>::STD140_ALIGN);
+        assert_eq!((b, c), (12, 16));
+
+        // struct { float a; mat4 m; }
+        assert_eq!(
+            std140_align_up(f32::STD140_SIZE, Mat4::<f32>::STD140_ALIGN),
+            16
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
 pub trait Zero {
     const ZERO: Self;
 }
@@ -25,3 +27,62 @@ impl One for f32 {
 impl One for f64 {
     const ONE: f64 = 1.0;
 }
+
+// generic counterparts of the inherent vector methods, which stay const
+pub trait Dot {
+    type Output;
+    fn dot(self, rhs: Self) -> Self::Output;
+}
+
+// only Vec3 implements this. a Vec4 cross would have to drop w, which silently
+// breaks for points (w = 1), so callers convert to Vec3 themselves
+pub trait Cross {
+    fn cross(self, rhs: Self) -> Self;
+}
+
+pub trait Normalize {
+    fn normalized(self) -> Self;
+}
+
+pub trait Float:
+    Copy
+    + PartialOrd
+    + Zero
+    + One
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    fn sqrt(self) -> Self;
+    fn tan(self) -> Self;
+    fn sin_cos(self) -> (Self, Self);
+    fn is_finite(self) -> bool;
+}
+
+macro_rules! impl_float {
+    ($t:ty) => {
+        impl Float for $t {
+            #[inline]
+            fn sqrt(self) -> Self {
+                <$t>::sqrt(self)
+            }
+            #[inline]
+            fn tan(self) -> Self {
+                <$t>::tan(self)
+            }
+            #[inline]
+            fn sin_cos(self) -> (Self, Self) {
+                <$t>::sin_cos(self)
+            }
+            #[inline]
+            fn is_finite(self) -> bool {
+                <$t>::is_finite(self)
+            }
+        }
+    };
+}
+
+impl_float!(f32);
+impl_float!(f64);
@@ -1,11 +1,12 @@
-use crate::traits::Zero;
+use crate::debug::Component;
+use crate::traits::{Dot, Normalize, Zero};
 
 use crate::vec3::Vec3;
 use crate::vec4::Vec4;
 
 #[allow(dead_code)]
 #[repr(transparent)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy)]
 pub struct Vec2<T>(pub(crate) [T; 2]);
 
 impl<T> Vec2<T> {
@@ -105,6 +106,65 @@ impl Vec2<f32> {
     pub const fn dot(&self, other: Self) -> f32 {
         self.x() * other.x() + self.y() * other.y()
     }
+    // t is not clamped, so values outside [0, 1] extrapolate
+    #[inline]
+    pub const fn lerp(&self, other: Self, t: f32) -> Self {
+        self.add(other.sub(*self).scaled(t))
+    }
+    // component wise. panics if a component of min is greater than max, like f32::clamp
+    #[inline]
+    pub const fn clamp(&self, min: Self, max: Self) -> Self {
+        Self::new(
+            self.x().clamp(min.x(), max.x()),
+            self.y().clamp(min.y(), max.y()),
+        )
+    }
+    // the z component of the 3d cross product. Positive when other is counter clockwise of self
+    #[inline]
+    pub const fn perp_dot(&self, other: Self) -> f32 {
+        self.x() * other.y() - self.y() * other.x()
+    }
+    // rotated 90 degrees counter clockwise
+    #[inline]
+    pub const fn perp(&self) -> Self {
+        Self::new(-self.y(), self.x())
+    }
+    #[inline]
+    pub fn rotated(&self, angle_radians: f32) -> Self {
+        let (sin, cos) = angle_radians.sin_cos();
+        Self::new(
+            self.x() * cos - self.y() * sin,
+            self.x() * sin + self.y() * cos,
+        )
+    }
+    // the counter clockwise angle from the x axis in (-pi, pi]
+    #[inline]
+    pub fn angle(&self) -> f32 {
+        self.y().atan2(self.x())
+    }
+}
+
+impl Dot for Vec2<f32> {
+    type Output = f32;
+
+    fn dot(self, rhs: Self) -> f32 {
+        Self::dot(&self, rhs)
+    }
+}
+
+impl Normalize for Vec2<f32> {
+    fn normalized(self) -> Self {
+        Self::normalized(self)
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Vec2<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Vec2")
+            .field("x", &Component(&self.0[0]))
+            .field("y", &Component(&self.0[1]))
+            .finish()
+    }
 }
 
 impl<T: std::fmt::Display + Copy> std::fmt::Display for Vec2<T> {
@@ -175,4 +235,42 @@ mod tests {
 
         assert_eq!(a.normalized(), b);
     }
+
+    #[test]
+    fn lerp1() {
+        let a = Vec2::<f32>::new(0.0, 2.0);
+        let b = Vec2::<f32>::new(10.0, -4.0);
+
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 0.5), Vec2::new(5.0, -1.0));
+        assert_eq!(a.lerp(b, 1.0), b);
+    }
+    #[test]
+    fn clamp1() {
+        let v = Vec2::<f32>::new(-5.0, 7.0);
+        let min = Vec2::<f32>::new(-1.0, 0.0);
+        let max = Vec2::<f32>::new(1.0, 2.0);
+
+        assert_eq!(v.clamp(min, max), Vec2::new(-1.0, 2.0));
+        assert_eq!(
+            Vec2::<f32>::new(0.5, 1.5).clamp(min, max),
+            Vec2::new(0.5, 1.5)
+        );
+    }
+    #[test]
+    fn rotate1() {
+        let v = Vec2::<f32>::new(1.0, 0.0);
+        let r = v.rotated(std::f32::consts::FRAC_PI_2);
+
+        assert!((r.x() - 0.0).abs() < 1e-6);
+        assert!((r.y() - 1.0).abs() < 1e-6);
+        assert_eq!(v.perp(), Vec2::new(0.0, 1.0));
+        assert_eq!(v.perp_dot(v.perp()), 1.0);
+
+        assert_eq!(v.angle(), 0.0);
+        assert!((r.angle() - std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+        assert!(
+            (Vec2::<f32>::new(-1.0, -1.0).angle() + 3.0 * std::f32::consts::FRAC_PI_4).abs() < 1e-6
+        );
+    }
 }
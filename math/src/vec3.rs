@@ -1,9 +1,10 @@
-use crate::traits::Zero;
+use crate::debug::Component;
+use crate::traits::{Cross, Dot, Normalize, Zero};
 use crate::vec4::Vec4;
 
 #[allow(dead_code)]
 #[repr(transparent)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy)]
 pub struct Vec3<T>(pub(crate) [T; 3]);
 
 impl<T> Vec3<T> {
@@ -140,6 +141,114 @@ impl Vec3<f32> {
             self.x() * other.y() - self.y() * other.x(),
         )
     }
+    // t is not clamped, so values outside [0, 1] extrapolate
+    #[inline]
+    pub const fn lerp(&self, other: Self, t: f32) -> Self {
+        self.add(other.sub(*self).scaled(t))
+    }
+    // component wise. panics if a component of min is greater than max, like f32::clamp
+    #[inline]
+    pub const fn clamp(&self, min: Self, max: Self) -> Self {
+        Self::new(
+            self.x().clamp(min.x(), max.x()),
+            self.y().clamp(min.y(), max.y()),
+            self.z().clamp(min.z(), max.z()),
+        )
+    }
+    // self is expected to be normalized.
+    #[inline]
+    pub fn any_orthonormal(&self) -> Self {
+        self.orthonormal_basis().0
+    }
+    // Returns two unit vectors that, together with self, form an orthonormal basis.
+    // self is expected to be normalized. Branchless method from Duff et al. (2017),
+    // "Building an Orthonormal Basis, Revisited".
+    pub fn orthonormal_basis(&self) -> (Self, Self) {
+        let (x, y, z) = (self.x(), self.y(), self.z());
+
+        let sign = 1.0f32.copysign(z);
+        let a = -1.0 / (sign + z);
+        let b = x * y * a;
+
+        (
+            Self::new(1.0 + sign * x * x * a, sign * b, -sign * x),
+            Self::new(b, sign + y * y * a, -y),
+        )
+    }
+}
+
+impl std::ops::Add for Vec3<f32> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::add(&self, rhs)
+    }
+}
+
+impl std::ops::AddAssign for Vec3<f32> {
+    fn add_assign(&mut self, rhs: Self) {
+        Self::add_assign(self, rhs)
+    }
+}
+
+impl std::ops::Sub for Vec3<f32> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::sub(&self, rhs)
+    }
+}
+
+impl std::ops::SubAssign for Vec3<f32> {
+    fn sub_assign(&mut self, rhs: Self) {
+        Self::sub_assign(self, rhs)
+    }
+}
+
+impl std::ops::Mul<f32> for Vec3<f32> {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        self.scaled(rhs)
+    }
+}
+
+impl std::ops::Neg for Vec3<f32> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.scaled(-1.0)
+    }
+}
+
+impl Dot for Vec3<f32> {
+    type Output = f32;
+
+    fn dot(self, rhs: Self) -> f32 {
+        Self::dot(&self, rhs)
+    }
+}
+
+impl Cross for Vec3<f32> {
+    fn cross(self, rhs: Self) -> Self {
+        Self::cross(&self, rhs)
+    }
+}
+
+impl Normalize for Vec3<f32> {
+    fn normalized(self) -> Self {
+        Self::normalized(self)
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Vec3<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Vec3")
+            .field("x", &Component(&self.0[0]))
+            .field("y", &Component(&self.0[1]))
+            .field("z", &Component(&self.0[2]))
+            .finish()
+    }
 }
 
 impl<T: std::fmt::Display + Copy> std::fmt::Display for Vec3<T> {
@@ -183,6 +292,22 @@ mod tests {
         assert_eq!(c, a);
     }
     #[test]
+    fn operators() {
+        let a = Vec3::<f32>::new(1.0, 2.0, 3.0);
+        let b = Vec3::<f32>::new(10.0, 20.0, 30.0);
+
+        assert_eq!(a + b, Vec3::new(11.0, 22.0, 33.0));
+        assert_eq!(b - a, Vec3::new(9.0, 18.0, 27.0));
+        assert_eq!(a * 2.0, Vec3::new(2.0, 4.0, 6.0));
+        assert_eq!(-a, Vec3::new(-1.0, -2.0, -3.0));
+
+        let mut c = a;
+        c += b;
+        assert_eq!(c, a + b);
+        c -= b;
+        assert_eq!(c, a);
+    }
+    #[test]
     fn scale1() {
         let mut v = Vec3::<f32>::new(1.0, 17.0, 65.0);
         let s1 = 0.5;
@@ -228,10 +353,85 @@ mod tests {
         assert_eq!(a.cross(b).cross(c), d);
     }
     #[test]
+    fn generic_traits() {
+        use crate::traits::{Cross, Dot, Normalize};
+
+        fn reject<
+            V: Dot<Output = f32>
+                + Normalize
+                + Copy
+                + std::ops::Sub<Output = V>
+                + std::ops::Mul<f32, Output = V>,
+        >(
+            v: V,
+            axis: V,
+        ) -> V {
+            let axis = axis.normalized();
+            v - axis * v.dot(axis)
+        }
+        fn normal<V: Cross + Normalize + std::ops::Sub<Output = V> + Copy>(a: V, b: V, c: V) -> V {
+            (b - a).cross(c - a).normalized()
+        }
+
+        let v = Vec3::<f32>::new(3.0, 4.0, 5.0);
+        assert_eq!(
+            reject(v, Vec3::new(0.0, 2.0, 0.0)),
+            Vec3::new(3.0, 0.0, 5.0)
+        );
+
+        let n = normal(
+            Vec3::<f32>::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        assert_eq!(n, Vec3::new(0.0, 0.0, 1.0));
+    }
+    #[test]
     fn normalize1() {
         let a = Vec3::<f32>::new(44.0, 55.0, 66.0);
         let b = Vec3::<f32>::new(0.45584232, 0.5698029, 0.6837635);
 
         assert_eq!(a.normalized(), b);
     }
+    #[test]
+    fn lerp1() {
+        let a = Vec3::<f32>::new(0.0, 2.0, -4.0);
+        let b = Vec3::<f32>::new(10.0, 4.0, 4.0);
+
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 0.5), Vec3::new(5.0, 3.0, 0.0));
+        assert_eq!(a.lerp(b, 1.0), b);
+    }
+    #[test]
+    fn clamp1() {
+        let v = Vec3::<f32>::new(-5.0, 0.5, 7.0);
+        let min = Vec3::<f32>::new(0.0, 0.0, 0.0);
+        let max = Vec3::<f32>::new(1.0, 1.0, 1.0);
+
+        assert_eq!(v.clamp(min, max), Vec3::new(0.0, 0.5, 1.0));
+    }
+    #[test]
+    fn orthonormal_basis1() {
+        const EPS: f32 = 0.0001;
+
+        let directions = [
+            Vec3::<f32>::new(0.0, 0.0, 1.0),
+            Vec3::<f32>::new(0.0, 0.0, -1.0),
+            Vec3::<f32>::new(1.0, 0.0, 0.0),
+            Vec3::<f32>::new(0.0, -1.0, 0.0),
+            Vec3::<f32>::new(1.0, 5.0, 9.0).normalized(),
+            Vec3::<f32>::new(-3.0, 0.5, -0.0001).normalized(),
+        ];
+
+        for n in directions {
+            let (b1, b2) = n.orthonormal_basis();
+
+            assert!((b1.length() - 1.0).abs() < EPS);
+            assert!((b2.length() - 1.0).abs() < EPS);
+            assert!(n.dot(b1).abs() < EPS);
+            assert!(n.dot(b2).abs() < EPS);
+            assert!(b1.dot(b2).abs() < EPS);
+            assert_eq!(n.any_orthonormal(), b1);
+        }
+    }
 }
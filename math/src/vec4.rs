@@ -1,8 +1,9 @@
-use crate::traits::Zero;
+use crate::debug::Component;
+use crate::traits::{Dot, Normalize, Zero};
 
 #[allow(dead_code)]
 #[repr(transparent)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy)]
 pub struct Vec4<T>(pub(crate) [T; 4]);
 
 impl<T> Vec4<T> {
@@ -157,6 +158,90 @@ impl Vec4<f32> {
     pub const fn dot(&self, other: &Self) -> f32 {
         self.x() * other.x() + self.y() * other.y() + self.z() * other.z() + self.w() * other.w()
     }
+    // t is not clamped, so values outside [0, 1] extrapolate
+    #[inline]
+    pub const fn lerp(&self, other: Self, t: f32) -> Self {
+        self.add(other.sub(*self).scaled(t))
+    }
+    // component wise. panics if a component of min is greater than max, like f32::clamp
+    #[inline]
+    pub const fn clamp(&self, min: Self, max: Self) -> Self {
+        Self::new(
+            self.x().clamp(min.x(), max.x()),
+            self.y().clamp(min.y(), max.y()),
+            self.z().clamp(min.z(), max.z()),
+            self.w().clamp(min.w(), max.w()),
+        )
+    }
+}
+
+impl std::ops::Add for Vec4<f32> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::add(&self, rhs)
+    }
+}
+
+impl std::ops::AddAssign for Vec4<f32> {
+    fn add_assign(&mut self, rhs: Self) {
+        Self::add_assign(self, rhs)
+    }
+}
+
+impl std::ops::Sub for Vec4<f32> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::sub(&self, rhs)
+    }
+}
+
+impl std::ops::SubAssign for Vec4<f32> {
+    fn sub_assign(&mut self, rhs: Self) {
+        Self::sub_assign(self, rhs)
+    }
+}
+
+impl std::ops::Mul<f32> for Vec4<f32> {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        self.scaled(rhs)
+    }
+}
+
+impl std::ops::Neg for Vec4<f32> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.scaled(-1.0)
+    }
+}
+
+impl Dot for Vec4<f32> {
+    type Output = f32;
+
+    fn dot(self, rhs: Self) -> f32 {
+        Self::dot(&self, &rhs)
+    }
+}
+
+impl Normalize for Vec4<f32> {
+    fn normalized(self) -> Self {
+        Self::normalized(self)
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Vec4<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Vec4")
+            .field("x", &Component(&self.0[0]))
+            .field("y", &Component(&self.0[1]))
+            .field("z", &Component(&self.0[2]))
+            .field("w", &Component(&self.0[3]))
+            .finish()
+    }
 }
 
 impl<T: std::fmt::Display + Copy> std::fmt::Display for Vec4<T> {
@@ -202,6 +287,22 @@ mod tests {
         assert_eq!(a, c);
     }
 
+    #[test]
+    fn operators() {
+        let a = Vec4::<f32>::new(1.0, 2.0, 3.0, 4.0);
+        let b = Vec4::<f32>::new(10.0, 20.0, 30.0, 40.0);
+
+        assert_eq!(a + b, Vec4::new(11.0, 22.0, 33.0, 44.0));
+        assert_eq!(b - a, Vec4::new(9.0, 18.0, 27.0, 36.0));
+        assert_eq!(a * 2.0, Vec4::new(2.0, 4.0, 6.0, 8.0));
+        assert_eq!(-a, Vec4::new(-1.0, -2.0, -3.0, -4.0));
+
+        let mut c = a;
+        c += b;
+        assert_eq!(c, a + b);
+        c -= b;
+        assert_eq!(c, a);
+    }
     #[test]
     fn sub1() {
         let a = Vec4::<f32>::new(1.0, 5.0, 9.0, 17.0);
@@ -245,4 +346,46 @@ mod tests {
 
         assert_eq!(a.normalized(), b);
     }
+    #[test]
+    fn lerp1() {
+        let a = Vec4::<f32>::new(0.0, 2.0, -4.0, 1.0);
+        let b = Vec4::<f32>::new(10.0, 4.0, 4.0, 1.0);
+
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 0.5), Vec4::new(5.0, 3.0, 0.0, 1.0));
+        assert_eq!(a.lerp(b, 1.0), b);
+    }
+    #[test]
+    fn clamp1() {
+        let v = Vec4::<f32>::new(-5.0, 0.5, 7.0, 1.0);
+        let min = Vec4::<f32>::new(0.0, 0.0, 0.0, 0.0);
+        let max = Vec4::<f32>::new(1.0, 1.0, 1.0, 1.0);
+
+        assert_eq!(v.clamp(min, max), Vec4::new(0.0, 0.5, 1.0, 1.0));
+    }
+    #[test]
+    fn debug_non_finite() {
+        let v = Vec4::<f32>::new(1.0, f32::NAN, 3.0, f32::INFINITY);
+
+        assert_eq!(
+            format!("{:?}", v),
+            "Vec4 { x: 1.0, y: NaN (non-finite), z: 3.0, w: inf (non-finite) }"
+        );
+
+        // non float components still get Debug, without the marking
+        let v = Vec4::<i32>::new(1, -2, 3, 4);
+        assert_eq!(format!("{:?}", v), "Vec4 { x: 1, y: -2, z: 3, w: 4 }");
+        let v = Vec4::<f64>::new(f64::NEG_INFINITY, -0.5, 0.0, 1e300);
+        assert_eq!(
+            format!("{:?}", v),
+            "Vec4 { x: -inf (non-finite), y: -0.5, z: 0.0, w: 1e300 }"
+        );
+
+        // the caller's precision reaches each component
+        let v = Vec4::<f32>::new(1.0, f32::NAN, 1.0 / 3.0, -2.5);
+        assert_eq!(
+            format!("{:.2?}", v),
+            "Vec4 { x: 1.00, y: NaN (non-finite), z: 0.33, w: -2.50 }"
+        );
+    }
 }
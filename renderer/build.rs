@@ -96,6 +96,8 @@ fn type_info_to_rust(type_info: &spirv::TypeInfo) -> String {
     }
 }
 
+const INSTANCE_INPUT_PREFIX: &str = "instance_";
+
 fn generate_struct_types(
     variable_types_path: &PathBuf,
     spv_modules: &[spirv::Module],
@@ -127,19 +129,44 @@ fn generate_struct_types(
             }
         }
 
-        let inputs = module.get_inputs();
+        let layout = module.vertex_layout().unwrap_or_else(|e| {
+            panic!(
+                "could not determine vertex layout of {}: {}",
+                module.name, e
+            )
+        });
+        // inputs named instance_* come from a per-instance buffer, so they get their own struct
+        let (instance_attributes, vertex_attributes): (Vec<_>, Vec<_>) = layout
+            .attributes
+            .iter()
+            .partition(|a| a.name.starts_with(INSTANCE_INPUT_PREFIX));
+
         writeln!(w, "#[repr(C)]")?;
         writeln!(w, "pub struct {}Vertex {{", module.name)?;
-        for info in inputs {
-            let ty_str = if let TypeInfo::Pointer { ptr_type } = info.type_info {
-                get_type_name(&ptr_type)
-            } else {
-                get_type_name(&info.type_info)
-            };
-
-            writeln!(w, "    pub {}: {},", info.name, ty_str)?;
+        for attribute in vertex_attributes.iter() {
+            writeln!(
+                w,
+                "    pub {}: {},",
+                attribute.name,
+                get_type_name(&attribute.type_info)
+            )?;
         }
         writeln!(w, "}}")?;
+
+        if !instance_attributes.is_empty() {
+            writeln!(w, "#[repr(C)]")?;
+            writeln!(w, "#[derive(Clone, Copy)]")?;
+            writeln!(w, "pub struct {}Instance {{", module.name)?;
+            for attribute in instance_attributes.iter() {
+                writeln!(
+                    w,
+                    "    pub {}: {},",
+                    attribute.name,
+                    get_type_name(&attribute.type_info)
+                )?;
+            }
+            writeln!(w, "}}")?;
+        }
     }
 
     for (_, type_info) in all_vars {
@@ -211,21 +238,255 @@ fn generate_entry_point_vars(
     let mut w = BufWriter::new(shader_paths_file);
 
     for m in modules {
-        let name = m
-            .get_entry_points()
-            .find_map(|s| match s.as_str() {
-                "main" => Some(s),
-                _ => None,
-            })
-            .expect("Could not find entry point \"main\" ");
+        let names: Vec<String> = m.get_entry_point_names().collect();
+        // "main" unless the module doesn't have one
+        let default = names
+            .iter()
+            .find(|s| s.as_str() == "main")
+            .or(names.first())
+            .unwrap_or_else(|| panic!("{} has no entry points", m.name));
 
         writeln!(
             w,
             "const ENTRY_POINT_NAME_{}: &str = \"{}\";",
             to_snake_caps(&m.name),
-            name
+            default
+        )?;
+        writeln!(
+            w,
+            "const ENTRY_POINTS_{}: &[&str] = &[{}];",
+            to_snake_caps(&m.name),
+            names
+                .iter()
+                .map(|s| format!("\"{}\"", s))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+    }
+
+    Ok(())
+}
+
+fn descriptor_type_name(uniform_type: spirv::UniformType) -> Option<&'static str> {
+    let name = match uniform_type {
+        spirv::UniformType::UniformBuffer => "UNIFORM_BUFFER",
+        spirv::UniformType::StorageBuffer => "STORAGE_BUFFER",
+        spirv::UniformType::Sampler => "SAMPLER",
+        spirv::UniformType::CombinedImageSampler => "COMBINED_IMAGE_SAMPLER",
+        spirv::UniformType::SampledImage => "SAMPLED_IMAGE",
+        spirv::UniformType::StorageImage => "STORAGE_IMAGE",
+        spirv::UniformType::InputAttachment => "INPUT_ATTACHMENT",
+        spirv::UniformType::Other => return None,
+    };
+
+    Some(name)
+}
+
+fn generate_uniform_bindings(
+    uniform_bindings_path: &PathBuf,
+    layout: &spirv::MergedLayout,
+) -> Result<(), io::Error> {
+    let uniform_bindings_file = File::create(uniform_bindings_path)?;
+    let mut w = BufWriter::new(uniform_bindings_file);
+
+    writeln!(w, "// the descriptor sets the shaders use")?;
+    writeln!(w, "const UNIFORM_SET_COUNT: u32 = {};", layout.set_count())?;
+    writeln!(
+        w,
+        "// every uniform in the shaders, sorted by set then binding"
+    )?;
+    writeln!(
+        w,
+        "const UNIFORM_BINDINGS: &[crate::bindings::UniformBinding] = &["
+    )?;
+    for merged in layout.bindings.iter() {
+        let b = &merged.info;
+        let descriptor_type = match descriptor_type_name(b.uniform_type) {
+            Some(name) => format!("Some(ash::vk::DescriptorType::{})", name),
+            None => String::from("None"),
+        };
+        writeln!(
+            w,
+            "crate::bindings::UniformBinding {{ name: \"{}\", set: {}, binding: {}, descriptor_count: {}, descriptor_type: {}, stage_flags: ash::vk::ShaderStageFlags::from_raw({:#x}) }},",
+            b.name, b.set, b.binding, b.descriptor_count, descriptor_type, merged.stage_flags
         )?;
     }
+    writeln!(w, "];")?;
+
+    Ok(())
+}
+
+// one vk::Format per location the attribute takes up, with the byte size of each
+fn vertex_formats(type_info: &TypeInfo) -> Vec<(String, u32)> {
+    let scalar = |t: &TypeInfo| match t {
+        TypeInfo::Float { width, .. } => (*width, "SFLOAT"),
+        TypeInfo::Int {
+            width,
+            signed: true,
+            ..
+        } => (*width, "SINT"),
+        TypeInfo::Int {
+            width,
+            signed: false,
+            ..
+        } => (*width, "UINT"),
+        _ => panic!("{:?} can't be a vertex input component", t),
+    };
+    let format = |component: &TypeInfo, count: u32| {
+        let (width, suffix) = scalar(component);
+        let channels: String = ["R", "G", "B", "A"][..count as usize]
+            .iter()
+            .map(|c| format!("{}{}", c, width))
+            .collect();
+        (format!("{}_{}", channels, suffix), width / 8 * count)
+    };
+
+    match type_info {
+        TypeInfo::Float { .. } | TypeInfo::Int { .. } => vec![format(type_info, 1)],
+        TypeInfo::Vec {
+            component_type,
+            component_count,
+            ..
+        } => vec![format(component_type, *component_count)],
+        TypeInfo::Mat {
+            col_type,
+            col_count,
+            ..
+        } => std::iter::repeat_n(vertex_formats(col_type), *col_count as usize)
+            .flatten()
+            .collect(),
+        TypeInfo::Array {
+            element_type,
+            element_count,
+        } => std::iter::repeat_n(vertex_formats(element_type), *element_count as usize)
+            .flatten()
+            .collect(),
+        _ => panic!("{:?} can't be a vertex input", type_info),
+    }
+}
+
+// the vertex input of every vertex stage, one entry per binding. the offsets match the
+// #[repr(C)] Vertex and Instance structs from generate_struct_types
+fn generate_vertex_layouts(
+    vertex_layouts_path: &PathBuf,
+    stages: &[(spirv::ShaderStage, &spirv::Module)],
+) -> Result<(), io::Error> {
+    let vertex_layouts_file = File::create(vertex_layouts_path)?;
+    let mut w = BufWriter::new(vertex_layouts_file);
+
+    for (_, module) in stages
+        .iter()
+        .filter(|(stage, _)| matches!(stage, spirv::ShaderStage::Vertex))
+    {
+        let layout = module.vertex_layout().unwrap_or_else(|e| {
+            panic!(
+                "could not determine vertex layout of {}: {}",
+                module.name, e
+            )
+        });
+        let (instance_attributes, vertex_attributes): (Vec<_>, Vec<_>) = layout
+            .attributes
+            .iter()
+            .partition(|a| a.name.starts_with(INSTANCE_INPUT_PREFIX));
+
+        writeln!(
+            w,
+            "const VERTEX_LAYOUT_{}: &[crate::vertex_layout::VertexBindingLayout] = &[",
+            to_snake_caps(&module.name)
+        )?;
+        // checked at compile time so the structs and the pipeline can't disagree
+        let mut offset_checks = Vec::<String>::new();
+        let bindings = [
+            (
+                "crate::VERTEX_BINDING",
+                "VERTEX",
+                "Vertex",
+                vertex_attributes,
+            ),
+            (
+                "crate::INSTANCE_BINDING",
+                "INSTANCE",
+                "Instance",
+                instance_attributes,
+            ),
+        ];
+        for (binding, input_rate, struct_suffix, attributes) in bindings {
+            if attributes.is_empty() {
+                continue;
+            }
+
+            let struct_name = format!("crate::{}{}", module.name, struct_suffix);
+            let mut stride = 0;
+            let mut attribute_layouts = String::new();
+            for attribute in attributes {
+                offset_checks.push(format!(
+                    "std::mem::offset_of!({}, {}) == {}",
+                    struct_name, attribute.name, stride
+                ));
+                for (i, (format, size)) in
+                    vertex_formats(&attribute.type_info).into_iter().enumerate()
+                {
+                    attribute_layouts.push_str(&format!(
+                        "crate::vertex_layout::VertexAttributeLayout {{ location: {}, format: ash::vk::Format::{}, offset: {} }},",
+                        attribute.location + i as u32,
+                        format,
+                        stride
+                    ));
+                    stride += size;
+                }
+            }
+            writeln!(
+                w,
+                "crate::vertex_layout::VertexBindingLayout {{ binding: {}, stride: {}, input_rate: ash::vk::VertexInputRate::{}, attributes: &[{}] }},",
+                binding, stride, input_rate, attribute_layouts
+            )?;
+            offset_checks.push(format!(
+                "std::mem::size_of::<{}>() == {}",
+                struct_name, stride
+            ));
+        }
+        writeln!(w, "];")?;
+        for check in offset_checks {
+            writeln!(w, "const _: () = assert!({});", check)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn generate_push_constant_ranges(
+    push_constant_ranges_path: &PathBuf,
+    stages: &[(spirv::ShaderStage, &spirv::Module)],
+) -> Result<(), io::Error> {
+    let push_constant_ranges_file = File::create(push_constant_ranges_path)?;
+    let mut w = BufWriter::new(push_constant_ranges_file);
+
+    writeln!(
+        w,
+        "// the push constant blocks of every stage. vulkan::PipelineLayout merges them"
+    )?;
+    writeln!(
+        w,
+        "const PUSH_CONSTANT_RANGES: &[ash::vk::PushConstantRange] = &["
+    )?;
+    for (stage, module) in stages {
+        let push_constants = module.get_push_constants().unwrap_or_else(|e| {
+            panic!(
+                "could not reflect the push constants of {}: {}",
+                module.name, e
+            )
+        });
+        for p in push_constants {
+            writeln!(
+                w,
+                "ash::vk::PushConstantRange {{ stage_flags: ash::vk::ShaderStageFlags::from_raw({:#x}), offset: {}, size: {} }},",
+                stage.flag(),
+                p.offset,
+                p.size
+            )?;
+        }
+    }
+    writeln!(w, "];")?;
 
     Ok(())
 }
@@ -284,6 +545,21 @@ fn main() {
         })
         .collect();
 
+    let stages: Box<[(spirv::ShaderStage, &spirv::Module)]> = shader_paths
+        .iter()
+        .zip(spv_modules.iter())
+        .map(|(path, module)| {
+            let stage = match path.extension().and_then(|e| e.to_str()) {
+                Some("vert") => spirv::ShaderStage::Vertex,
+                Some("frag") => spirv::ShaderStage::Fragment,
+                _ => panic!("unknown shader stage for {}", path.display()),
+            };
+            (stage, module)
+        })
+        .collect();
+    let merged_layout = spirv::merge_stages(&stages)
+        .unwrap_or_else(|e| panic!("shader stages are incompatible: {}", e));
+
     let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
 
     let variable_types_path = out_dir.join("variable_types.rs");
@@ -297,4 +573,16 @@ fn main() {
     let entry_point_names_path = out_dir.join("entry_points.rs");
     generate_entry_point_vars(&entry_point_names_path, &spv_modules).unwrap();
     run_rustfmt_on(&entry_point_names_path);
+
+    let uniform_bindings_path = out_dir.join("uniform_bindings.rs");
+    generate_uniform_bindings(&uniform_bindings_path, &merged_layout).unwrap();
+    run_rustfmt_on(&uniform_bindings_path);
+
+    let push_constant_ranges_path = out_dir.join("push_constant_ranges.rs");
+    generate_push_constant_ranges(&push_constant_ranges_path, &stages).unwrap();
+    run_rustfmt_on(&push_constant_ranges_path);
+
+    let vertex_layouts_path = out_dir.join("vertex_layouts.rs");
+    generate_vertex_layouts(&vertex_layouts_path, &stages).unwrap();
+    run_rustfmt_on(&vertex_layouts_path);
 }
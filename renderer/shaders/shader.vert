@@ -17,12 +17,14 @@ layout(std140, set = 1, binding = 0) uniform MeshUBO {
 layout(location = 0) in vec3 position;
 layout(location = 1) in vec2 tex_coord;
 layout(location = 2) in vec3 normal;
+// per instance, in model space
+layout(location = 3) in vec3 instance_offset;
 
 layout(location = 0) out vec2 v_tex_coord;
 layout(location = 1) out vec3 v_normal;
 
 void main() {
-    gl_Position = camera.proj * camera.view * mesh.model * vec4(position, 1);
+    gl_Position = camera.proj * camera.view * mesh.model * vec4(position + instance_offset, 1);
     v_tex_coord = tex_coord;
     v_normal = normal;
 }
@@ -0,0 +1,303 @@
+use ash::vk;
+
+use crate::{Error, Result};
+
+pub(crate) struct UniformBinding {
+    pub name: &'static str,
+    pub set: u32,
+    pub binding: u32,
+    // the reflected array size. 1 for non arrays and runtime arrays
+    pub descriptor_count: u32,
+    // None for uniforms the reflection doesn't know a descriptor type for
+    pub descriptor_type: Option<vk::DescriptorType>,
+    // every stage that declares the uniform
+    pub stage_flags: vk::ShaderStageFlags,
+}
+
+pub(crate) fn find_binding<'a>(
+    bindings: &'a [UniformBinding],
+    name: &str,
+) -> Result<&'a UniformBinding> {
+    bindings
+        .iter()
+        .find(|b| b.name == name)
+        .ok_or_else(|| Error::UnknownUniform(name.into()))
+}
+
+// every graphics stage when no shader uses set/binding
+pub(crate) fn reflected_stage_flags(
+    bindings: &[UniformBinding],
+    set: u32,
+    binding: u32,
+) -> vk::ShaderStageFlags {
+    bindings
+        .iter()
+        .find(|b| b.set == set && b.binding == binding)
+        .map_or(vk::ShaderStageFlags::ALL_GRAPHICS, |b| b.stage_flags)
+}
+
+// 1 when no shader uses set/binding
+pub(crate) fn reflected_descriptor_count(
+    bindings: &[UniformBinding],
+    set: u32,
+    binding: u32,
+) -> u32 {
+    bindings
+        .iter()
+        .find(|b| b.set == set && b.binding == binding)
+        .map_or(1, |b| b.descriptor_count)
+}
+
+// whether a layout binding of layout_type can back a uniform the shaders declare as
+// reflected. the shaders can't tell dynamic buffers apart from the others
+pub(crate) fn layout_matches_reflection(
+    binding: &UniformBinding,
+    layout_type: vk::DescriptorType,
+) -> bool {
+    let layout_type = match layout_type {
+        vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC => vk::DescriptorType::UNIFORM_BUFFER,
+        vk::DescriptorType::STORAGE_BUFFER_DYNAMIC => vk::DescriptorType::STORAGE_BUFFER,
+        t => t,
+    };
+
+    binding.descriptor_type.is_none_or(|t| t == layout_type)
+}
+
+fn check_array_range(
+    name: &str,
+    layout_binding: &vulkan::DescriptorSetLayoutBindingInfo,
+    first_element: u32,
+    count: usize,
+) -> Result<()> {
+    match first_element.checked_add(count as u32) {
+        Some(end) if end <= layout_binding.descriptor_count => Ok(()),
+        _ => Err(Error::DescriptorArrayOutOfRange {
+            name: name.into(),
+            first_element,
+            count: count as u32,
+            descriptor_count: layout_binding.descriptor_count,
+        }),
+    }
+}
+
+pub(crate) fn buffer_write<'a>(
+    name: &str,
+    dst_set: vk::DescriptorSet,
+    layout_binding: &vulkan::DescriptorSetLayoutBindingInfo,
+    first_element: u32,
+    buffer_infos: &'a [vk::DescriptorBufferInfo],
+) -> Result<vk::WriteDescriptorSet<'a>> {
+    match layout_binding.descriptor_type {
+        vk::DescriptorType::UNIFORM_BUFFER
+        | vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC
+        | vk::DescriptorType::STORAGE_BUFFER
+        | vk::DescriptorType::STORAGE_BUFFER_DYNAMIC => {}
+        descriptor_type => {
+            return Err(Error::DescriptorTypeMismatch {
+                name: name.into(),
+                descriptor_type,
+            });
+        }
+    }
+    check_array_range(name, layout_binding, first_element, buffer_infos.len())?;
+
+    Ok(vk::WriteDescriptorSet {
+        dst_set,
+        dst_binding: layout_binding.binding,
+        dst_array_element: first_element,
+        descriptor_count: buffer_infos.len() as u32,
+        descriptor_type: layout_binding.descriptor_type,
+        p_buffer_info: buffer_infos.as_ptr(),
+        ..Default::default()
+    })
+}
+
+pub(crate) fn image_write<'a>(
+    name: &str,
+    dst_set: vk::DescriptorSet,
+    layout_binding: &vulkan::DescriptorSetLayoutBindingInfo,
+    first_element: u32,
+    image_infos: &'a [vk::DescriptorImageInfo],
+) -> Result<vk::WriteDescriptorSet<'a>> {
+    match layout_binding.descriptor_type {
+        vk::DescriptorType::COMBINED_IMAGE_SAMPLER | vk::DescriptorType::SAMPLED_IMAGE => {}
+        descriptor_type => {
+            return Err(Error::DescriptorTypeMismatch {
+                name: name.into(),
+                descriptor_type,
+            });
+        }
+    }
+    check_array_range(name, layout_binding, first_element, image_infos.len())?;
+
+    Ok(vk::WriteDescriptorSet {
+        dst_set,
+        dst_binding: layout_binding.binding,
+        dst_array_element: first_element,
+        descriptor_count: image_infos.len() as u32,
+        descriptor_type: layout_binding.descriptor_type,
+        p_image_info: image_infos.as_ptr(),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BINDINGS: &[UniformBinding] = &[
+        UniformBinding {
+            name: "camera",
+            set: 0,
+            binding: 0,
+            descriptor_count: 1,
+            descriptor_type: Some(vk::DescriptorType::UNIFORM_BUFFER),
+            stage_flags: vk::ShaderStageFlags::VERTEX,
+        },
+        UniformBinding {
+            name: "world_light",
+            set: 2,
+            binding: 0,
+            descriptor_count: 1,
+            descriptor_type: Some(vk::DescriptorType::UNIFORM_BUFFER),
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+        },
+        UniformBinding {
+            name: "tex",
+            set: 2,
+            binding: 1,
+            descriptor_count: 8,
+            descriptor_type: Some(vk::DescriptorType::COMBINED_IMAGE_SAMPLER),
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+        },
+    ];
+
+    fn layout_binding(
+        binding: u32,
+        descriptor_type: vk::DescriptorType,
+        descriptor_count: u32,
+    ) -> vulkan::DescriptorSetLayoutBindingInfo {
+        vulkan::DescriptorSetLayoutBindingInfo {
+            binding,
+            descriptor_type,
+            descriptor_count,
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            p_immutable_shader: std::ptr::null(),
+            size: None,
+        }
+    }
+
+    #[test]
+    fn named_uniform_write() {
+        let b = find_binding(BINDINGS, "world_light").unwrap();
+        assert_eq!((b.set, b.binding), (2, 0));
+
+        let layout = layout_binding(b.binding, vk::DescriptorType::UNIFORM_BUFFER, 1);
+        let buffer_infos = [vk::DescriptorBufferInfo::default()];
+        let write = buffer_write(
+            "world_light",
+            vk::DescriptorSet::null(),
+            &layout,
+            0,
+            &buffer_infos,
+        )
+        .unwrap();
+        assert_eq!(write.dst_binding, 0);
+        assert_eq!(write.descriptor_type, vk::DescriptorType::UNIFORM_BUFFER);
+        assert_eq!(write.p_buffer_info, buffer_infos.as_ptr());
+
+        let image_infos = [vk::DescriptorImageInfo::default()];
+        match image_write(
+            "world_light",
+            vk::DescriptorSet::null(),
+            &layout,
+            0,
+            &image_infos,
+        ) {
+            Err(Error::DescriptorTypeMismatch { name, .. }) => {
+                assert_eq!(name.as_ref(), "world_light")
+            }
+            r => panic!("expected DescriptorTypeMismatch, got {:?}", r.map(|_| ())),
+        }
+
+        match find_binding(BINDINGS, "albedo") {
+            Err(Error::UnknownUniform(name)) => assert_eq!(name.as_ref(), "albedo"),
+            r => panic!("expected UnknownUniform, got {:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn arrayed_binding() {
+        assert_eq!(reflected_descriptor_count(BINDINGS, 2, 1), 8);
+        assert_eq!(reflected_descriptor_count(BINDINGS, 0, 0), 1);
+        assert_eq!(reflected_descriptor_count(BINDINGS, 3, 0), 1);
+        assert_eq!(
+            reflected_stage_flags(BINDINGS, 0, 0),
+            vk::ShaderStageFlags::VERTEX
+        );
+        assert_eq!(
+            reflected_stage_flags(BINDINGS, 3, 0),
+            vk::ShaderStageFlags::ALL_GRAPHICS
+        );
+
+        let b = find_binding(BINDINGS, "tex").unwrap();
+        let layout = layout_binding(
+            b.binding,
+            vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            reflected_descriptor_count(BINDINGS, b.set, b.binding),
+        );
+
+        let image_infos = [vk::DescriptorImageInfo::default(); 3];
+        let write =
+            image_write("tex", vk::DescriptorSet::null(), &layout, 5, &image_infos).unwrap();
+        assert_eq!(write.dst_array_element, 5);
+        assert_eq!(write.descriptor_count, 3);
+
+        match image_write("tex", vk::DescriptorSet::null(), &layout, 6, &image_infos) {
+            Err(Error::DescriptorArrayOutOfRange {
+                first_element,
+                count,
+                descriptor_count,
+                ..
+            }) => {
+                assert_eq!(first_element, 6);
+                assert_eq!(count, 3);
+                assert_eq!(descriptor_count, 8);
+            }
+            r => panic!(
+                "expected DescriptorArrayOutOfRange, got {:?}",
+                r.map(|_| ())
+            ),
+        }
+    }
+
+    #[test]
+    fn reflected_storage_buffer() {
+        // the materials block in shader.frag, reflected from its spir-v by build.rs
+        let b = crate::UNIFORM_BINDINGS
+            .iter()
+            .find(|b| (b.set, b.binding) == (2, 2))
+            .unwrap();
+        assert_eq!(b.descriptor_type, Some(vk::DescriptorType::STORAGE_BUFFER));
+        assert!(layout_matches_reflection(
+            b,
+            vk::DescriptorType::STORAGE_BUFFER_DYNAMIC
+        ));
+        assert!(!layout_matches_reflection(
+            b,
+            vk::DescriptorType::UNIFORM_BUFFER
+        ));
+
+        let layout = layout_binding(b.binding, vk::DescriptorType::STORAGE_BUFFER, 1);
+        let buffer_infos = [vk::DescriptorBufferInfo {
+            buffer: vk::Buffer::null(),
+            offset: 0,
+            range: vk::WHOLE_SIZE,
+        }];
+        let write =
+            buffer_write(b.name, vk::DescriptorSet::null(), &layout, 0, &buffer_infos).unwrap();
+        assert_eq!(write.dst_binding, 2);
+        assert_eq!(write.descriptor_type, vk::DescriptorType::STORAGE_BUFFER);
+        assert_eq!(write.descriptor_count, 1);
+    }
+}
@@ -0,0 +1,101 @@
+use std::time::{Duration, Instant};
+
+// frames longer than this are clamped so a stall (breakpoint, window drag)
+// does not make step run hundreds of updates to catch up
+const MAX_FRAME_TIME: Duration = Duration::from_millis(250);
+
+pub struct Clock {
+    last_tick: Instant,
+    delta_time: Duration,
+    fixed_step: Duration,
+    accumulator: Duration,
+}
+
+impl Clock {
+    pub fn new(fixed_step: Duration) -> Self {
+        Self::starting_at(Instant::now(), fixed_step)
+    }
+
+    pub fn starting_at(now: Instant, fixed_step: Duration) -> Self {
+        assert!(!fixed_step.is_zero(), "fixed_step must be non zero");
+
+        Self {
+            last_tick: now,
+            delta_time: Duration::ZERO,
+            fixed_step,
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    // call once per frame
+    pub fn tick(&mut self) {
+        self.tick_at(Instant::now());
+    }
+
+    pub fn tick_at(&mut self, now: Instant) {
+        self.delta_time = now.saturating_duration_since(self.last_tick);
+        self.last_tick = now;
+        self.accumulator += self.delta_time.min(MAX_FRAME_TIME);
+    }
+
+    // seconds between the last two ticks
+    pub fn delta_time(&self) -> f32 {
+        self.delta_time.as_secs_f32()
+    }
+
+    pub fn fixed_step(&self) -> f32 {
+        self.fixed_step.as_secs_f32()
+    }
+
+    // calls update once per whole fixed step accumulated by tick.
+    // returns how many times update was called
+    pub fn step<F: FnMut(f32)>(&mut self, mut update: F) -> u32 {
+        let dt = self.fixed_step.as_secs_f32();
+        let mut steps = 0;
+        while self.accumulator >= self.fixed_step {
+            self.accumulator -= self.fixed_step;
+            update(dt);
+            steps += 1;
+        }
+        steps
+    }
+
+    // how far between the last and next fixed step the current frame is, in [0, 1)
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.fixed_step.as_secs_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_steps() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut clock = Clock::starting_at(start, ms(10));
+
+        // not enough time for a step yet
+        clock.tick_at(start + ms(4));
+        assert_eq!(clock.step(|_| {}), 0);
+        assert!((clock.delta_time() - 0.004).abs() < 1e-6);
+
+        // 4 + 17 = 21 -> 2 steps, 1ms left over
+        clock.tick_at(start + ms(21));
+        let mut total = 0.0;
+        assert_eq!(clock.step(|dt| total += dt), 2);
+        assert!((total - 0.02).abs() < 1e-6);
+        assert!((clock.alpha() - 0.1).abs() < 1e-4);
+
+        // 1 + 9 = 10 -> exactly 1 step
+        clock.tick_at(start + ms(30));
+        assert_eq!(clock.step(|_| {}), 1);
+        assert_eq!(clock.alpha(), 0.0);
+
+        // a stall only catches up MAX_FRAME_TIME worth of steps
+        clock.tick_at(start + ms(30) + Duration::from_secs(5));
+        assert!((clock.delta_time() - 5.0).abs() < 1e-6);
+        assert_eq!(clock.step(|_| {}), 25);
+    }
+}
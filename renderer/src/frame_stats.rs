@@ -0,0 +1,108 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// how many frames average_fps is taken over
+pub const FRAME_STATS_WINDOW: usize = 120;
+
+pub struct FrameStats {
+    last_frame: Option<Instant>,
+    frame_times: VecDeque<Duration>,
+    total: Duration,
+    swapchain_recreations: u32,
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self {
+            last_frame: None,
+            frame_times: VecDeque::with_capacity(FRAME_STATS_WINDOW),
+            total: Duration::ZERO,
+            swapchain_recreations: 0,
+        }
+    }
+}
+
+impl FrameStats {
+    // call once per frame
+    pub fn record_frame(&mut self) {
+        self.record_frame_at(Instant::now());
+    }
+
+    pub fn record_frame_at(&mut self, now: Instant) {
+        let Some(last_frame) = self.last_frame.replace(now) else {
+            return;
+        };
+        let frame_time = now.saturating_duration_since(last_frame);
+
+        if self.frame_times.len() == FRAME_STATS_WINDOW {
+            self.total -= self.frame_times.pop_front().unwrap();
+        }
+        self.frame_times.push_back(frame_time);
+        self.total += frame_time;
+    }
+
+    pub fn record_swapchain_recreation(&mut self) {
+        self.swapchain_recreations += 1;
+    }
+
+    // time between the last two frames
+    pub fn frame_time(&self) -> Duration {
+        self.frame_times.back().copied().unwrap_or(Duration::ZERO)
+    }
+
+    // over the last FRAME_STATS_WINDOW frames. 0 until two frames were recorded
+    pub fn average_fps(&self) -> f32 {
+        if self.total.is_zero() {
+            return 0.0;
+        }
+
+        self.frame_times.len() as f32 / self.total.as_secs_f32()
+    }
+
+    // the longest frame in the window, which shows stutter the average hides
+    pub fn max_frame_time(&self) -> Duration {
+        self.frame_times
+            .iter()
+            .max()
+            .copied()
+            .unwrap_or(Duration::ZERO)
+    }
+
+    pub fn swapchain_recreations(&self) -> u32 {
+        self.swapchain_recreations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolling_average() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut stats = FrameStats::default();
+
+        stats.record_frame_at(start);
+        assert_eq!(stats.frame_time(), Duration::ZERO);
+        assert_eq!(stats.average_fps(), 0.0);
+
+        stats.record_frame_at(start + ms(10));
+        stats.record_frame_at(start + ms(40));
+        assert_eq!(stats.frame_time(), ms(30));
+        assert_eq!(stats.max_frame_time(), ms(30));
+        assert!((stats.average_fps() - 50.0).abs() < 1e-3);
+
+        // once the window is full the oldest frames drop out, including the slow one
+        let mut now = start + ms(40);
+        for _ in 0..FRAME_STATS_WINDOW {
+            now += ms(20);
+            stats.record_frame_at(now);
+        }
+        assert_eq!(stats.max_frame_time(), ms(20));
+        assert!((stats.average_fps() - 50.0).abs() < 1e-3);
+
+        stats.record_swapchain_recreation();
+        assert_eq!(stats.swapchain_recreations(), 1);
+    }
+}
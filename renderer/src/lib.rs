@@ -1,19 +1,32 @@
+mod bindings;
+mod clock;
+mod frame_stats;
 mod render_context;
 mod result;
+mod vertex_layout;
+mod window_config;
 
 include!(concat!(env!("OUT_DIR"), "/variable_types.rs"));
 include!(concat!(env!("OUT_DIR"), "/shader_paths.rs"));
 include!(concat!(env!("OUT_DIR"), "/entry_points.rs"));
+include!(concat!(env!("OUT_DIR"), "/uniform_bindings.rs"));
+include!(concat!(env!("OUT_DIR"), "/push_constant_ranges.rs"));
+include!(concat!(env!("OUT_DIR"), "/vertex_layouts.rs"));
 
-pub use render_context::RenderContext;
+pub use clock::Clock;
+pub use frame_stats::{FRAME_STATS_WINDOW, FrameStats};
+pub use render_context::{MAX_FRAME_COUNT, PipelineConfig, RenderContext};
 pub use result::Error;
 pub use result::Result;
+pub use window_config::WindowConfig;
 
 use ash::vk;
 use std::rc::Rc;
 use vulkan::device::SharedDeviceRef;
 
-use crate::render_context::MAX_FRAME_COUNT;
+// vertex buffer bindings of the pipelines create_pipeline builds
+pub const VERTEX_BINDING: u32 = 0;
+pub const INSTANCE_BINDING: u32 = 1;
 
 unsafe extern "system" fn vulkan_debug_callback(
     message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
@@ -61,8 +74,12 @@ pub const MAX_MATERIALS: u32 = 32;
 pub struct Renderer {
     pub device: SharedDeviceRef,
     pub pipeline_layout: Rc<vulkan::PipelineLayout>,
-    command_pool: vk::CommandPool,
-    descriptor_pool: vk::DescriptorPool,
+    // shared by every RenderContext whose swapchain has the same color format
+    pipelines: Vec<((vk::Format, vk::SampleCountFlags), Rc<vulkan::Pipeline>)>,
+    pipeline_config: PipelineConfig,
+    pipeline_cache: vulkan::PipelineCache,
+    swapchain_config: vulkan::SwapchainConfig,
+    descriptor_pool: vulkan::DescriptorPool,
     per_frame_ds_layout: vk::DescriptorSetLayout,
     per_obj_ds_layout: vk::DescriptorSetLayout,
     other_ds_layout: vk::DescriptorSetLayout,
@@ -72,205 +89,138 @@ pub struct Renderer {
     global_light_buffer: vulkan::Buffer,
     textures: Box<[vulkan::Image]>,
     material_buffer: vulkan::Buffer,
-    repeat_sampler: vk::Sampler,
+    repeat_sampler: vulkan::Sampler,
 }
 
-impl Renderer {
-    pub fn new(
-        debug_enabled: bool,
-        display_handle: &winit::raw_window_handle::DisplayHandle,
-        model_transform_count: u64,
-        texture_data: &[image::DynamicImage],
-        material_data: &[crate::MaterialUBO],
-    ) -> result::Result<Renderer> {
-        let instance = vulkan::Instance::new(debug_enabled, display_handle)?;
-        let device = vulkan::Device::new(instance, Some(vulkan_debug_callback))?;
-
-        let command_pool = {
-            let command_pool_create_info = vk::CommandPoolCreateInfo {
-                queue_family_index: device.get_queue_family_index(),
-                ..Default::default()
-            };
+// copies image_data into a device local, sampled image through a staging buffer. the image
+// is left in SHADER_READ_ONLY_OPTIMAL
+fn upload_image(
+    device: &SharedDeviceRef,
+    image_data: &image::DynamicImage,
+    mipmaps: bool,
+) -> result::Result<vulkan::Image> {
+    use image::GenericImageView;
+
+    let (width, height) = image_data.dimensions();
+    let rgba = image_data.to_rgba8();
+    let data = rgba.as_raw();
+    let size = data.len() as u64;
+    let mip_levels = if mipmaps {
+        vulkan::mip_level_count(width, height, 1)
+    } else {
+        1
+    };
 
-            unsafe { device.create_command_pool(&command_pool_create_info) }
-                .inspect_err(|e| tracing::error!("{e}"))?
+    let image = {
+        let image_create_info = vulkan::ImageCreateInfo {
+            memory_property_flags: vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            mip_levels,
+            image_type: vk::ImageType::TYPE_2D,
+            format: vk::Format::R8G8B8A8_SRGB,
+            width,
+            height,
+            depth: 1,
+            usage: if mipmaps {
+                vk::ImageUsageFlags::TRANSFER_SRC
+                    | vk::ImageUsageFlags::TRANSFER_DST
+                    | vk::ImageUsageFlags::SAMPLED
+            } else {
+                vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED
+            },
+            array_layers: 1,
+            samples: vk::SampleCountFlags::TYPE_1,
         };
 
-        let mut textures = Vec::<vulkan::Image>::with_capacity(texture_data.len());
-        for data in texture_data {
-            use image::GenericImageView;
-
-            let (width, height) = data.dimensions();
-            let rgba = data.clone().into_rgba8();
-            let data = rgba.as_raw();
-            let size = data.len() as u64;
-
-            let image = {
-                let image_create_info = vulkan::ImageCreateInfo {
-                    memory_property_flags: vk::MemoryPropertyFlags::DEVICE_LOCAL,
-                    mip_levels: 1,
-                    image_type: vk::ImageType::TYPE_2D,
-                    format: vk::Format::R8G8B8A8_SRGB,
-                    width,
-                    height,
-                    depth: 1,
-                    usage: vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
-                    array_layers: 1,
-                };
-
-                vulkan::Image::new(device.clone(), &image_create_info)?
-            };
-
-            let transfer_buffer = {
-                let create_info = vulkan::BufferCreateInfo {
-                    size: size,
-                    usage: vk::BufferUsageFlags::TRANSFER_SRC,
-                    memory_property_flags: vk::MemoryPropertyFlags::HOST_VISIBLE
-                        | vk::MemoryPropertyFlags::HOST_COHERENT,
-                };
-
-                vulkan::Buffer::new(device.clone(), &create_info)
-                    .inspect_err(|e| tracing::error!("{}", e))?
-            };
-
-            unsafe {
-                let dst = transfer_buffer.map_memory(0, size)?;
-
-                std::ptr::copy_nonoverlapping(data.as_ptr(), dst as *mut u8, size as usize);
-
-                transfer_buffer.unmap();
-            }
-
-            let command_buffer = {
-                let command_buffer_allocate_info = vk::CommandBufferAllocateInfo {
-                    command_pool,
-                    command_buffer_count: 1,
-                    level: vk::CommandBufferLevel::PRIMARY,
-                    ..Default::default()
-                };
-
-                let command_buffers =
-                    unsafe { device.allocate_command_buffers(&command_buffer_allocate_info) }?;
-
-                command_buffers[0]
-            };
-
-            {
-                let begin_info = vk::CommandBufferBeginInfo {
-                    flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
-                    ..Default::default()
-                };
-
-                unsafe { device.begin_command_buffer(command_buffer, &begin_info) }?
-            }
-
-            // transfer commands here
-            {
-                // to I need the stage mask here?
-                let barriers = [vk::ImageMemoryBarrier2 {
-                    image: image.handle,
-                    old_layout: vk::ImageLayout::UNDEFINED,
-                    new_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-                    src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-                    dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-                    subresource_range: vk::ImageSubresourceRange {
-                        aspect_mask: vk::ImageAspectFlags::COLOR,
-                        base_mip_level: 0,
-                        level_count: 1,
-                        base_array_layer: 0,
-                        layer_count: 1,
-                    },
-                    src_stage_mask: vk::PipelineStageFlags2::TOP_OF_PIPE,
-                    dst_stage_mask: vk::PipelineStageFlags2::TRANSFER,
-                    src_access_mask: vk::AccessFlags2::NONE,
-                    dst_access_mask: vk::AccessFlags2::TRANSFER_WRITE,
-                    ..Default::default()
-                }];
-
-                let dependency_info = vk::DependencyInfo {
-                    image_memory_barrier_count: barriers.len() as u32,
-                    p_image_memory_barriers: barriers.as_ptr(),
-                    ..Default::default()
-                };
+        vulkan::Image::new(device.clone(), &image_create_info)?
+    };
 
-                unsafe { device.cmd_pipeline_barrier2(command_buffer, &dependency_info) };
+    let staging_buffer = {
+        let create_info = vulkan::BufferCreateInfo {
+            size,
+            usage: vk::BufferUsageFlags::TRANSFER_SRC,
+            memory_property_flags: vk::MemoryPropertyFlags::HOST_VISIBLE
+                | vk::MemoryPropertyFlags::HOST_COHERENT,
+        };
 
-                let regions = [vk::BufferImageCopy2 {
-                    buffer_offset: 0,
-                    buffer_row_length: 0,
-                    buffer_image_height: 0,
-                    image_subresource: vk::ImageSubresourceLayers {
-                        aspect_mask: vk::ImageAspectFlags::COLOR,
-                        mip_level: 0,
-                        base_array_layer: 0,
-                        layer_count: 1,
-                    },
-                    image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
-                    image_extent: vk::Extent3D {
-                        width: image.width,
-                        height: image.height,
-                        depth: image.depth,
-                    },
-                    ..Default::default()
-                }];
+        vulkan::Buffer::new(device.clone(), &create_info)
+            .inspect_err(|e| tracing::error!("{}", e))?
+    };
 
-                let copy_buffer_to_image_info = vk::CopyBufferToImageInfo2 {
-                    src_buffer: transfer_buffer.handle,
-                    dst_image: image.handle,
-                    dst_image_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-                    region_count: regions.len() as u32,
-                    p_regions: regions.as_ptr(),
-                    ..Default::default()
-                };
+    unsafe {
+        let dst = staging_buffer.map_memory(0, size)?;
 
-                unsafe {
-                    device.cmd_copy_buffer_to_image2(command_buffer, &copy_buffer_to_image_info)
-                };
+        std::ptr::copy_nonoverlapping(data.as_ptr(), dst as *mut u8, size as usize);
 
-                let barriers = [vk::ImageMemoryBarrier2 {
-                    image: image.handle,
-                    old_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-                    new_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-                    src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-                    dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-                    subresource_range: vk::ImageSubresourceRange {
-                        aspect_mask: vk::ImageAspectFlags::COLOR,
-                        base_mip_level: 0,
-                        level_count: 1,
-                        base_array_layer: 0,
-                        layer_count: 1,
-                    },
-                    src_stage_mask: vk::PipelineStageFlags2::TRANSFER,
-                    dst_stage_mask: vk::PipelineStageFlags2::FRAGMENT_SHADER,
-                    src_access_mask: vk::AccessFlags2::TRANSFER_WRITE,
-                    dst_access_mask: vk::AccessFlags2::SHADER_READ,
-                    ..Default::default()
-                }];
+        staging_buffer.unmap();
+    }
 
-                let dependency_info = vk::DependencyInfo {
-                    image_memory_barrier_count: barriers.len() as u32,
-                    p_image_memory_barriers: barriers.as_ptr(),
-                    ..Default::default()
-                };
+    device.submit_immediate(|command_buffer| {
+        image.transition_layout(
+            command_buffer,
+            vk::ImageLayout::UNDEFINED,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vk::PipelineStageFlags2::TOP_OF_PIPE,
+            vk::PipelineStageFlags2::TRANSFER,
+        );
+
+        let regions = [vk::BufferImageCopy {
+            buffer_offset: 0,
+            buffer_row_length: 0,
+            buffer_image_height: 0,
+            image_subresource: vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            },
+            image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+            image_extent: vk::Extent3D {
+                width: image.width,
+                height: image.height,
+                depth: image.depth,
+            },
+        }];
 
-                unsafe { device.cmd_pipeline_barrier2(command_buffer, &dependency_info) };
-            }
+        unsafe {
+            device.cmd_copy_buffer_to_image(
+                command_buffer,
+                staging_buffer.handle,
+                image.handle,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &regions,
+            )
+        };
 
-            unsafe {
-                device.end_command_buffer(command_buffer)?;
+        if mipmaps {
+            image.generate_mipmaps(command_buffer);
+        } else {
+            image.transition_layout(
+                command_buffer,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                vk::PipelineStageFlags2::TRANSFER,
+                vk::PipelineStageFlags2::FRAGMENT_SHADER,
+            );
+        }
+    })?;
 
-                let submit_info = [vk::SubmitInfo {
-                    command_buffer_count: 1,
-                    p_command_buffers: &command_buffer,
-                    ..Default::default()
-                }];
+    Ok(image)
+}
 
-                device.queue_submit(device.queue, &submit_info, vk::Fence::null())?;
-                device.device_wait_idle()?;
-                device.free_command_buffers(command_pool, &[command_buffer]);
-            }
+impl Renderer {
+    pub fn new(
+        debug_enabled: bool,
+        display_handle: &winit::raw_window_handle::DisplayHandle,
+        model_transform_count: u64,
+        texture_data: &[image::DynamicImage],
+        material_data: &[crate::MaterialUBO],
+    ) -> result::Result<Renderer> {
+        let instance = vulkan::Instance::new(debug_enabled, display_handle)?;
+        let device = vulkan::Device::new(instance, Some(vulkan_debug_callback))?;
 
-            textures.push(image);
+        let mut textures = Vec::<vulkan::Image>::with_capacity(texture_data.len());
+        for data in texture_data {
+            textures.push(upload_image(&device, data, false)?);
         }
 
         let (model_transform_buffer, model_transform_buffer_element_size) = {
@@ -292,6 +242,7 @@ impl Renderer {
             };
 
             let buffer = vulkan::Buffer::new(device.clone(), &model_transform_buffer_create_info)?;
+            device.set_object_name(buffer.handle, "model transform buffer");
 
             (buffer, element_size)
         };
@@ -314,7 +265,10 @@ impl Renderer {
                     | vk::MemoryPropertyFlags::HOST_COHERENT,
             };
 
-            vulkan::Buffer::new(device.clone(), &global_light_buffer_create_info)?
+            let buffer = vulkan::Buffer::new(device.clone(), &global_light_buffer_create_info)?;
+            device.set_object_name(buffer.handle, "global light buffer");
+
+            buffer
         };
 
         let material_buffer = {
@@ -334,6 +288,7 @@ impl Renderer {
             };
 
             let buffer = vulkan::Buffer::new(device.clone(), &buffer_create_info)?;
+            device.set_object_name(buffer.handle, "material buffer");
             for (i, material) in material_data.iter().enumerate() {
                 let offset = element_size * i;
 
@@ -353,16 +308,16 @@ impl Renderer {
             &[vk::DescriptorSetLayoutBinding {
                 binding: 0,
                 descriptor_type: vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC,
-                descriptor_count: 1,
-                stage_flags: vk::ShaderStageFlags::VERTEX,
+                descriptor_count: bindings::reflected_descriptor_count(UNIFORM_BINDINGS, 0, 0),
+                stage_flags: bindings::reflected_stage_flags(UNIFORM_BINDINGS, 0, 0),
                 ..Default::default()
             }],
             // SET 1 - per obj
             &[vk::DescriptorSetLayoutBinding {
                 binding: 0,
                 descriptor_type: vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC,
-                descriptor_count: 1,
-                stage_flags: vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                descriptor_count: bindings::reflected_descriptor_count(UNIFORM_BINDINGS, 1, 0),
+                stage_flags: bindings::reflected_stage_flags(UNIFORM_BINDINGS, 1, 0),
                 p_immutable_samplers: std::ptr::null(),
                 _marker: std::marker::PhantomData {},
             }],
@@ -372,17 +327,17 @@ impl Renderer {
                 vk::DescriptorSetLayoutBinding {
                     binding: 0,
                     descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
-                    descriptor_count: 1,
-                    stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                    descriptor_count: bindings::reflected_descriptor_count(UNIFORM_BINDINGS, 2, 0),
+                    stage_flags: bindings::reflected_stage_flags(UNIFORM_BINDINGS, 2, 0),
                     p_immutable_samplers: std::ptr::null(),
                     _marker: std::marker::PhantomData {},
                 },
-                // global_textures
+                // global_textures is a runtime array, so its size comes from the textures loaded
                 vk::DescriptorSetLayoutBinding {
                     binding: 1,
                     descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
                     descriptor_count: textures.len() as u32,
-                    stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                    stage_flags: bindings::reflected_stage_flags(UNIFORM_BINDINGS, 2, 1),
                     p_immutable_samplers: std::ptr::null(),
                     _marker: std::marker::PhantomData {},
                 },
@@ -390,52 +345,30 @@ impl Renderer {
                 vk::DescriptorSetLayoutBinding {
                     binding: 2,
                     descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
-                    descriptor_count: 1,
-                    stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                    descriptor_count: bindings::reflected_descriptor_count(UNIFORM_BINDINGS, 2, 2),
+                    stage_flags: bindings::reflected_stage_flags(UNIFORM_BINDINGS, 2, 2),
                     p_immutable_samplers: std::ptr::null(),
                     _marker: std::marker::PhantomData {},
                 },
             ],
         ];
+        debug_assert_eq!(ds_layout_bindings.len() as u32, UNIFORM_SET_COUNT);
         let pipeline_layout = Rc::new(vulkan::PipelineLayout::new(
             device.clone(),
             ds_layout_bindings,
+            PUSH_CONSTANT_RANGES,
         )?);
 
         let descriptor_pool = {
-            let pool_sizes = [
-                vk::DescriptorPoolSize {
-                    ty: vk::DescriptorType::UNIFORM_BUFFER,
-                    descriptor_count: 1,
-                },
-                vk::DescriptorPoolSize {
-                    ty: vk::DescriptorType::STORAGE_BUFFER,
-                    descriptor_count: MAX_MATERIALS,
-                },
-                vk::DescriptorPoolSize {
-                    ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-                    descriptor_count: MAX_TEXTURES,
-                },
-                vk::DescriptorPoolSize {
-                    ty: vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC,
-                    descriptor_count: 2,
-                },
-            ];
-            let descrptor_pool_create_info = vk::DescriptorPoolCreateInfo {
-                max_sets: crate::MAX_FRAME_COUNT as u32 + MAX_MATERIALS + 1,
-                pool_size_count: pool_sizes.len() as u32,
-                p_pool_sizes: pool_sizes.as_ptr(),
-                ..Default::default()
-            };
-
-            unsafe { device.create_descriptor_pool(&descrptor_pool_create_info) }.inspect_err(
-                |e| {
-                    unsafe {
-                        device.destroy_command_pool(command_pool);
-                    }
-                    tracing::error!("{e}")
-                },
-            )?
+            let set_layouts: Box<[&vulkan::DescriptorSetLayout]> =
+                pipeline_layout.get_set_layouts().iter().collect();
+
+            vulkan::DescriptorPool::from_layouts(
+                device.clone(),
+                &set_layouts,
+                set_layouts.len() as u32,
+            )
+            .inspect_err(|e| tracing::error!("{e}"))?
         };
 
         let per_frame_ds_layout = {
@@ -449,12 +382,6 @@ impl Renderer {
             unsafe { device.create_descriptor_set_layout(&ds_layout_create_info) }.inspect_err(
                 |e| {
                     tracing::error!("{e}");
-                    unsafe {
-                        device.destroy_command_pool(command_pool);
-                    }
-                    unsafe {
-                        device.destroy_descriptor_pool(descriptor_pool);
-                    }
                 },
             )?
         };
@@ -473,12 +400,6 @@ impl Renderer {
                     unsafe {
                         device.destroy_descriptor_set_layout(per_frame_ds_layout);
                     }
-                    unsafe {
-                        device.destroy_command_pool(command_pool);
-                    }
-                    unsafe {
-                        device.destroy_descriptor_pool(descriptor_pool);
-                    }
                 },
             )?
         };
@@ -500,12 +421,6 @@ impl Renderer {
                     unsafe {
                         device.destroy_descriptor_set_layout(per_frame_ds_layout);
                     }
-                    unsafe {
-                        device.destroy_command_pool(command_pool);
-                    }
-                    unsafe {
-                        device.destroy_descriptor_pool(descriptor_pool);
-                    }
                 },
             )?
         };
@@ -513,7 +428,7 @@ impl Renderer {
         let descriptor_sets = {
             let ds_layouts = [per_frame_ds_layout, per_obj_ds_layout, other_ds_layout];
             let ds_create_info = vk::DescriptorSetAllocateInfo {
-                descriptor_pool,
+                descriptor_pool: descriptor_pool.handle,
                 descriptor_set_count: ds_layouts.len() as u32,
                 p_set_layouts: ds_layouts.as_ptr(),
                 ..Default::default()
@@ -530,33 +445,24 @@ impl Renderer {
                 unsafe {
                     device.destroy_descriptor_set_layout(other_ds_layout);
                 }
-                unsafe {
-                    device.destroy_command_pool(command_pool);
-                }
-                unsafe {
-                    device.destroy_descriptor_pool(descriptor_pool);
-                }
             })?
         };
 
         let repeat_sampler = {
             let properties = unsafe { device.get_physical_device_properties() };
-            let sampler_create_info = vk::SamplerCreateInfo {
+            let sampler_create_info = vulkan::SamplerCreateInfo {
                 mag_filter: vk::Filter::LINEAR,
                 min_filter: vk::Filter::LINEAR,
                 mipmap_mode: vk::SamplerMipmapMode::LINEAR,
                 address_mode_u: vk::SamplerAddressMode::REPEAT,
                 address_mode_v: vk::SamplerAddressMode::REPEAT,
                 address_mode_w: vk::SamplerAddressMode::REPEAT,
-                mip_lod_bias: 0.0,
-                anisotropy_enable: vk::TRUE,
-                max_anisotropy: properties.limits.max_sampler_anisotropy,
-                compare_enable: vk::FALSE,
-                compare_op: vk::CompareOp::ALWAYS,
-                ..Default::default()
+                max_anisotropy: Some(properties.limits.max_sampler_anisotropy),
+                min_lod: 0.0,
+                max_lod: vk::LOD_CLAMP_NONE,
             };
 
-            unsafe { device.create_sampler(&sampler_create_info) }.inspect_err(|e| {
+            vulkan::Sampler::new(device.clone(), &sampler_create_info).inspect_err(|e| {
                 tracing::error!("{e}");
                 unsafe {
                     device.destroy_descriptor_set_layout(per_obj_ds_layout);
@@ -567,12 +473,6 @@ impl Renderer {
                 unsafe {
                     device.destroy_descriptor_set_layout(other_ds_layout);
                 }
-                unsafe {
-                    device.destroy_command_pool(command_pool);
-                }
-                unsafe {
-                    device.destroy_descriptor_pool(descriptor_pool);
-                }
             })?
         };
 
@@ -591,7 +491,7 @@ impl Renderer {
             let image_infos: Box<[vk::DescriptorImageInfo]> = textures
                 .iter()
                 .map(|img| vk::DescriptorImageInfo {
-                    sampler: repeat_sampler,
+                    sampler: repeat_sampler.handle,
                     image_view: img.view,
                     image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
                 })
@@ -642,10 +542,15 @@ impl Renderer {
             unsafe { device.update_descriptor_sets(&writes, &[]) };
         }
 
+        let pipeline_cache = vulkan::PipelineCache::new(device.clone())?;
+
         Ok(Renderer {
             device,
             pipeline_layout,
-            command_pool,
+            pipelines: Vec::new(),
+            pipeline_config: PipelineConfig::default(),
+            pipeline_cache,
+            swapchain_config: vulkan::SwapchainConfig::default(),
             descriptor_pool,
             per_frame_ds_layout,
             per_obj_ds_layout,
@@ -659,14 +564,37 @@ impl Renderer {
             repeat_sampler,
         })
     }
-    pub fn create_render_context(&self, window: &winit::window::Window) -> Result<RenderContext> {
+    pub fn create_render_context(
+        &mut self,
+        window: &winit::window::Window,
+    ) -> Result<RenderContext> {
         RenderContext::new(
             self.device.clone(),
             self.pipeline_layout.clone(),
+            &mut self.pipelines,
+            &self.pipeline_config,
+            &self.pipeline_cache,
+            &self.swapchain_config,
             window,
             self.descriptor_sets[0],
         )
     }
+    // replaces the in memory pipeline cache with the one saved at path. it's written back
+    // there when the renderer is dropped
+    pub fn load_pipeline_cache(&mut self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        self.pipeline_cache = vulkan::PipelineCache::load(self.device.clone(), path)?;
+
+        Ok(())
+    }
+    // only affects pipelines created afterwards. existing RenderContexts keep theirs
+    pub fn set_pipeline_config(&mut self, config: PipelineConfig) {
+        self.pipeline_config = config;
+        self.pipelines.clear();
+    }
+    // only affects RenderContexts created afterwards
+    pub fn set_present_policy(&mut self, policy: vulkan::PresentPolicy) {
+        self.swapchain_config.present_policy = policy;
+    }
     pub fn update_world_light(
         &self,
         ambient: f32,
@@ -693,6 +621,108 @@ impl Renderer {
 
         Ok(())
     }
+    fn get_layout_binding(
+        &self,
+        binding: &bindings::UniformBinding,
+    ) -> Result<&vulkan::DescriptorSetLayoutBindingInfo> {
+        let layout_binding = self
+            .pipeline_layout
+            .get_set_layouts()
+            .get(binding.set as usize)
+            .and_then(|layout| layout.get_binding(binding.binding))
+            .ok_or_else(|| Error::UnknownUniform(binding.name.into()))?;
+
+        // the layouts are written by hand, so they can disagree with the shaders
+        if !bindings::layout_matches_reflection(binding, layout_binding.descriptor_type) {
+            return Err(Error::DescriptorTypeMismatch {
+                name: binding.name.into(),
+                descriptor_type: layout_binding.descriptor_type,
+            });
+        }
+
+        Ok(layout_binding)
+    }
+    // points the uniform or storage buffer named in the shaders at uniform_bv
+    #[inline]
+    pub fn set_uniform(&self, name: &str, uniform_bv: &vulkan::UniformBV) -> Result<()> {
+        self.set_uniform_buffer(name, &uniform_bv.buffer, uniform_bv.offset, uniform_bv.size)
+    }
+    // like set_uniform, for views from create_uniform_buffer and create_storage_buffer
+    pub fn set_uniform_view(&self, name: &str, view: &vulkan::BufferView) -> Result<()> {
+        let (offset, size) = view
+            .descriptor_range()
+            .ok_or(Error::ExpectedDescriptorBufferView)?;
+
+        self.set_uniform_buffer(name, view.buffer(), offset, size)
+    }
+    fn set_uniform_buffer(
+        &self,
+        name: &str,
+        buffer: &vulkan::Buffer,
+        offset: vk::DeviceSize,
+        range: vk::DeviceSize,
+    ) -> Result<()> {
+        let binding = bindings::find_binding(UNIFORM_BINDINGS, name)?;
+        let layout_binding = self.get_layout_binding(binding)?;
+
+        let buffer_infos = [vk::DescriptorBufferInfo {
+            buffer: buffer.handle,
+            offset,
+            range,
+        }];
+        let write = bindings::buffer_write(
+            name,
+            self.descriptor_sets[binding.set as usize],
+            layout_binding,
+            0,
+            &buffer_infos,
+        )?;
+
+        unsafe { self.device.update_descriptor_sets(&[write], &[]) };
+
+        Ok(())
+    }
+    // points the first element of the texture named in the shaders at image_view
+    #[inline]
+    pub fn set_texture(
+        &self,
+        name: &str,
+        image_view: vk::ImageView,
+        sampler: vk::Sampler,
+    ) -> Result<()> {
+        self.set_textures(name, 0, &[(image_view, sampler)])
+    }
+    // writes textures to the elements of the texture array named in the shaders
+    // starting at first_element
+    pub fn set_textures(
+        &self,
+        name: &str,
+        first_element: u32,
+        textures: &[(vk::ImageView, vk::Sampler)],
+    ) -> Result<()> {
+        let binding = bindings::find_binding(UNIFORM_BINDINGS, name)?;
+        let layout_binding = self.get_layout_binding(binding)?;
+
+        let image_infos: Box<[vk::DescriptorImageInfo]> = textures
+            .iter()
+            .map(|(image_view, sampler)| vk::DescriptorImageInfo {
+                sampler: *sampler,
+                image_view: *image_view,
+                image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            })
+            .collect();
+        let write = bindings::image_write(
+            name,
+            self.descriptor_sets[binding.set as usize],
+            layout_binding,
+            first_element,
+            &image_infos,
+        )?;
+
+        unsafe { self.device.update_descriptor_sets(&[write], &[]) };
+
+        Ok(())
+    }
     fn get_transfer_buffer(&self, size: u64) -> result::Result<vulkan::Buffer> {
         let create_info = vulkan::BufferCreateInfo {
             size: size,
@@ -704,54 +734,61 @@ impl Renderer {
         let buffer = vulkan::Buffer::new(self.device.clone(), &create_info)
             .inspect_err(|e| tracing::error!("{}", e))?;
 
-        Ok(buffer)
-    }
-    fn get_command_buffer(&self) -> Result<vk::CommandBuffer> {
-        let command_buffer_allocate_info = vk::CommandBufferAllocateInfo {
-            command_pool: self.command_pool,
-            command_buffer_count: 1,
-            level: vk::CommandBufferLevel::PRIMARY,
-            ..Default::default()
-        };
-
-        let command_buffers = unsafe {
-            self.device
-                .allocate_command_buffers(&command_buffer_allocate_info)
-        }?;
+        self.device.set_object_name(buffer.handle, "staging buffer");
 
-        Ok(*command_buffers.get(0).unwrap())
+        Ok(buffer)
     }
     pub fn create_vertex_buffer(
         &self,
         data: &[u8],
         vertex_count: u32,
+        instance_count: u32,
     ) -> vulkan::Result<vulkan::VertexBV> {
-        let buffer = {
-            let buffer_create_info = vulkan::BufferCreateInfo {
-                size: data.len() as u64,
-                usage: vk::BufferUsageFlags::VERTEX_BUFFER,
-                memory_property_flags: ash::vk::MemoryPropertyFlags::HOST_VISIBLE
-                    | vk::MemoryPropertyFlags::HOST_COHERENT,
-            };
-
-            vulkan::Buffer::new(self.device.clone(), &buffer_create_info)?
-        };
+        let buffer = Rc::new(vulkan::Buffer::from_slice(
+            self.device.clone(),
+            data,
+            vk::BufferUsageFlags::VERTEX_BUFFER,
+        )?);
 
-        let buffer = Rc::new(buffer);
+        self.device.set_object_name(buffer.handle, "vertex buffer");
 
-        unsafe {
-            let dst = buffer.map_memory(buffer.offset, buffer.size)?;
+        let view = vulkan::VertexBV {
+            buffer,
+            vertex_count,
+            instance_count,
+            first_binding: VERTEX_BINDING,
+            offset: 0,
+        };
 
-            std::ptr::copy_nonoverlapping(data.as_ptr(), dst as *mut u8, data.len());
+        Ok(view)
+    }
+    // per-instance data for INSTANCE_BINDING, i.e. ShaderVertInstances. binding it is
+    // all that's needed, the vertex or index view still does the draw
+    pub fn create_instance_buffer(
+        &self,
+        instances: &[ShaderVertInstance],
+    ) -> Result<vulkan::VertexBV> {
+        let data = unsafe {
+            std::slice::from_raw_parts(
+                instances.as_ptr() as *const u8,
+                std::mem::size_of_val(instances),
+            )
+        };
+        let buffer = Rc::new(self.create_device_local_buffer(
+            data,
+            vk::BufferUsageFlags::VERTEX_BUFFER,
+            vk::PipelineStageFlags2::VERTEX_ATTRIBUTE_INPUT,
+            vk::AccessFlags2::VERTEX_ATTRIBUTE_READ,
+        )?);
 
-            buffer.unmap();
-        }
+        self.device
+            .set_object_name(buffer.handle, "instance buffer");
 
         let view = vulkan::VertexBV {
             buffer,
-            vertex_count,
-            instance_count: 1,
-            first_binding: 0,
+            vertex_count: 0,
+            instance_count: instances.len() as u32,
+            first_binding: INSTANCE_BINDING,
             offset: 0,
         };
 
@@ -763,33 +800,153 @@ impl Renderer {
         index_type: vk::IndexType,
         index_count: u32,
         first_index: u32,
+        instance_count: u32,
     ) -> result::Result<vulkan::IndexBV> {
+        let buffer = Rc::new(vulkan::Buffer::from_slice(
+            self.device.clone(),
+            data,
+            vk::BufferUsageFlags::INDEX_BUFFER,
+        )?);
+
+        self.device.set_object_name(buffer.handle, "index buffer");
+
+        let view = vulkan::IndexBV {
+            buffer,
+            offset: 0,
+            index_count,
+            instance_count,
+            first_index,
+            vertex_offset: 0,
+            first_instance: 0,
+            index_type,
+        };
+
+        Ok(view)
+    }
+    // copies data into a DEVICE_LOCAL buffer through a staging buffer. Waits for the
+    // copy to finish. dst_stage/dst_access describe how the buffer is read afterwards
+    fn create_device_local_buffer(
+        &self,
+        data: &[u8],
+        usage: vk::BufferUsageFlags,
+        dst_stage_mask: vk::PipelineStageFlags2,
+        dst_access_mask: vk::AccessFlags2,
+    ) -> Result<vulkan::Buffer> {
+        let size = data.len() as u64;
+
         let buffer = {
-            let buffer_create_info = vulkan::buffer::BufferCreateInfo {
-                size: data.len() as u64,
-                usage: vk::BufferUsageFlags::INDEX_BUFFER,
-                memory_property_flags: vk::MemoryPropertyFlags::HOST_VISIBLE
-                    | vk::MemoryPropertyFlags::HOST_COHERENT,
+            let create_info = vulkan::BufferCreateInfo {
+                size,
+                usage: usage | vk::BufferUsageFlags::TRANSFER_DST,
+                memory_property_flags: vk::MemoryPropertyFlags::DEVICE_LOCAL,
             };
 
-            vulkan::Buffer::new(self.device.clone(), &buffer_create_info)?
+            vulkan::Buffer::new(self.device.clone(), &create_info)
+                .inspect_err(|e| tracing::error!("{}", e))?
         };
 
-        let buffer = Rc::new(buffer);
+        let transfer_buffer = self.get_transfer_buffer(size)?;
 
         unsafe {
-            let dst = buffer.map_memory(buffer.offset, buffer.size)?;
+            let dst = transfer_buffer.map_memory(0, size)?;
 
             std::ptr::copy_nonoverlapping(data.as_ptr(), dst as *mut u8, data.len());
 
-            buffer.unmap();
+            transfer_buffer.unmap();
         }
 
+        self.device.submit_immediate(|command_buffer| {
+            let regions = [vk::BufferCopy {
+                src_offset: 0,
+                dst_offset: 0,
+                size,
+            }];
+
+            unsafe {
+                self.device.cmd_copy_buffer(
+                    command_buffer,
+                    transfer_buffer.handle,
+                    buffer.handle,
+                    &regions,
+                )
+            };
+
+            let barriers = [vk::BufferMemoryBarrier2 {
+                src_stage_mask: vk::PipelineStageFlags2::TRANSFER,
+                src_access_mask: vk::AccessFlags2::TRANSFER_WRITE,
+                dst_stage_mask,
+                dst_access_mask,
+                src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                buffer: buffer.handle,
+                offset: 0,
+                size: vk::WHOLE_SIZE,
+                ..Default::default()
+            }];
+
+            let dependency_info = vk::DependencyInfo {
+                buffer_memory_barrier_count: barriers.len() as u32,
+                p_buffer_memory_barriers: barriers.as_ptr(),
+                ..Default::default()
+            };
+
+            unsafe {
+                self.device
+                    .cmd_pipeline_barrier2(command_buffer, &dependency_info)
+            };
+        })?;
+
+        Ok(buffer)
+    }
+    // for static meshes. create_vertex_buffer is still the one to use for data that
+    // is rewritten from the cpu
+    pub fn create_vertex_buffer_device_local(
+        &self,
+        data: &[u8],
+        vertex_count: u32,
+        instance_count: u32,
+    ) -> Result<vulkan::VertexBV> {
+        let buffer = Rc::new(self.create_device_local_buffer(
+            data,
+            vk::BufferUsageFlags::VERTEX_BUFFER,
+            vk::PipelineStageFlags2::VERTEX_ATTRIBUTE_INPUT,
+            vk::AccessFlags2::VERTEX_ATTRIBUTE_READ,
+        )?);
+
+        self.device.set_object_name(buffer.handle, "vertex buffer");
+
+        let view = vulkan::VertexBV {
+            buffer,
+            vertex_count,
+            instance_count,
+            first_binding: VERTEX_BINDING,
+            offset: 0,
+        };
+
+        Ok(view)
+    }
+    pub fn create_index_buffer_device_local(
+        &self,
+        data: &[u8],
+        index_type: vk::IndexType,
+        index_count: u32,
+        first_index: u32,
+        instance_count: u32,
+    ) -> Result<vulkan::IndexBV> {
+        let buffer = Rc::new(self.create_device_local_buffer(
+            data,
+            vk::BufferUsageFlags::INDEX_BUFFER,
+            vk::PipelineStageFlags2::INDEX_INPUT,
+            vk::AccessFlags2::INDEX_READ,
+        )?);
+
+        self.device.set_object_name(buffer.handle, "index buffer");
+
         let view = vulkan::IndexBV {
             buffer,
             offset: 0,
             index_count,
-            instance_count: 1,
+            instance_count,
             first_index,
             vertex_offset: 0,
             first_instance: 0,
@@ -813,6 +970,7 @@ impl Renderer {
 
             vulkan::Buffer::new(self.device.clone(), &buffer_create_info)?
         };
+        self.device.set_object_name(buffer.handle, "uniform buffer");
 
         let buffer = Rc::new(buffer);
 
@@ -826,6 +984,95 @@ impl Renderer {
 
         Ok(views)
     }
+    // a HOST_VISIBLE | HOST_COHERENT buffer of size bytes, viewed from offset 0
+    pub fn create_uniform_buffer(&self, size: u64) -> Result<Rc<vulkan::BufferView>> {
+        let buffer = {
+            let create_info = vulkan::BufferCreateInfo {
+                size,
+                usage: vk::BufferUsageFlags::UNIFORM_BUFFER,
+                memory_property_flags: vk::MemoryPropertyFlags::HOST_VISIBLE
+                    | vk::MemoryPropertyFlags::HOST_COHERENT,
+            };
+
+            vulkan::Buffer::new(self.device.clone(), &create_info)
+                .inspect_err(|e| tracing::error!("{}", e))?
+        };
+        self.device.set_object_name(buffer.handle, "uniform buffer");
+
+        let view = vulkan::UniformBV {
+            buffer: Rc::new(buffer),
+            offset: 0,
+            size,
+        };
+
+        Ok(Rc::new(view.into()))
+    }
+    // MAX_FRAME_COUNT views of size bytes, one per frame in flight, so a frame's uniforms can
+    // be written while the previous frames still read theirs. they share one buffer
+    pub fn create_uniform_buffers_per_frame(
+        &self,
+        size: u64,
+    ) -> Result<Box<[Rc<vulkan::BufferView>]>> {
+        let properties = unsafe { self.device.get_physical_device_properties() };
+        let stride =
+            size.next_multiple_of(properties.limits.min_uniform_buffer_offset_alignment.max(1));
+
+        let buffer = {
+            let create_info = vulkan::BufferCreateInfo {
+                size: stride * MAX_FRAME_COUNT as u64,
+                usage: vk::BufferUsageFlags::UNIFORM_BUFFER,
+                memory_property_flags: vk::MemoryPropertyFlags::HOST_VISIBLE
+                    | vk::MemoryPropertyFlags::HOST_COHERENT,
+            };
+
+            vulkan::Buffer::new(self.device.clone(), &create_info)
+                .inspect_err(|e| tracing::error!("{}", e))?
+        };
+        self.device
+            .set_object_name(buffer.handle, "per frame uniform buffer");
+
+        let buffer = Rc::new(buffer);
+        let views = (0..MAX_FRAME_COUNT as u64)
+            .map(|i| {
+                let view = vulkan::UniformBV {
+                    buffer: buffer.clone(),
+                    offset: i * stride,
+                    size,
+                };
+                Rc::new(view.into())
+            })
+            .collect();
+
+        Ok(views)
+    }
+    // a storage buffer holding data. device local ones can only be written by shaders or
+    // transfers afterwards, the others stay HOST_VISIBLE | HOST_COHERENT
+    pub fn create_storage_buffer(
+        &self,
+        data: &[u8],
+        device_local: bool,
+    ) -> Result<Rc<vulkan::BufferView>> {
+        let usage = vk::BufferUsageFlags::STORAGE_BUFFER | vk::BufferUsageFlags::TRANSFER_SRC;
+        let buffer = if device_local {
+            self.create_device_local_buffer(
+                data,
+                usage,
+                vk::PipelineStageFlags2::ALL_COMMANDS,
+                vk::AccessFlags2::SHADER_STORAGE_READ | vk::AccessFlags2::SHADER_STORAGE_WRITE,
+            )?
+        } else {
+            vulkan::Buffer::from_slice(self.device.clone(), data, usage)?
+        };
+        self.device.set_object_name(buffer.handle, "storage buffer");
+
+        let view = vulkan::StorageBV {
+            size: data.len() as vk::DeviceSize,
+            buffer: Rc::new(buffer),
+            offset: 0,
+        };
+
+        Ok(Rc::new(view.into()))
+    }
     pub fn update_uniform_buffer(
         &self,
         data: *const u8,
@@ -842,6 +1089,29 @@ impl Renderer {
             Ok(uniform_bv.buffer.unmap())
         }
     }
+    // writes data to the start of a uniform or dynamic uniform view
+    pub fn update_buffer_view(&self, data: &[u8], view: &vulkan::BufferView) -> result::Result<()> {
+        let (offset, size) = view
+            .uniform_range()
+            .ok_or(Error::ExpectedUniformBufferView)?;
+
+        if data.len() as u64 > size {
+            return Err(Error::BufferViewTooSmall {
+                data_size: data.len() as u64,
+                view_size: size,
+            });
+        }
+
+        unsafe {
+            let dst = view.buffer().map_memory(offset, size)?;
+
+            std::ptr::copy_nonoverlapping(data.as_ptr(), dst as *mut u8, data.len());
+
+            view.buffer().unmap();
+        }
+
+        Ok(())
+    }
     pub fn create_dynamic_uniform_buffer(&self, size: u64) -> Result<vulkan::Buffer> {
         let buffer = {
             let create_info = vulkan::BufferCreateInfo {
@@ -855,6 +1125,9 @@ impl Renderer {
                 .inspect_err(|e| tracing::error!("{}", e))?
         };
 
+        self.device
+            .set_object_name(buffer.handle, "dynamic uniform buffer");
+
         Ok(buffer)
     }
     pub fn update_dynamic_uniform_buffer(
@@ -873,170 +1146,249 @@ impl Renderer {
             Ok(uniform_bv.buffer.unmap())
         }
     }
-    pub fn create_image(&self, image_data: image::DynamicImage) -> result::Result<vulkan::Image> {
-        use image::GenericImageView;
+    // with mipmaps set the image gets a full mip chain, generated on the gpu
+    pub fn create_image(
+        &self,
+        image_data: image::DynamicImage,
+        mipmaps: bool,
+    ) -> result::Result<Rc<vulkan::Image>> {
+        Ok(Rc::new(upload_image(&self.device, &image_data, mipmaps)?))
+    }
+    // renders a single frame into an offscreen width x height target and reads it back.
+    // record_draw_commands is called inside the render pass with the viewport and scissor
+    // covering the whole target, like RenderContext::draw.
+    pub fn render_to_image<F>(
+        &mut self,
+        width: u32,
+        height: u32,
+        clear_color: [f32; 4],
+        record_draw_commands: F,
+    ) -> Result<image::RgbaImage>
+    where
+        F: FnOnce(vk::CommandBuffer, &vulkan::Pipeline),
+    {
+        if width == 0 || height == 0 {
+            return Err(Error::EmptyImageExtent { width, height });
+        }
 
-        let (width, height) = image_data.dimensions();
-        let rgba = image_data.into_rgba8();
-        let data = rgba.as_raw();
-        let size = data.len() as u64;
+        const COLOR_FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
+        // the offscreen target isn't multisampled, whatever the pipeline config asks for
+        const PIPELINE_KEY: (vk::Format, vk::SampleCountFlags) =
+            (COLOR_FORMAT, vk::SampleCountFlags::TYPE_1);
+
+        let pipeline =
+            render_context::get_or_create_pipeline(&mut self.pipelines, PIPELINE_KEY, || {
+                render_context::create_pipeline(
+                    self.device.clone(),
+                    self.pipeline_layout.clone(),
+                    COLOR_FORMAT,
+                    vk::SampleCountFlags::TYPE_1,
+                    &self.pipeline_config,
+                    &self.pipeline_cache,
+                )
+            })?;
 
-        let image = {
-            let image_create_info = vulkan::ImageCreateInfo {
+        let color_image = vulkan::Image::new(
+            self.device.clone(),
+            &vulkan::ImageCreateInfo {
                 memory_property_flags: vk::MemoryPropertyFlags::DEVICE_LOCAL,
                 mip_levels: 1,
                 image_type: vk::ImageType::TYPE_2D,
-                format: vk::Format::R8G8B8A8_SRGB,
+                format: COLOR_FORMAT,
                 width,
                 height,
                 depth: 1,
-                usage: vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
+                usage: vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
                 array_layers: 1,
-            };
-
-            vulkan::Image::new(self.device.clone(), &image_create_info)?
-        };
-
-        let transfer_buffer = self.get_transfer_buffer(size)?;
-
-        unsafe {
-            let dst = transfer_buffer.map_memory(0, size)?;
-
-            std::ptr::copy_nonoverlapping(data.as_ptr(), dst as *mut u8, size as usize);
-
-            transfer_buffer.unmap();
-        }
-
-        let command_buffer = self.get_command_buffer()?;
-
-        {
-            let begin_info = vk::CommandBufferBeginInfo {
-                flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
-                ..Default::default()
-            };
+                samples: vk::SampleCountFlags::TYPE_1,
+            },
+        )?;
+        let depth_image = vulkan::Image::new(
+            self.device.clone(),
+            &vulkan::ImageCreateInfo {
+                memory_property_flags: vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                mip_levels: 1,
+                image_type: vk::ImageType::TYPE_2D,
+                format: self.device.depth_stencil_format(),
+                width,
+                height,
+                depth: 1,
+                usage: vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+                array_layers: 1,
+                samples: vk::SampleCountFlags::TYPE_1,
+            },
+        )?;
 
-            unsafe {
-                self.device
-                    .begin_command_buffer(command_buffer, &begin_info)
-            }?;
-        }
+        let size = width as u64 * height as u64 * 4;
+        let readback_buffer = vulkan::Buffer::new(
+            self.device.clone(),
+            &vulkan::BufferCreateInfo {
+                size,
+                usage: vk::BufferUsageFlags::TRANSFER_DST,
+                memory_property_flags: vk::MemoryPropertyFlags::HOST_VISIBLE
+                    | vk::MemoryPropertyFlags::HOST_COHERENT,
+            },
+        )?;
+
+        self.device.submit_immediate(|command_buffer| {
+            color_image.transition_layout(
+                command_buffer,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                vk::PipelineStageFlags2::TOP_OF_PIPE,
+                vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+            );
+            depth_image.transition_layout(
+                command_buffer,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                vk::PipelineStageFlags2::TOP_OF_PIPE,
+                vk::PipelineStageFlags2::EARLY_FRAGMENT_TESTS,
+            );
 
-        // transfer commands here
-        {
-            // to I need the stage mask here?
-            let barriers = [vk::ImageMemoryBarrier2 {
-                image: image.handle,
-                old_layout: vk::ImageLayout::UNDEFINED,
-                new_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-                src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-                dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-                subresource_range: vk::ImageSubresourceRange {
-                    aspect_mask: vk::ImageAspectFlags::COLOR,
-                    base_mip_level: 0,
-                    level_count: 1,
-                    base_array_layer: 0,
+            {
+                let extent = vk::Extent2D { width, height };
+
+                let color_attachment_info = vk::RenderingAttachmentInfo {
+                    image_view: color_image.view,
+                    image_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                    load_op: vk::AttachmentLoadOp::CLEAR,
+                    store_op: vk::AttachmentStoreOp::STORE,
+                    clear_value: vk::ClearValue {
+                        color: vk::ClearColorValue {
+                            float32: clear_color,
+                        },
+                    },
+                    ..Default::default()
+                };
+                let depth_attachment_info = vk::RenderingAttachmentInfo {
+                    image_view: depth_image.view,
+                    image_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                    load_op: vk::AttachmentLoadOp::CLEAR,
+                    store_op: vk::AttachmentStoreOp::DONT_CARE,
+                    clear_value: vk::ClearValue {
+                        depth_stencil: vk::ClearDepthStencilValue {
+                            depth: 1.0,
+                            stencil: 0,
+                        },
+                    },
+                    ..Default::default()
+                };
+                let rendering_info = vk::RenderingInfo {
+                    render_area: vk::Rect2D {
+                        offset: vk::Offset2D { x: 0, y: 0 },
+                        extent,
+                    },
                     layer_count: 1,
-                },
-                src_stage_mask: vk::PipelineStageFlags2::TOP_OF_PIPE,
-                dst_stage_mask: vk::PipelineStageFlags2::TRANSFER,
-                src_access_mask: vk::AccessFlags2::NONE,
-                dst_access_mask: vk::AccessFlags2::TRANSFER_WRITE,
-                ..Default::default()
-            }];
+                    view_mask: 0,
+                    color_attachment_count: 1,
+                    p_color_attachments: &color_attachment_info,
+                    p_depth_attachment: &depth_attachment_info,
+                    ..Default::default()
+                };
 
-            let dependency_info = vk::DependencyInfo {
-                image_memory_barrier_count: barriers.len() as u32,
-                p_image_memory_barriers: barriers.as_ptr(),
-                ..Default::default()
-            };
+                let viewport = vk::Viewport {
+                    x: 0.0,
+                    y: 0.0,
+                    width: width as f32,
+                    height: height as f32,
+                    min_depth: 0.0,
+                    max_depth: 1.0,
+                };
+                let scissor = vk::Rect2D {
+                    offset: vk::Offset2D { x: 0, y: 0 },
+                    extent,
+                };
 
-            unsafe {
-                self.device
-                    .cmd_pipeline_barrier2(command_buffer, &dependency_info)
-            };
+                unsafe {
+                    self.device
+                        .cmd_begin_rendering(command_buffer, &rendering_info);
+                    self.device.cmd_set_viewport(command_buffer, 0, &[viewport]);
+                    self.device.cmd_set_scissor(command_buffer, 0, &[scissor]);
+                }
 
-            let regions = [vk::BufferImageCopy2 {
-                buffer_offset: 0,
-                buffer_row_length: 0,
-                buffer_image_height: 0,
-                image_subresource: vk::ImageSubresourceLayers {
-                    aspect_mask: vk::ImageAspectFlags::COLOR,
-                    mip_level: 0,
-                    base_array_layer: 0,
-                    layer_count: 1,
-                },
-                image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
-                image_extent: vk::Extent3D {
-                    width: image.width,
-                    height: image.height,
-                    depth: image.depth,
-                },
-                ..Default::default()
-            }];
+                record_draw_commands(command_buffer, &pipeline);
 
-            let copy_buffer_to_image_info = vk::CopyBufferToImageInfo2 {
-                src_buffer: transfer_buffer.handle,
-                dst_image: image.handle,
-                dst_image_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-                region_count: regions.len() as u32,
-                p_regions: regions.as_ptr(),
-                ..Default::default()
-            };
+                unsafe { self.device.cmd_end_rendering(command_buffer) };
+            }
 
-            unsafe {
-                self.device
-                    .cmd_copy_buffer_to_image2(command_buffer, &copy_buffer_to_image_info)
-            };
+            {
+                color_image.transition_layout(
+                    command_buffer,
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+                    vk::PipelineStageFlags2::TRANSFER,
+                );
 
-            let barriers = [vk::ImageMemoryBarrier2 {
-                image: image.handle,
-                old_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-                new_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-                src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-                dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-                subresource_range: vk::ImageSubresourceRange {
-                    aspect_mask: vk::ImageAspectFlags::COLOR,
-                    base_mip_level: 0,
-                    level_count: 1,
-                    base_array_layer: 0,
-                    layer_count: 1,
-                },
-                src_stage_mask: vk::PipelineStageFlags2::TRANSFER,
-                dst_stage_mask: vk::PipelineStageFlags2::FRAGMENT_SHADER,
-                src_access_mask: vk::AccessFlags2::TRANSFER_WRITE,
-                dst_access_mask: vk::AccessFlags2::SHADER_READ,
-                ..Default::default()
-            }];
+                let regions = [vk::BufferImageCopy2 {
+                    buffer_offset: 0,
+                    buffer_row_length: 0,
+                    buffer_image_height: 0,
+                    image_subresource: vk::ImageSubresourceLayers {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        mip_level: 0,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    },
+                    image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+                    image_extent: vk::Extent3D {
+                        width,
+                        height,
+                        depth: 1,
+                    },
+                    ..Default::default()
+                }];
+                let copy_image_to_buffer_info = vk::CopyImageToBufferInfo2 {
+                    src_image: color_image.handle,
+                    src_image_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    dst_buffer: readback_buffer.handle,
+                    region_count: regions.len() as u32,
+                    p_regions: regions.as_ptr(),
+                    ..Default::default()
+                };
 
-            let dependency_info = vk::DependencyInfo {
-                image_memory_barrier_count: barriers.len() as u32,
-                p_image_memory_barriers: barriers.as_ptr(),
-                ..Default::default()
-            };
+                unsafe {
+                    self.device
+                        .cmd_copy_image_to_buffer2(command_buffer, &copy_image_to_buffer_info)
+                };
 
-            unsafe {
-                self.device
-                    .cmd_pipeline_barrier2(command_buffer, &dependency_info)
-            };
-        }
+                // the fence wait alone doesn't make the copy visible to the host
+                let barriers = [vk::BufferMemoryBarrier2 {
+                    src_stage_mask: vk::PipelineStageFlags2::TRANSFER,
+                    src_access_mask: vk::AccessFlags2::TRANSFER_WRITE,
+                    dst_stage_mask: vk::PipelineStageFlags2::HOST,
+                    dst_access_mask: vk::AccessFlags2::HOST_READ,
+                    src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    buffer: readback_buffer.handle,
+                    offset: 0,
+                    size: vk::WHOLE_SIZE,
+                    ..Default::default()
+                }];
+                let dependency_info = vk::DependencyInfo {
+                    buffer_memory_barrier_count: barriers.len() as u32,
+                    p_buffer_memory_barriers: barriers.as_ptr(),
+                    ..Default::default()
+                };
 
+                unsafe {
+                    self.device
+                        .cmd_pipeline_barrier2(command_buffer, &dependency_info)
+                };
+            }
+        })?;
+
+        let mut pixels = vec![0u8; size as usize];
         unsafe {
-            self.device.end_command_buffer(command_buffer)?;
+            let src = readback_buffer.map_memory(0, size)?;
 
-            let submit_info = [vk::SubmitInfo {
-                command_buffer_count: 1,
-                p_command_buffers: &command_buffer,
-                ..Default::default()
-            }];
+            std::ptr::copy_nonoverlapping(src as *const u8, pixels.as_mut_ptr(), pixels.len());
 
-            self.device
-                .queue_submit(self.device.queue, &submit_info, vk::Fence::null())?;
-            self.device.device_wait_idle()?;
-            self.device
-                .free_command_buffers(self.command_pool, &[command_buffer]);
+            readback_buffer.unmap();
         }
 
-        Ok(image)
+        image::RgbaImage::from_raw(width, height, pixels).ok_or(Error::ImageReadbackFailed)
     }
 }
 
@@ -1044,15 +1396,12 @@ impl Drop for Renderer {
     fn drop(&mut self) {
         unsafe {
             let _ = self.device.device_wait_idle();
-            self.device.destroy_sampler(self.repeat_sampler);
             self.device
                 .destroy_descriptor_set_layout(self.per_obj_ds_layout);
             self.device
                 .destroy_descriptor_set_layout(self.per_frame_ds_layout);
             self.device
                 .destroy_descriptor_set_layout(self.other_ds_layout);
-            self.device.destroy_command_pool(self.command_pool);
-            self.device.destroy_descriptor_pool(self.descriptor_pool);
         }
     }
 }
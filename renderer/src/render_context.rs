@@ -13,22 +13,323 @@ pub struct RenderContext {
     render_complete: Box<[vk::Semaphore]>,
     command_infos: Box<[(vk::CommandPool, vk::CommandBuffer)]>,
     depth_images: Box<[vulkan::Image]>,
+    // multisampled color targets that resolve into the swapchain images. empty without msaa
+    color_images: Box<[vulkan::Image]>,
+    samples: vk::SampleCountFlags,
     pipeline: Rc<vulkan::Pipeline>,
     pub per_frame_buffer_element_size: u32,
     per_frame_buffer: vulkan::Buffer,
+    occlusion_queries: Box<[Rc<vulkan::QueryPool>]>,
+    queries_submitted: Box<[bool]>,
+    occlusion_samples: Box<[Option<u64>]>,
+    // two per frame, written at the start and end of its command buffer. empty if the queue
+    // doesn't support timestamps
+    timestamp_queries: Box<[vulkan::QueryPool]>,
+    timestamp_valid_bits: u32,
+    last_gpu_time_ms: Option<f32>,
+    stats: crate::FrameStats,
+    clear_color: [f32; 4],
+    clear_depth: f32,
+    // how long draw waits for a frame's fence and for a swapchain image. None waits forever
+    pub timeout: Option<std::time::Duration>,
     pub index: usize,
 }
 
 pub const MAX_FRAME_COUNT: usize = 3;
+pub const MAX_OCCLUSION_QUERIES: u32 = 64;
+
+// fixed function state of the pipelines the renderer creates
+#[derive(Debug, Clone)]
+pub struct PipelineConfig {
+    pub rasterization: vulkan::RasterizationConfig,
+    // one entry per color attachment. missing entries are opaque
+    pub blend: Vec<vulkan::ColorBlendConfig>,
+    // None uses "main", or the shader's only entry point if it has no "main"
+    pub vertex_entry: Option<String>,
+    pub fragment_entry: Option<String>,
+    // clamped to the highest count the device supports
+    pub msaa_samples: vk::SampleCountFlags,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            rasterization: vulkan::RasterizationConfig::default(),
+            blend: Vec::new(),
+            vertex_entry: None,
+            fragment_entry: None,
+            msaa_samples: vk::SampleCountFlags::TYPE_1,
+        }
+    }
+}
+
+impl PipelineConfig {
+    // the sample count pipelines and attachments are actually created with
+    pub fn sample_count(&self, device: &vulkan::Device) -> vk::SampleCountFlags {
+        vulkan::clamp_sample_count(self.msaa_samples, device.supported_sample_counts())
+    }
+}
+
+fn select_entry_point<'a>(
+    requested: Option<&'a str>,
+    default: &'a str,
+    available: &[&str],
+) -> crate::Result<std::ffi::CString> {
+    let name = requested.unwrap_or(default);
+    if !available.contains(&name) {
+        return Err(crate::Error::EntryPointNotFound(name.into()));
+    }
+
+    Ok(std::ffi::CString::new(name).map_err(vulkan::result::Error::from)?)
+}
+
+pub(crate) fn create_pipeline(
+    device: SharedDeviceRef,
+    pipeline_layout: Rc<vulkan::PipelineLayout>,
+    color_format: vk::Format,
+    samples: vk::SampleCountFlags,
+    config: &PipelineConfig,
+    pipeline_cache: &vulkan::PipelineCache,
+) -> crate::Result<Pipeline> {
+    let depth_stencil_format = device.depth_stencil_format();
+
+    let vert_entry_point_name = select_entry_point(
+        config.vertex_entry.as_deref(),
+        crate::ENTRY_POINT_NAME_SHADER_VERT,
+        crate::ENTRY_POINTS_SHADER_VERT,
+    )?;
+    let frag_entry_point_name = select_entry_point(
+        config.fragment_entry.as_deref(),
+        crate::ENTRY_POINT_NAME_SHADER_FRAG,
+        crate::ENTRY_POINTS_SHADER_FRAG,
+    )?;
+
+    // TODO: convert crate::VERT_SHADER_PATH and crate::FRAG_SHADER_PATH into macros?
+    const COMPILED_VERT_SHADER: &[u8] = include_bytes!("../shaders/shader.vert.spv");
+    const COMPILED_FRAG_SHADER: &[u8] = include_bytes!("../shaders/shader.frag.spv");
+
+    let vert_shader_module =
+        vulkan::ShaderModule::from_compiled_spv(COMPILED_VERT_SHADER, device.clone())?;
+    let frag_shader_module =
+        vulkan::ShaderModule::from_compiled_spv(COMPILED_FRAG_SHADER, device.clone())?;
+
+    let stages = {
+        let vert_stage = vk::PipelineShaderStageCreateInfo {
+            stage: vk::ShaderStageFlags::VERTEX,
+            module: unsafe { *vert_shader_module.raw() },
+            p_name: vert_entry_point_name.as_ptr(),
+            ..Default::default()
+        };
+        let frag_stage = vk::PipelineShaderStageCreateInfo {
+            stage: vk::ShaderStageFlags::FRAGMENT,
+            module: unsafe { *frag_shader_module.raw() },
+            p_name: frag_entry_point_name.as_ptr(),
+            ..Default::default()
+        };
+        [vert_stage, frag_stage]
+    };
+
+    let vertex_input = crate::vertex_layout::vertex_input(crate::VERTEX_LAYOUT_SHADER_VERT);
+    let vertex_input_state = vertex_input.create_info();
+    let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo {
+        topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+        primitive_restart_enable: vk::FALSE,
+        ..Default::default()
+    };
+    let viewport_state = vk::PipelineViewportStateCreateInfo {
+        viewport_count: 1,
+        p_viewports: std::ptr::null(), // Since dynamic viewports is enabled this can be null
+        scissor_count: 1,
+        p_scissors: std::ptr::null(), // this is also be dynamic
+        ..Default::default()
+    };
+    let rasterization_state = config.rasterization.create_info();
+    let multisample_state = vk::PipelineMultisampleStateCreateInfo {
+        rasterization_samples: samples,
+        sample_shading_enable: vk::FALSE,
+        ..Default::default()
+    };
+    let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo {
+        depth_test_enable: vk::TRUE,
+        depth_write_enable: vk::TRUE,
+        depth_compare_op: vk::CompareOp::LESS,
+        depth_bounds_test_enable: vk::FALSE,
+        stencil_test_enable: vk::FALSE,
+        min_depth_bounds: 0.0,
+        max_depth_bounds: 1.0,
+        ..Default::default()
+    };
+    let color_formats = [color_format];
+    let attachments: Box<[vk::PipelineColorBlendAttachmentState]> = (0..color_formats.len())
+        .map(|i| {
+            config
+                .blend
+                .get(i)
+                .copied()
+                .unwrap_or_default()
+                .attachment_state()
+        })
+        .collect();
+    let color_blend_state = vk::PipelineColorBlendStateCreateInfo {
+        logic_op_enable: vk::FALSE,
+        logic_op: vk::LogicOp::COPY,
+        attachment_count: attachments.len() as u32,
+        p_attachments: attachments.as_ptr(),
+        blend_constants: [0.0, 0.0, 0.0, 0.0],
+        ..Default::default()
+    };
+    let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+    let dynamic_state = vk::PipelineDynamicStateCreateInfo {
+        dynamic_state_count: dynamic_states.len() as u32,
+        p_dynamic_states: dynamic_states.as_ptr(),
+        ..Default::default()
+    };
+    let pipeline_rendering_info = vk::PipelineRenderingCreateInfo {
+        color_attachment_count: color_formats.len() as u32,
+        p_color_attachment_formats: color_formats.as_ptr(),
+        depth_attachment_format: depth_stencil_format,
+        stencil_attachment_format: depth_stencil_format,
+        ..Default::default()
+    };
+    let pipeline_create_info = vk::GraphicsPipelineCreateInfo {
+        p_next: &pipeline_rendering_info as *const _ as *const std::ffi::c_void,
+        stage_count: stages.len() as u32,
+        p_stages: stages.as_ptr(),
+        p_vertex_input_state: &vertex_input_state,
+        p_input_assembly_state: &input_assembly_state,
+        p_tessellation_state: std::ptr::null(),
+        p_viewport_state: &viewport_state,
+        p_rasterization_state: &rasterization_state,
+        p_multisample_state: &multisample_state,
+        p_depth_stencil_state: &depth_stencil_state,
+        p_color_blend_state: &color_blend_state,
+        p_dynamic_state: &dynamic_state,
+        layout: pipeline_layout.handle,
+        render_pass: vk::RenderPass::null(), // dynamic rendering is enabled
+        subpass: 0,
+        ..Default::default()
+    };
+
+    // the create info only points at locals that outlive this call
+    let pipeline = unsafe {
+        Pipeline::new_graphics(
+            device.clone(),
+            pipeline_layout,
+            &pipeline_create_info,
+            Some(pipeline_cache),
+        )
+    }?;
+    device.set_object_name(
+        pipeline.get_handle(),
+        &format!("pipeline {:?} {:?}", color_format, samples),
+    );
+
+    Ok(pipeline)
+}
+
+// pipelines only depend on the attachments they render to (the color format and sample
+// count), so windows whose swapchains share a format share one pipeline
+pub(crate) fn get_or_create_pipeline<K: PartialEq, P>(
+    pipelines: &mut Vec<(K, Rc<P>)>,
+    key: K,
+    create: impl FnOnce() -> crate::Result<P>,
+) -> crate::Result<Rc<P>> {
+    if let Some((_, pipeline)) = pipelines.iter().find(|(k, _)| *k == key) {
+        return Ok(pipeline.clone());
+    }
+
+    let pipeline = Rc::new(create()?);
+    pipelines.push((key, pipeline.clone()));
+
+    Ok(pipeline)
+}
+
+// one depth image per swapchain image, matching its extent
+fn create_depth_images(
+    device: &SharedDeviceRef,
+    swapchain: &vulkan::Swapchain,
+    samples: vk::SampleCountFlags,
+) -> vulkan::result::Result<Box<[vulkan::Image]>> {
+    let depth_image_create_info = vulkan::image::ImageCreateInfo {
+        memory_property_flags: vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        mip_levels: 1,
+        image_type: vk::ImageType::TYPE_2D,
+        format: device.depth_stencil_format(),
+        width: swapchain.get_extent().width,
+        height: swapchain.get_extent().height,
+        depth: 1,
+        usage: vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+        array_layers: 1,
+        samples,
+    };
+
+    (0..swapchain.get_image_count())
+        .map(|_| vulkan::image::Image::new(device.clone(), &depth_image_create_info))
+        .collect()
+}
+
+// gives the swapchain images and the attachments rendered with them debug names
+fn name_attachments(
+    device: &SharedDeviceRef,
+    swapchain: &vulkan::Swapchain,
+    depth_images: &[vulkan::Image],
+    color_images: &[vulkan::Image],
+) {
+    for i in 0..swapchain.get_image_count() {
+        if let Some(image) = swapchain.get_image(i) {
+            device.set_object_name(*image, &format!("swapchain image {i}"));
+        }
+    }
+    for (i, image) in depth_images.iter().enumerate() {
+        device.set_object_name(image.handle, &format!("depth image {i}"));
+    }
+    for (i, image) in color_images.iter().enumerate() {
+        device.set_object_name(image.handle, &format!("msaa color image {i}"));
+    }
+}
+
+// the multisampled color targets, one per swapchain image. none when samples is TYPE_1,
+// since then the swapchain images are rendered to directly
+fn create_color_images(
+    device: &SharedDeviceRef,
+    swapchain: &vulkan::Swapchain,
+    samples: vk::SampleCountFlags,
+) -> vulkan::result::Result<Box<[vulkan::Image]>> {
+    if samples == vk::SampleCountFlags::TYPE_1 {
+        return Ok(Box::new([]));
+    }
+
+    let color_image_create_info = vulkan::image::ImageCreateInfo {
+        memory_property_flags: vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        mip_levels: 1,
+        image_type: vk::ImageType::TYPE_2D,
+        format: swapchain.get_format(),
+        width: swapchain.get_extent().width,
+        height: swapchain.get_extent().height,
+        depth: 1,
+        usage: vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT,
+        array_layers: 1,
+        samples,
+    };
+
+    (0..swapchain.get_image_count())
+        .map(|_| vulkan::image::Image::new(device.clone(), &color_image_create_info))
+        .collect()
+}
 
 impl RenderContext {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         device: SharedDeviceRef,
         pipeline_layout: Rc<vulkan::PipelineLayout>,
+        pipelines: &mut Vec<((vk::Format, vk::SampleCountFlags), Rc<vulkan::Pipeline>)>,
+        pipeline_config: &PipelineConfig,
+        pipeline_cache: &vulkan::PipelineCache,
+        swapchain_config: &vulkan::SwapchainConfig,
         window: &winit::window::Window,
         per_frame_ds: vk::DescriptorSet,
     ) -> crate::Result<RenderContext> {
-        let swapchain = vulkan::Swapchain::new(device.clone(), window)
+        let swapchain = vulkan::Swapchain::with_config(device.clone(), window, swapchain_config)
             .inspect_err(|e| tracing::error!("{e}"))?;
 
         let command_buffer_executed = {
@@ -76,6 +377,7 @@ impl RenderContext {
 
                 vulkan::Buffer::new(device.clone(), &buffer_create_info)?
             };
+            device.set_object_name(buffer.handle, "per frame buffer");
 
             let buffer_info = vk::DescriptorBufferInfo {
                 buffer: buffer.handle,
@@ -132,7 +434,8 @@ impl RenderContext {
             for _ in 0..MAX_FRAME_COUNT {
                 let pool = {
                     let pool_create_info = vk::CommandPoolCreateInfo {
-                        flags: vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
+                        // the whole pool is reset once per frame in draw
+                        flags: vk::CommandPoolCreateFlags::TRANSIENT,
                         queue_family_index: device.get_queue_family_index(),
                         ..Default::default()
                     };
@@ -190,217 +493,58 @@ impl RenderContext {
             infos.into_boxed_slice()
         };
 
-        let depth_stencil_format = device
-            .find_viable_depth_stencil_format()
-            .ok_or(vulkan::result::Error::CouldNotDetermineFormat)
-            .inspect_err(|e| tracing::error!("{}", e))?;
-
-        let depth_images = {
-            let mut images = Vec::with_capacity(swapchain.get_image_count());
-
-            let depth_image_create_info = vulkan::image::ImageCreateInfo {
-                memory_property_flags: vk::MemoryPropertyFlags::HOST_VISIBLE
-                    | vk::MemoryPropertyFlags::HOST_COHERENT,
-                mip_levels: 1,
-                image_type: vk::ImageType::TYPE_2D,
-                format: depth_stencil_format,
-                width: swapchain.get_extent().width,
-                height: swapchain.get_extent().height,
-                depth: 1,
-                usage: vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
-                array_layers: 1,
-            };
+        let samples = pipeline_config.sample_count(&device);
 
-            for _ in 0..swapchain.get_image_count() {
-                let image = vulkan::image::Image::new(device.clone(), &depth_image_create_info)
-                    .inspect_err(|e| {
-                        tracing::error!("{}", e);
-                        unsafe {
-                            for (pool, buffer) in command_infos.iter() {
-                                device.free_command_buffers(*pool, &[*buffer]);
-                                device.destroy_command_pool(*pool);
-                            }
-                            for semaphore in image_acquired.iter() {
-                                device.destroy_semaphore(*semaphore);
-                            }
-                            for semaphore in render_complete.iter() {
-                                device.destroy_semaphore(*semaphore);
-                            }
-                            for fence in command_buffer_executed.iter() {
-                                device.destroy_fence(*fence);
-                            }
-                        }
-                    })?;
-                images.push(image);
+        let attachments = create_depth_images(&device, &swapchain, samples)
+            .and_then(|depth| Ok((depth, create_color_images(&device, &swapchain, samples)?)));
+        let (depth_images, color_images) = attachments.inspect_err(|e| {
+            tracing::error!("{}", e);
+            unsafe {
+                for (pool, buffer) in command_infos.iter() {
+                    device.free_command_buffers(*pool, &[*buffer]);
+                    device.destroy_command_pool(*pool);
+                }
+                for semaphore in image_acquired.iter() {
+                    device.destroy_semaphore(*semaphore);
+                }
+                for semaphore in render_complete.iter() {
+                    device.destroy_semaphore(*semaphore);
+                }
+                for fence in command_buffer_executed.iter() {
+                    device.destroy_fence(*fence);
+                }
             }
+        })?;
+        name_attachments(&device, &swapchain, &depth_images, &color_images);
 
-            images.into_boxed_slice()
-        };
-
-        let pipeline: Rc<vulkan::Pipeline> = {
-            let vert_entry_point_name =
-                std::ffi::CString::new(crate::ENTRY_POINT_NAME_SHADER_VERT).unwrap();
-            let frag_entry_point_name =
-                std::ffi::CString::new(crate::ENTRY_POINT_NAME_SHADER_FRAG).unwrap();
-
-            // TODO: convert crate::VERT_SHADER_PATH and crate::FRAG_SHADER_PATH into macros?
-            const COMPILED_VERT_SHADER: &[u8] = include_bytes!("../shaders/shader.vert.spv");
-            const COMPILED_FRAG_SHADER: &[u8] = include_bytes!("../shaders/shader.frag.spv");
-
-            let vert_shader_module =
-                vulkan::ShaderModule::from_compiled_spv(COMPILED_VERT_SHADER, device.clone())?;
-            let frag_shader_module =
-                vulkan::ShaderModule::from_compiled_spv(COMPILED_FRAG_SHADER, device.clone())?;
-
-            let stages = {
-                let vert_stage = vk::PipelineShaderStageCreateInfo {
-                    stage: vk::ShaderStageFlags::VERTEX,
-                    module: unsafe { *vert_shader_module.raw() },
-                    p_name: vert_entry_point_name.as_ptr(),
-                    ..Default::default()
-                };
-                let frag_stage = vk::PipelineShaderStageCreateInfo {
-                    stage: vk::ShaderStageFlags::FRAGMENT,
-                    module: unsafe { *frag_shader_module.raw() },
-                    p_name: frag_entry_point_name.as_ptr(),
-                    ..Default::default()
-                };
-                [vert_stage, frag_stage]
-            };
-
-            let (vertex_input_attributes, vertex_input_bindings) = {
-                let vk_input_attributes = [
-                    vk::VertexInputAttributeDescription {
-                        location: 0,
-                        binding: 0,
-                        format: vk::Format::R32G32B32_SFLOAT,
-                        offset: std::mem::offset_of!(crate::ShaderVertVertex, position) as u32,
-                    },
-                    vk::VertexInputAttributeDescription {
-                        location: 1,
-                        binding: 0,
-                        format: vk::Format::R32G32_SFLOAT,
-                        offset: std::mem::offset_of!(crate::ShaderVertVertex, tex_coord) as u32,
-                    },
-                    vk::VertexInputAttributeDescription {
-                        location: 2,
-                        binding: 0,
-                        format: vk::Format::R32G32B32_SFLOAT,
-                        offset: std::mem::offset_of!(crate::ShaderVertVertex, normal) as u32,
-                    },
-                ];
-
-                let vk_binding_descriptions = [vk::VertexInputBindingDescription {
-                    binding: 0,
-                    stride: std::mem::size_of::<crate::ShaderVertVertex>() as u32,
-                    input_rate: vk::VertexInputRate::VERTEX,
-                }];
-
-                (vk_input_attributes, vk_binding_descriptions)
-            };
-            let vertex_input_state = vk::PipelineVertexInputStateCreateInfo {
-                vertex_binding_description_count: vertex_input_bindings.len() as u32,
-                p_vertex_binding_descriptions: vertex_input_bindings.as_ptr(),
-                vertex_attribute_description_count: vertex_input_attributes.len() as u32,
-                p_vertex_attribute_descriptions: vertex_input_attributes.as_ptr(),
-                ..Default::default()
-            };
-            let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo {
-                topology: vk::PrimitiveTopology::TRIANGLE_LIST,
-                primitive_restart_enable: vk::FALSE,
-                ..Default::default()
-            };
-            let viewport_state = vk::PipelineViewportStateCreateInfo {
-                viewport_count: 1,
-                p_viewports: std::ptr::null(), // Since dynamic viewports is enabled this can be null
-                scissor_count: 1,
-                p_scissors: std::ptr::null(), // this is also be dynamic
-                ..Default::default()
-            };
-            let rasterization_state = vk::PipelineRasterizationStateCreateInfo {
-                depth_clamp_enable: vk::FALSE,
-                rasterizer_discard_enable: vk::FALSE,
-                polygon_mode: vk::PolygonMode::FILL,
-                cull_mode: vk::CullModeFlags::NONE,
-                front_face: vk::FrontFace::CLOCKWISE,
-                depth_bias_enable: vk::FALSE,
-                depth_bias_constant_factor: 0.0,
-                depth_bias_clamp: 0.0,
-                depth_bias_slope_factor: 0.0,
-                line_width: 1.0, // dyamic states is on and VK_DYNAMIC_STATE_LINE_WIDTH is not
-                ..Default::default()
-            };
-            let multisample_state = vk::PipelineMultisampleStateCreateInfo {
-                rasterization_samples: vk::SampleCountFlags::TYPE_1,
-                sample_shading_enable: vk::FALSE,
-                ..Default::default()
-            };
-            let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo {
-                depth_test_enable: vk::TRUE,
-                depth_write_enable: vk::TRUE,
-                depth_compare_op: vk::CompareOp::LESS,
-                depth_bounds_test_enable: vk::FALSE,
-                stencil_test_enable: vk::FALSE,
-                min_depth_bounds: 0.0,
-                max_depth_bounds: 1.0,
-                ..Default::default()
-            };
-            let attachments = [vk::PipelineColorBlendAttachmentState {
-                blend_enable: vk::TRUE,
-                src_color_blend_factor: vk::BlendFactor::SRC_ALPHA,
-                dst_color_blend_factor: vk::BlendFactor::ZERO,
-                color_blend_op: vk::BlendOp::ADD,
-                src_alpha_blend_factor: vk::BlendFactor::ZERO,
-                dst_alpha_blend_factor: vk::BlendFactor::ZERO,
-                alpha_blend_op: vk::BlendOp::ADD,
-                color_write_mask: vk::ColorComponentFlags::RGBA,
-            }];
-            let color_blend_state = vk::PipelineColorBlendStateCreateInfo {
-                logic_op_enable: vk::FALSE,
-                logic_op: vk::LogicOp::COPY,
-                attachment_count: attachments.len() as u32,
-                p_attachments: attachments.as_ptr(),
-                blend_constants: [0.0, 0.0, 0.0, 0.0],
-                ..Default::default()
-            };
-            let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
-            let dynamic_state = vk::PipelineDynamicStateCreateInfo {
-                dynamic_state_count: dynamic_states.len() as u32,
-                p_dynamic_states: dynamic_states.as_ptr(),
-                ..Default::default()
-            };
-            let color_formats = [swapchain.get_format()];
-            let pipeline_rendering_info = vk::PipelineRenderingCreateInfo {
-                color_attachment_count: color_formats.len() as u32,
-                p_color_attachment_formats: color_formats.as_ptr(),
-                depth_attachment_format: depth_stencil_format,
-                stencil_attachment_format: depth_stencil_format,
-                ..Default::default()
-            };
-            let pipeline_create_info = vk::GraphicsPipelineCreateInfo {
-                p_next: &pipeline_rendering_info as *const _ as *const std::ffi::c_void,
-                stage_count: stages.len() as u32,
-                p_stages: stages.as_ptr(),
-                p_vertex_input_state: &vertex_input_state,
-                p_input_assembly_state: &input_assembly_state,
-                p_tessellation_state: std::ptr::null(),
-                p_viewport_state: &viewport_state,
-                p_rasterization_state: &rasterization_state,
-                p_multisample_state: &multisample_state,
-                p_depth_stencil_state: &depth_stencil_state,
-                p_color_blend_state: &color_blend_state,
-                p_dynamic_state: &dynamic_state,
-                layout: pipeline_layout.handle,
-                render_pass: vk::RenderPass::null(), // dynamic rendering is enabled
-                subpass: 0,
-                ..Default::default()
-            };
-
-            Rc::new(Pipeline::new_graphics(
+        let pipeline_key = (swapchain.get_format(), samples);
+        let pipeline = get_or_create_pipeline(pipelines, pipeline_key, || {
+            create_pipeline(
                 device.clone(),
                 pipeline_layout,
-                &pipeline_create_info,
-            )?)
+                swapchain.get_format(),
+                samples,
+                pipeline_config,
+                pipeline_cache,
+            )
+        })
+        .inspect_err(|e| tracing::error!("{e}"))?;
+
+        let occlusion_queries = (0..MAX_FRAME_COUNT)
+            .map(|_| {
+                vulkan::QueryPool::occlusion(device.clone(), MAX_OCCLUSION_QUERIES).map(Rc::new)
+            })
+            .collect::<vulkan::result::Result<Box<[_]>>>()
+            .inspect_err(|e| tracing::error!("{e}"))?;
+
+        let timestamp_valid_bits = device.timestamp_valid_bits();
+        let timestamp_queries = if timestamp_valid_bits == 0 {
+            Box::new([])
+        } else {
+            (0..MAX_FRAME_COUNT)
+                .map(|_| vulkan::QueryPool::timestamp(device.clone(), 2))
+                .collect::<vulkan::result::Result<Box<[_]>>>()
+                .inspect_err(|e| tracing::error!("{e}"))?
         };
 
         Ok(RenderContext {
@@ -411,14 +555,73 @@ impl RenderContext {
             render_complete,
             command_infos,
             depth_images,
+            color_images,
+            samples,
             pipeline,
             per_frame_buffer_element_size: per_frame_buffer_element_size as u32,
             per_frame_buffer,
+            occlusion_queries,
+            queries_submitted: vec![false; MAX_FRAME_COUNT].into_boxed_slice(),
+            occlusion_samples: vec![None; MAX_OCCLUSION_QUERIES as usize].into_boxed_slice(),
+            timestamp_queries,
+            timestamp_valid_bits,
+            last_gpu_time_ms: None,
+            stats: crate::FrameStats::default(),
+            clear_color: [0.0, 0.0, 0.0, 0.0],
+            clear_depth: 1.0,
+            timeout: None,
             index: 0,
         })
     }
 }
 
+impl RenderContext {
+    // recreates the swapchain and depth images for the window's current size. fences, command
+    // pools and the pipeline are kept. returns false while the window is minimized
+    pub fn resize(&mut self, window: &winit::window::Window) -> crate::Result<bool> {
+        unsafe { self.device.device_wait_idle() }?;
+
+        if !self.swapchain.recreate(window)? {
+            return Ok(false);
+        }
+        self.stats.record_swapchain_recreation();
+
+        // render_complete has one semaphore per swapchain image
+        let image_count = self.swapchain.get_image_count();
+        if image_count != self.render_complete.len() {
+            let mut semaphores = Vec::with_capacity(image_count);
+            for _ in 0..image_count {
+                let semaphore = unsafe {
+                    self.device
+                        .create_semaphore(&vk::SemaphoreCreateInfo::default())
+                }
+                .inspect_err(|_| unsafe {
+                    for s in semaphores.iter() {
+                        self.device.destroy_semaphore(*s);
+                    }
+                })?;
+                semaphores.push(semaphore);
+            }
+
+            let old = std::mem::replace(&mut self.render_complete, semaphores.into_boxed_slice());
+            for semaphore in old.iter() {
+                unsafe { self.device.destroy_semaphore(*semaphore) };
+            }
+        }
+
+        self.depth_images = create_depth_images(&self.device, &self.swapchain, self.samples)?;
+        self.color_images = create_color_images(&self.device, &self.swapchain, self.samples)?;
+        name_attachments(
+            &self.device,
+            &self.swapchain,
+            &self.depth_images,
+            &self.color_images,
+        );
+
+        Ok(true)
+    }
+}
+
 impl Drop for RenderContext {
     fn drop(&mut self) {
         unsafe {
@@ -441,10 +644,70 @@ impl Drop for RenderContext {
     }
 }
 
+// width / height, or 1 while the window is minimized and has no area
+fn aspect_ratio_of(extent: &vk::Extent2D) -> f32 {
+    if extent.width == 0 || extent.height == 0 {
+        return 1.0;
+    }
+
+    extent.width as f32 / extent.height as f32
+}
+
 impl RenderContext {
     pub fn get_pipeline(&self) -> Rc<vulkan::Pipeline> {
         self.pipeline.clone()
     }
+    #[inline]
+    pub fn get_sample_count(&self) -> vk::SampleCountFlags {
+        self.samples
+    }
+    #[inline]
+    pub fn get_extent(&self) -> &vk::Extent2D {
+        self.swapchain.get_extent()
+    }
+    // the swapchain extent can differ from the window size, so cameras should use this
+    #[inline]
+    pub fn aspect_ratio(&self) -> f32 {
+        aspect_ratio_of(self.swapchain.get_extent())
+    }
+    // the dynamic offset of this frame's camera in the per frame set (set 0)
+    #[inline]
+    pub fn per_frame_dynamic_offset(&self) -> u32 {
+        self.index as u32 * self.per_frame_buffer_element_size
+    }
+    // the pool the next call to draw records into. It is reset before the draw commands
+    // are recorded, so queries can be begun and ended from inside record_draw_commands.
+    pub fn get_occlusion_queries(&self) -> Rc<vulkan::QueryPool> {
+        self.occlusion_queries[self.index].clone()
+    }
+    // the background of every frame drawn after this. black by default
+    #[inline]
+    pub fn set_clear_color(&mut self, rgba: [f32; 4]) {
+        self.clear_color = rgba;
+    }
+    // 1.0 by default, the far plane with the LESS depth test the pipelines use
+    #[inline]
+    pub fn set_clear_depth(&mut self, depth: f32) {
+        self.clear_depth = depth;
+    }
+    #[inline]
+    pub fn stats(&self) -> &crate::FrameStats {
+        &self.stats
+    }
+    // how long the gpu took for the most recent frame whose results came back, usually
+    // MAX_FRAME_COUNT frames ago. None if the queue doesn't support timestamps
+    #[inline]
+    pub fn last_gpu_time_ms(&self) -> Option<f32> {
+        self.last_gpu_time_ms
+    }
+    // number of samples that passed the depth test the last time the query was used.
+    // Queries are not precise, so any non-zero value only means the object was visible.
+    pub fn occlusion_sample_count(&self, query: u32) -> Option<u64> {
+        self.occlusion_samples
+            .get(query as usize)
+            .copied()
+            .flatten()
+    }
     pub fn update_camera(&self, camera_ubo: crate::CameraUBO) -> crate::Result<()> {
         let element_size = {
             let struct_size = std::mem::size_of::<CameraUBO>();
@@ -471,36 +734,144 @@ impl RenderContext {
 
         Ok(())
     }
+    // what secondary command buffers passed to draw_with_secondaries have to be begun with
+    pub fn rendering_inheritance(&self) -> vulkan::RenderingInheritance {
+        vulkan::RenderingInheritance {
+            color_formats: Box::new([self.swapchain.get_format()]),
+            depth_format: self.device.depth_stencil_format(),
+            // draw_frame only attaches the depth image as a depth attachment
+            stencil_format: vk::Format::UNDEFINED,
+            samples: self.samples,
+        }
+    }
+    // covers the whole swapchain image. draw sets it, secondaries have to set it themselves
+    pub fn get_viewport(&self) -> vk::Viewport {
+        vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: self.swapchain.get_extent().width as f32,
+            height: self.swapchain.get_extent().height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }
+    }
+    #[inline]
+    pub fn get_scissor(&self) -> vk::Rect2D {
+        vk::Rect2D {
+            offset: vk::Offset2D { x: 0, y: 0 },
+            extent: *self.swapchain.get_extent(),
+        }
+    }
+    // returns Error::SwapchainOutOfDate when the swapchain is out of date or suboptimal, in
+    // which case the caller should call resize. a suboptimal frame is still presented
+    #[inline]
     pub unsafe fn draw<F>(&mut self, record_draw_commands: F) -> vulkan::result::Result<()>
     where
         F: FnOnce(vk::CommandBuffer),
     {
+        unsafe { self.draw_frame(vk::RenderingFlags::empty(), record_draw_commands) }
+    }
+    // like draw, but the frame's draws come from secondary command buffers begun with
+    // rendering_inheritance, e.g. recorded on other threads. they have to be fully recorded
+    // and stay alive until the frame's fence is signaled
+    pub unsafe fn draw_with_secondaries(
+        &mut self,
+        secondaries: &[vk::CommandBuffer],
+    ) -> vulkan::result::Result<()> {
+        let device = self.device.clone();
+
+        unsafe {
+            self.draw_frame(
+                vk::RenderingFlags::CONTENTS_SECONDARY_COMMAND_BUFFERS,
+                |command_buffer| {
+                    if !secondaries.is_empty() {
+                        device.cmd_execute_commands(command_buffer, secondaries);
+                    }
+                },
+            )
+        }
+    }
+    unsafe fn draw_frame<F>(
+        &mut self,
+        rendering_flags: vk::RenderingFlags,
+        record_draw_commands: F,
+    ) -> vulkan::result::Result<()>
+    where
+        F: FnOnce(vk::CommandBuffer),
+    {
+        self.stats.record_frame();
+
+        let acquire_suboptimal;
+
         // Acquire image
         let (swapchain_image_index, swapchain_image_view) = {
-            unsafe {
-                self.device.wait_for_fences(
+            let timeout_ns = self
+                .timeout
+                .map_or(u64::MAX, |t| t.as_nanos().min(u64::MAX as u128) as u64);
+
+            let signaled = unsafe {
+                self.device.wait_for_fences_timeout(
                     &[self.command_buffer_executed[self.index]],
                     true,
-                    u64::MAX,
+                    timeout_ns,
                 )?
             };
+            if !signaled {
+                return Err(vulkan::result::Error::Timeout);
+            }
 
-            let (image_index, _) = unsafe {
-                self.swapchain
-                    .acquire_next_image(self.image_acquired[self.index], vk::Fence::null())?
+            // the fence is only reset once an image was acquired, so returning here can't
+            // leave the next wait_for_fences waiting forever
+            let (image_index, suboptimal) = match unsafe {
+                self.swapchain.acquire_next_image_timeout(
+                    self.image_acquired[self.index],
+                    vk::Fence::null(),
+                    timeout_ns,
+                )
+            } {
+                Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                    return Err(vulkan::result::Error::SwapchainOutOfDate);
+                }
+                r => r?.ok_or(vulkan::result::Error::Timeout)?,
             };
+            acquire_suboptimal = suboptimal;
 
             unsafe {
                 self.device
                     .reset_fences(&[self.command_buffer_executed[self.index]])?
             };
+
+            if self.queries_submitted[self.index] {
+                let results =
+                    self.occlusion_queries[self.index].get_results(0, MAX_OCCLUSION_QUERIES)?;
+                for (sample, result) in self.occlusion_samples.iter_mut().zip(results) {
+                    if result.is_some() {
+                        *sample = result;
+                    }
+                }
+
+                if let Some(queries) = self.timestamp_queries.get(self.index)
+                    && let [Some(start), Some(end)] = *queries.get_results(0, 2)?
+                {
+                    let period = unsafe { self.device.get_physical_device_properties() }
+                        .limits
+                        .timestamp_period;
+                    self.last_gpu_time_ms = Some(vulkan::timestamp_elapsed_ms(
+                        start,
+                        end,
+                        self.timestamp_valid_bits,
+                        period,
+                    ));
+                }
+            }
+
             (
                 image_index as usize,
                 self.swapchain.get_image_view(image_index as usize).unwrap(),
             )
         };
 
-        let (_, command_buffer) = self.command_infos.get(self.index).unwrap();
+        let (command_pool, command_buffer) = self.command_infos.get(self.index).unwrap();
 
         // Begin command buffer
         let begin_info = vk::CommandBufferBeginInfo {
@@ -509,51 +880,60 @@ impl RenderContext {
         };
 
         unsafe {
-            // Reset the command buffer (requires pool/reset capability)
+            // The fence for this frame has been waited on, so nothing allocated from its pool
+            // is still in use. Resetting the pool also resets command_buffer.
             self.device
-                .reset_command_buffer(*command_buffer, vk::CommandBufferResetFlags::empty())?;
+                .reset_command_pool(*command_pool, vk::CommandPoolResetFlags::empty())?;
 
             self.device
                 .begin_command_buffer(*command_buffer, &begin_info)?;
+
+            self.occlusion_queries[self.index].cmd_reset(
+                *command_buffer,
+                0,
+                MAX_OCCLUSION_QUERIES,
+            )?;
+
+            if let Some(queries) = self.timestamp_queries.get(self.index) {
+                queries.cmd_reset(*command_buffer, 0, 2)?;
+                queries.cmd_write_timestamp(
+                    *command_buffer,
+                    vk::PipelineStageFlags2::TOP_OF_PIPE,
+                    0,
+                )?;
+            }
         }
 
         {
-            let color_barrier = ash::vk::ImageMemoryBarrier2 {
-                src_stage_mask: vk::PipelineStageFlags2::TOP_OF_PIPE,
-                src_access_mask: vk::AccessFlags2::empty(),
-                dst_stage_mask: vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
-                dst_access_mask: vk::AccessFlags2::COLOR_ATTACHMENT_WRITE,
-                old_layout: vk::ImageLayout::UNDEFINED,
-                new_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-                image: *self.swapchain.get_image(swapchain_image_index).unwrap(),
-                subresource_range: vk::ImageSubresourceRange {
-                    aspect_mask: vk::ImageAspectFlags::COLOR,
-                    base_mip_level: 0,
-                    level_count: 1,
-                    base_array_layer: 0,
-                    layer_count: 1,
-                },
-                ..Default::default()
-            };
-            let depth_barrier = vk::ImageMemoryBarrier2 {
-                src_stage_mask: vk::PipelineStageFlags2::TOP_OF_PIPE,
-                src_access_mask: vk::AccessFlags2::empty(),
-                dst_stage_mask: vk::PipelineStageFlags2::EARLY_FRAGMENT_TESTS,
-                dst_access_mask: vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
-                old_layout: vk::ImageLayout::UNDEFINED,
-                new_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-                image: self.depth_images.get(swapchain_image_index).unwrap().handle,
-                subresource_range: vk::ImageSubresourceRange {
-                    aspect_mask: vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL,
-                    base_mip_level: 0,
-                    level_count: 1,
-                    base_array_layer: 0,
-                    layer_count: 1,
-                },
-                ..Default::default()
-            };
-
-            let dependencies = [color_barrier, depth_barrier];
+            let color_barrier = vulkan::layout_transition_barrier(
+                *self.swapchain.get_image(swapchain_image_index).unwrap(),
+                self.swapchain.get_format(),
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                vk::PipelineStageFlags2::TOP_OF_PIPE,
+                vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+            );
+            let depth_image = self.depth_images.get(swapchain_image_index).unwrap();
+            let depth_barrier = vulkan::layout_transition_barrier(
+                depth_image.handle,
+                depth_image.format,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                vk::PipelineStageFlags2::TOP_OF_PIPE,
+                vk::PipelineStageFlags2::EARLY_FRAGMENT_TESTS,
+            );
+
+            let mut dependencies = vec![color_barrier, depth_barrier];
+            if let Some(color_image) = self.color_images.get(swapchain_image_index) {
+                dependencies.push(vulkan::layout_transition_barrier(
+                    color_image.handle,
+                    color_image.format,
+                    vk::ImageLayout::UNDEFINED,
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                    vk::PipelineStageFlags2::TOP_OF_PIPE,
+                    vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+                ));
+            }
             let dependency_info = vk::DependencyInfo {
                 image_memory_barrier_count: dependencies.len() as u32,
                 p_image_memory_barriers: dependencies.as_ptr(),
@@ -574,11 +954,24 @@ impl RenderContext {
                 store_op: vk::AttachmentStoreOp::STORE,
                 clear_value: vk::ClearValue {
                     color: vk::ClearColorValue {
-                        float32: [0.0, 0.0, 0.0, 0.0],
+                        float32: self.clear_color,
                     },
                 },
                 ..Default::default()
             };
+            // with msaa the samples are rendered into the color image and only the resolved
+            // result is written to the swapchain image
+            let color_attachment_info = match self.color_images.get(swapchain_image_index) {
+                Some(color_image) => vk::RenderingAttachmentInfo {
+                    image_view: color_image.view,
+                    store_op: vk::AttachmentStoreOp::DONT_CARE,
+                    resolve_mode: vk::ResolveModeFlags::AVERAGE,
+                    resolve_image_view: *swapchain_image_view,
+                    resolve_image_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                    ..color_attachment_info
+                },
+                None => color_attachment_info,
+            };
 
             let depth_image = self.depth_images.get(swapchain_image_index).unwrap();
             let depth_attachment_info = ash::vk::RenderingAttachmentInfo {
@@ -588,7 +981,7 @@ impl RenderContext {
                 store_op: vk::AttachmentStoreOp::STORE,
                 clear_value: vk::ClearValue {
                     depth_stencil: vk::ClearDepthStencilValue {
-                        depth: 1.0,
+                        depth: self.clear_depth,
                         stencil: 0,
                     },
                 },
@@ -596,10 +989,8 @@ impl RenderContext {
             };
 
             let rendering_info = ash::vk::RenderingInfo {
-                render_area: vk::Rect2D {
-                    offset: vk::Offset2D { x: 0, y: 0 },
-                    extent: *self.swapchain.get_extent(),
-                },
+                flags: rendering_flags,
+                render_area: self.get_scissor(),
                 layer_count: 1,
                 view_mask: 0,
                 color_attachment_count: 1,
@@ -608,25 +999,18 @@ impl RenderContext {
                 ..Default::default()
             };
 
-            let viewport = ash::vk::Viewport {
-                x: 0.0,
-                y: 0.0,
-                width: self.swapchain.get_extent().width as f32,
-                height: self.swapchain.get_extent().height as f32,
-                min_depth: 0.0,
-                max_depth: 1.0,
-            };
-            let scissor = vk::Rect2D {
-                offset: vk::Offset2D { x: 0, y: 0 },
-                extent: *self.swapchain.get_extent(),
-            };
             unsafe {
                 self.device
                     .cmd_begin_rendering(*command_buffer, &rendering_info);
 
-                self.device
-                    .cmd_set_viewport(*command_buffer, 0, &[viewport]);
-                self.device.cmd_set_scissor(*command_buffer, 0, &[scissor]);
+                // only vkCmdExecuteCommands may be recorded into a rendering begun for secondaries
+                if !rendering_flags.contains(vk::RenderingFlags::CONTENTS_SECONDARY_COMMAND_BUFFERS)
+                {
+                    self.device
+                        .cmd_set_viewport(*command_buffer, 0, &[self.get_viewport()]);
+                    self.device
+                        .cmd_set_scissor(*command_buffer, 0, &[self.get_scissor()]);
+                }
             };
         }
 
@@ -639,23 +1023,14 @@ impl RenderContext {
 
         // Barrier to transition for pres
         {
-            let dependencies = [vk::ImageMemoryBarrier2 {
-                src_stage_mask: vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
-                src_access_mask: vk::AccessFlags2::COLOR_ATTACHMENT_WRITE,
-                dst_stage_mask: vk::PipelineStageFlags2::BOTTOM_OF_PIPE,
-                dst_access_mask: vk::AccessFlags2::empty(),
-                old_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-                new_layout: vk::ImageLayout::PRESENT_SRC_KHR,
-                image: *self.swapchain.get_image(swapchain_image_index).unwrap(),
-                subresource_range: vk::ImageSubresourceRange {
-                    aspect_mask: vk::ImageAspectFlags::COLOR,
-                    base_mip_level: 0,
-                    level_count: 1,
-                    base_array_layer: 0,
-                    layer_count: 1,
-                },
-                ..Default::default()
-            }];
+            let dependencies = [vulkan::layout_transition_barrier(
+                *self.swapchain.get_image(swapchain_image_index).unwrap(),
+                self.swapchain.get_format(),
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                vk::ImageLayout::PRESENT_SRC_KHR,
+                vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+                vk::PipelineStageFlags2::BOTTOM_OF_PIPE,
+            )];
             let dependency_info = vk::DependencyInfo {
                 image_memory_barrier_count: dependencies.len() as u32,
                 p_image_memory_barriers: dependencies.as_ptr(),
@@ -669,6 +1044,14 @@ impl RenderContext {
         }
 
         unsafe {
+            if let Some(queries) = self.timestamp_queries.get(self.index) {
+                queries.cmd_write_timestamp(
+                    *command_buffer,
+                    vk::PipelineStageFlags2::BOTTOM_OF_PIPE,
+                    1,
+                )?;
+            }
+
             self.device
                 .end_command_buffer(*command_buffer)
                 .inspect_err(|e| tracing::error!("{}", e))?;
@@ -699,6 +1082,7 @@ impl RenderContext {
                     *self.command_buffer_executed.get(self.index).unwrap(),
                 )?
             };
+            self.queries_submitted[self.index] = true;
 
             let present_wait_semaphores = signal_semaphores;
             let present_info = vk::PresentInfoKHR {
@@ -709,16 +1093,78 @@ impl RenderContext {
                 p_image_indices: &(swapchain_image_index as u32),
                 ..Default::default()
             };
-            unsafe { self.device.queue_present(&present_info)? };
+            let present_result = unsafe { self.device.queue_present(&present_info) };
+
+            self.index += 1;
+            self.index %=
+                vulkan::frames_in_flight(self.swapchain.get_present_mode()).min(MAX_FRAME_COUNT);
+
+            match present_result {
+                Ok(false) if !acquire_suboptimal => Ok(()),
+                Ok(_) | Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                    Err(vulkan::result::Error::SwapchainOutOfDate)
+                }
+                Err(e) => Err(e.into()),
+            }
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_pipeline() {
+        let mut pipelines = Vec::<(vk::Format, Rc<u32>)>::new();
+        let mut created = 0;
 
-        self.index += 1;
-        let max_frames = match self.swapchain.get_present_mode() {
-            vk::PresentModeKHR::MAILBOX => 3,
-            _ => 2,
+        let mut create = |format| {
+            get_or_create_pipeline(&mut pipelines, format, || {
+                created += 1;
+                Ok(created)
+            })
         };
-        self.index %= max_frames;
 
-        Ok(())
+        let first = create(vk::Format::B8G8R8A8_SRGB).unwrap();
+        let second = create(vk::Format::B8G8R8A8_SRGB).unwrap();
+        assert!(Rc::ptr_eq(&first, &second));
+
+        let other = create(vk::Format::R8G8B8A8_UNORM).unwrap();
+        assert!(!Rc::ptr_eq(&first, &other));
+
+        assert_eq!(created, 2);
+        assert_eq!(pipelines.len(), 2);
+    }
+
+    #[test]
+    fn entry_point_selection() {
+        let available = ["main", "vs_main"];
+
+        let name = select_entry_point(None, "main", &available).unwrap();
+        assert_eq!(name.to_str(), Ok("main"));
+
+        let name = select_entry_point(Some("vs_main"), "main", &available).unwrap();
+        assert_eq!(name.to_str(), Ok("vs_main"));
+
+        match select_entry_point(Some("ps_main"), "main", &available) {
+            Err(crate::Error::EntryPointNotFound(name)) => assert_eq!(name.as_ref(), "ps_main"),
+            r => panic!("expected EntryPointNotFound, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn aspect_ratio() {
+        let extent = vk::Extent2D {
+            width: 1920,
+            height: 1080,
+        };
+        assert_eq!(aspect_ratio_of(&extent), 16.0 / 9.0);
+
+        let minimized = vk::Extent2D {
+            width: 1920,
+            height: 0,
+        };
+        assert_eq!(aspect_ratio_of(&minimized), 1.0);
     }
 }
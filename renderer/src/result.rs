@@ -2,6 +2,28 @@
 pub enum Error {
     VulkanError(vulkan::result::Error),
     ExpectedUniformBufferView,
+    ExpectedDescriptorBufferView,
+    BufferViewTooSmall {
+        data_size: u64,
+        view_size: u64,
+    },
+    UnknownUniform(Box<str>),
+    DescriptorTypeMismatch {
+        name: Box<str>,
+        descriptor_type: ash::vk::DescriptorType,
+    },
+    DescriptorArrayOutOfRange {
+        name: Box<str>,
+        first_element: u32,
+        count: u32,
+        descriptor_count: u32,
+    },
+    ImageReadbackFailed,
+    EmptyImageExtent {
+        width: u32,
+        height: u32,
+    },
+    EntryPointNotFound(Box<str>),
     NotAdded,
 }
 
@@ -9,6 +31,47 @@ impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::VulkanError(e) => write!(f, "VulkanError({})", e),
+            Self::ExpectedUniformBufferView => {
+                write!(f, "Expected a uniform or dynamic uniform buffer view")
+            }
+            Self::ExpectedDescriptorBufferView => {
+                write!(f, "Expected a uniform or storage buffer view")
+            }
+            Self::BufferViewTooSmall {
+                data_size,
+                view_size,
+            } => write!(
+                f,
+                "{} bytes don't fit in a buffer view of {} bytes",
+                data_size, view_size
+            ),
+            Self::UnknownUniform(name) => write!(f, "No uniform named {}", name),
+            Self::ImageReadbackFailed => write!(f, "Could not read back the rendered image"),
+            Self::EmptyImageExtent { width, height } => {
+                write!(f, "Can't render to a {}x{} image", width, height)
+            }
+            Self::EntryPointNotFound(name) => write!(f, "No entry point named {}", name),
+            Self::DescriptorTypeMismatch {
+                name,
+                descriptor_type,
+            } => write!(
+                f,
+                "Uniform {} has descriptor type {:?}",
+                name, descriptor_type
+            ),
+            Self::DescriptorArrayOutOfRange {
+                name,
+                first_element,
+                count,
+                descriptor_count,
+            } => write!(
+                f,
+                "Elements {}..{} are out of range for uniform {} with {} descriptors",
+                first_element,
+                first_element.saturating_add(*count),
+                name,
+                descriptor_count
+            ),
             _ => write!(f, "Error type not added yet"),
         }
     }
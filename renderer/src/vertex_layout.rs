@@ -0,0 +1,103 @@
+use ash::vk;
+
+// build.rs emits these from the reflected vertex shader inputs
+pub(crate) struct VertexAttributeLayout {
+    pub location: u32,
+    pub format: vk::Format,
+    pub offset: u32,
+}
+
+pub(crate) struct VertexBindingLayout {
+    pub binding: u32,
+    pub stride: u32,
+    pub input_rate: vk::VertexInputRate,
+    pub attributes: &'static [VertexAttributeLayout],
+}
+
+pub(crate) fn vertex_input(layout: &[VertexBindingLayout]) -> vulkan::VertexInputBuilder {
+    let mut builder = vulkan::VertexInputBuilder::default();
+
+    for binding_layout in layout {
+        let binding = builder.add_binding(binding_layout.stride, binding_layout.input_rate);
+        // add_binding numbers bindings in order
+        debug_assert_eq!(binding, binding_layout.binding);
+
+        for attribute in binding_layout.attributes {
+            builder.add_attribute(
+                binding,
+                attribute.location,
+                attribute.format,
+                attribute.offset,
+            );
+        }
+    }
+
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::vertex_input;
+    use ash::vk;
+
+    #[test]
+    fn reflected_vertex_layout() {
+        let builder = vertex_input(crate::VERTEX_LAYOUT_SHADER_VERT);
+
+        let bindings: Vec<_> = builder
+            .get_bindings()
+            .iter()
+            .map(|b| (b.binding, b.stride, b.input_rate))
+            .collect();
+        assert_eq!(
+            bindings,
+            [
+                (
+                    crate::VERTEX_BINDING,
+                    std::mem::size_of::<crate::ShaderVertVertex>() as u32,
+                    vk::VertexInputRate::VERTEX
+                ),
+                (
+                    crate::INSTANCE_BINDING,
+                    std::mem::size_of::<crate::ShaderVertInstance>() as u32,
+                    vk::VertexInputRate::INSTANCE
+                ),
+            ]
+        );
+
+        let attributes: Vec<_> = builder
+            .get_attributes()
+            .iter()
+            .map(|a| (a.location, a.binding, a.format, a.offset))
+            .collect();
+        assert_eq!(
+            attributes,
+            [
+                (
+                    0,
+                    crate::VERTEX_BINDING,
+                    vk::Format::R32G32B32_SFLOAT,
+                    std::mem::offset_of!(crate::ShaderVertVertex, position) as u32
+                ),
+                (
+                    1,
+                    crate::VERTEX_BINDING,
+                    vk::Format::R32G32_SFLOAT,
+                    std::mem::offset_of!(crate::ShaderVertVertex, tex_coord) as u32
+                ),
+                (
+                    2,
+                    crate::VERTEX_BINDING,
+                    vk::Format::R32G32B32_SFLOAT,
+                    std::mem::offset_of!(crate::ShaderVertVertex, normal) as u32
+                ),
+                (
+                    3,
+                    crate::INSTANCE_BINDING,
+                    vk::Format::R32G32B32_SFLOAT,
+                    std::mem::offset_of!(crate::ShaderVertInstance, instance_offset) as u32
+                ),
+            ]
+        );
+    }
+}
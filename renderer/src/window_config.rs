@@ -0,0 +1,55 @@
+use winit::dpi::PhysicalSize;
+use winit::window::WindowAttributes;
+
+#[derive(Debug, Clone)]
+pub struct WindowConfig {
+    pub title: String,
+    // when either dimension is None the platform picks the window size
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub resizable: bool,
+    pub decorations: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            title: String::from("dlk-objviewer"),
+            width: None,
+            height: None,
+            resizable: true,
+            decorations: true,
+        }
+    }
+}
+
+impl WindowConfig {
+    pub fn window_attributes(&self) -> WindowAttributes {
+        let attributes = WindowAttributes::default()
+            .with_title(self.title.as_str())
+            .with_resizable(self.resizable)
+            .with_decorations(self.decorations);
+
+        match (self.width, self.height) {
+            (Some(width), Some(height)) => {
+                attributes.with_inner_size(PhysicalSize::new(width, height))
+            }
+            _ => attributes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_matches_winit_defaults() {
+        let attributes = WindowConfig::default().window_attributes();
+
+        assert_eq!(attributes.title, "dlk-objviewer");
+        assert_eq!(attributes.inner_size, None);
+        assert!(attributes.resizable);
+        assert!(attributes.decorations);
+    }
+}
@@ -1,6 +1,6 @@
 use core::f32;
 
-use math::{Identity, Mat4, Quat, RigidTransform, Vec3, Vec4};
+use math::{Identity, Mat4, Quat, Ray, RigidTransform, Vec3};
 
 use crate::{WORLD_FORWARDS, WORLD_RIGHT, WORLD_UP};
 
@@ -11,6 +11,9 @@ pub struct Camera {
     aspect_ratio: f32,
     near: f32,
     far: f32,
+    target: Option<RigidTransform>,
+    // higher values make the camera reach its target faster. 0 disables smoothing.
+    pub smoothing: f32,
 }
 
 impl Default for Camera {
@@ -22,6 +25,8 @@ impl Default for Camera {
             aspect_ratio: 1.0,
             near: 0.1,
             far: 1000.0,
+            target: None,
+            smoothing: 0.0,
         }
     }
 }
@@ -54,6 +59,8 @@ impl Camera {
             aspect_ratio,
             near: 0.1,
             far: 1000.0,
+            target: None,
+            smoothing: 0.0,
         }
     }
     pub fn set_aspect_ratio(&mut self, new_aspect_ratio: f32) {
@@ -87,6 +94,32 @@ impl Camera {
         let pitch = math::Quat::unit_from_angle_axis(pitch_angle, WORLD_RIGHT);
         self.transform.rotate_local(pitch);
     }
+    pub fn set_target(&mut self, position: Vec3<f32>, orientation: Quat) {
+        self.target = Some(RigidTransform::new(position, orientation));
+    }
+    pub fn clear_target(&mut self) {
+        self.target = None;
+    }
+    // moves the camera towards its target. dt is in seconds.
+    pub fn update(&mut self, dt: f32) {
+        let target = match &self.target {
+            Some(t) => t,
+            None => return,
+        };
+
+        let t = if self.smoothing > 0.0 {
+            1.0 - (-self.smoothing * dt).exp()
+        } else {
+            1.0
+        };
+
+        self.transform.position = self.transform.position.lerp(target.position, t);
+        self.transform.orientation = self
+            .transform
+            .orientation
+            .slerp(target.orientation, t)
+            .normalized();
+    }
     #[inline]
     pub const fn move_global(&mut self, offset: Vec3<f32>) {
         self.transform.translate_global(offset);
@@ -97,11 +130,7 @@ impl Camera {
     }
     #[inline]
     pub const fn get_view_matrix(&self) -> Mat4<f32> {
-        let inv = self.transform.inv();
-        let t = inv.get_translation_matrix();
-        let r = inv.get_rotation_matrix();
-
-        r.mul(&t)
+        self.transform.inverse().as_mat4()
     }
     #[inline]
     pub fn get_projection_matrix(&self) -> Mat4<f32> {
@@ -119,30 +148,37 @@ impl Camera {
             FROM_WORLD.mul(&TO_VK).into_mat4(1.0)
         };
 
-        let n = self.near;
-        let f = self.far;
-        const R: f32 = vulkan::VK_VIEW_VOLUME_RIGHT;
-        const L: f32 = vulkan::VK_VIEW_VOLUME_LEFT;
-        const T: f32 = vulkan::VK_VIEW_VOLUME_TOP;
-        const B: f32 = vulkan::VK_VIEW_VOLUME_BOTTOM;
+        let p = Mat4::perspective(
+            self.fov_y.to_radians(),
+            self.aspect_ratio,
+            self.near,
+            self.far,
+        );
 
+        p.mul(&WORLD_TO_VK)
+    }
+    // ndc_x and ndc_y are in [-1, 1] with y pointing down, matching get_projection_matrix.
+    // The ray starts at the camera, so the [0, 1] depth range doesn't matter.
+    pub fn screen_ray(&self, ndc_x: f32, ndc_y: f32) -> Ray {
         let half_tan = (self.fov_y.to_radians() / 2.0).tan();
 
-        let p = Mat4::from_cols(
-            Vec4::new(1.0 / (self.aspect_ratio * half_tan), 0.0, 0.0, 0.0),
-            Vec4::new(0.0, -1.0 / half_tan, 0.0, 0.0),
-            Vec4::new((R + L) / (R - L), (T + B) / (T - B), -f / (f - n), -1.0),
-            Vec4::new(0.0, 0.0, -f * n / (f - n), 0.0),
-        );
+        let orientation = &self.transform.orientation;
+        let forward = orientation.rotate_vec(WORLD_FORWARDS);
+        let right = orientation.rotate_vec(WORLD_RIGHT);
+        let up = orientation.rotate_vec(WORLD_UP);
 
-        p.mul(&WORLD_TO_VK)
+        let direction = forward
+            .add(right.scaled(ndc_x * self.aspect_ratio * half_tan))
+            .add(up.scaled(-ndc_y * half_tan));
+
+        Ray::new(self.transform.position, direction)
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::{Camera, constants::WORLD_FORWARDS, constants::WORLD_RIGHT};
-    use math::Vec3;
+    use crate::{Camera, constants::WORLD_FORWARDS, constants::WORLD_RIGHT, constants::WORLD_UP};
+    use math::{Quat, Vec3, Vec4};
     fn approx_eq_f32(a: f32, b: f32, eps: f32) -> bool {
         (a - b).abs() < eps
     }
@@ -186,4 +222,60 @@ mod test {
             true
         );
     }
+
+    #[test]
+    fn update_converges_to_target() {
+        let mut c = Camera {
+            smoothing: 10.0,
+            ..Default::default()
+        };
+
+        let position = Vec3::new(4.0, -2.0, 8.0);
+        let orientation = Quat::unit_from_angle_axis(std::f32::consts::FRAC_PI_2, WORLD_UP);
+        c.set_target(position, orientation);
+
+        let mut last_distance = c.transform.position.sub(position).length();
+        for _ in 0..120 {
+            c.update(1.0 / 60.0);
+
+            let distance = c.transform.position.sub(position).length();
+            assert!(distance <= last_distance);
+            last_distance = distance;
+        }
+
+        assert!(approx_eq_vec3(c.transform.position, position));
+        assert!(approx_eq_vec3(
+            c.transform.orientation.rotate_vec(WORLD_FORWARDS),
+            orientation.rotate_vec(WORLD_FORWARDS)
+        ));
+    }
+
+    #[test]
+    fn screen_ray() {
+        let mut c = Camera::new(
+            60.0,
+            16.0 / 9.0,
+            Vec3::new(1.0, 2.0, -3.0),
+            Vec3::new(1.0, 0.5, 1.0),
+        );
+        c.rotate(0.3, 0.2);
+
+        let center = c.screen_ray(0.0, 0.0);
+        assert!(approx_eq_vec3(center.origin, c.transform.position));
+        assert!(approx_eq_vec3(
+            center.direction,
+            c.transform.orientation.rotate_vec(WORLD_FORWARDS)
+        ));
+
+        // points along the ray project back onto the same ndc coordinates
+        let view_proj = c.get_projection_matrix().mul(&c.get_view_matrix());
+        for (x, y) in [(0.5, -0.75), (-0.9, 0.25)] {
+            let p = c.screen_ray(x, y).at(5.0);
+            let p = Vec4::new(p.x(), p.y(), p.z(), 1.0);
+
+            let w = view_proj.r3().dot(&p);
+            assert!(approx_eq_f32(view_proj.r0().dot(&p) / w, x, 0.0001));
+            assert!(approx_eq_f32(view_proj.r1().dot(&p) / w, y, 0.0001));
+        }
+    }
 }
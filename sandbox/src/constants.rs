@@ -3,3 +3,13 @@ use math::Vec3;
 pub(crate) const WORLD_RIGHT: Vec3<f32> = Vec3::new(1.0, 0.0, 0.0);
 pub(crate) const WORLD_UP: Vec3<f32> = Vec3::new(0.0, 1.0, 0.0);
 pub(crate) const WORLD_FORWARDS: Vec3<f32> = Vec3::new(0.0, 0.0, 1.0);
+
+// how often camera smoothing and movement are updated
+pub(crate) const FIXED_STEP: std::time::Duration =
+    std::time::Duration::from_nanos(1_000_000_000 / 120);
+
+// world units per second
+pub(crate) const CAMERA_SPEED: f32 = 2.0;
+
+// linear rgba, the swapchain formats are srgb
+pub(crate) const CLEAR_COLOR: [f32; 4] = [0.02, 0.03, 0.05, 1.0];
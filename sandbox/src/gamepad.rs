@@ -0,0 +1,69 @@
+use gilrs::{Axis, EventType, Gilrs};
+use math::Vec3;
+
+use crate::input::apply_dead_zone;
+use crate::{WORLD_FORWARDS, WORLD_RIGHT};
+
+// stick deflections shorter than this read as centered
+pub(crate) const GAMEPAD_DEAD_ZONE: f32 = 0.15;
+// radians per second at full right stick deflection
+pub(crate) const GAMEPAD_LOOK_SENSITIVITY: f32 = 2.5;
+
+pub struct GamepadInput {
+    // in camera space, no longer than 1
+    pub movement: Vec3<f32>,
+    // radians, in the same directions as the mouse deltas passed to Camera::rotate
+    pub look: (f32, f32),
+}
+
+pub struct Gamepads {
+    gilrs: Gilrs,
+}
+
+impl Gamepads {
+    // None if the platform has no gamepad backend
+    pub fn new() -> Option<Self> {
+        let gilrs = Gilrs::new()
+            .inspect_err(|e| tracing::warn!("gamepad support disabled: {e}"))
+            .ok()?;
+
+        Some(Self { gilrs })
+    }
+    // call once per frame. gilrs only updates the stick state while its events are drained.
+    // reads the first connected gamepad, dt is in seconds
+    pub fn poll(&mut self, dt: f32) -> Option<GamepadInput> {
+        while let Some(event) = self.gilrs.next_event() {
+            match event.event {
+                EventType::Connected => {
+                    tracing::info!("gamepad connected: {}", self.gilrs.gamepad(event.id).name());
+                }
+                EventType::Disconnected => {
+                    tracing::info!("gamepad disconnected");
+                }
+                _ => {}
+            }
+        }
+
+        let (_, gamepad) = self.gilrs.gamepads().next()?;
+
+        let (move_x, move_y) = apply_dead_zone(
+            gamepad.value(Axis::LeftStickX),
+            gamepad.value(Axis::LeftStickY),
+            GAMEPAD_DEAD_ZONE,
+        );
+        let (look_x, look_y) = apply_dead_zone(
+            gamepad.value(Axis::RightStickX),
+            gamepad.value(Axis::RightStickY),
+            GAMEPAD_DEAD_ZONE,
+        );
+
+        // stick y points up, mouse y points down
+        let speed = GAMEPAD_LOOK_SENSITIVITY * dt;
+        Some(GamepadInput {
+            movement: WORLD_RIGHT
+                .scaled(move_x)
+                .add(WORLD_FORWARDS.scaled(move_y)),
+            look: (look_x * speed, -look_y * speed),
+        })
+    }
+}
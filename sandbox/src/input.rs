@@ -0,0 +1,119 @@
+use std::collections::HashSet;
+
+use math::{Vec3, Zero};
+use winit::keyboard::KeyCode;
+
+use crate::{WORLD_FORWARDS, WORLD_RIGHT, WORLD_UP};
+
+const MOVEMENT_KEYS: [(KeyCode, Vec3<f32>); 6] = [
+    (KeyCode::KeyW, WORLD_FORWARDS),
+    (KeyCode::KeyS, Vec3::ZERO.sub(WORLD_FORWARDS)),
+    (KeyCode::KeyD, WORLD_RIGHT),
+    (KeyCode::KeyA, Vec3::ZERO.sub(WORLD_RIGHT)),
+    (KeyCode::Space, WORLD_UP),
+    (KeyCode::ControlLeft, Vec3::ZERO.sub(WORLD_UP)),
+];
+
+// tracks the held movement keys so the camera moves smoothly instead of on key repeat
+#[derive(Default)]
+pub struct MovementKeys {
+    held: HashSet<KeyCode>,
+}
+
+impl MovementKeys {
+    // returns false if the key isn't a movement key
+    pub fn set(&mut self, key: KeyCode, pressed: bool) -> bool {
+        if !MOVEMENT_KEYS.iter().any(|(k, _)| *k == key) {
+            return false;
+        }
+
+        if pressed {
+            self.held.insert(key);
+        } else {
+            self.held.remove(&key);
+        }
+        true
+    }
+    // key releases are missed while the window is unfocused
+    pub fn clear(&mut self) {
+        self.held.clear();
+    }
+    // in camera space, normalized so diagonals aren't faster. zero if nothing is held
+    pub fn direction(&self) -> Vec3<f32> {
+        let sum = MOVEMENT_KEYS
+            .iter()
+            .filter(|(k, _)| self.held.contains(k))
+            .fold(Vec3::ZERO, |acc, (_, dir)| acc.add(*dir));
+
+        if sum.length_squared() < 1e-6 {
+            Vec3::ZERO
+        } else {
+            sum.normalized()
+        }
+    }
+}
+
+// radial, so diagonals aren't cut off. the rest of the range is rescaled to still start at 0
+#[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+pub fn apply_dead_zone(x: f32, y: f32, dead_zone: f32) -> (f32, f32) {
+    let length = (x * x + y * y).sqrt();
+    if length <= dead_zone {
+        return (0.0, 0.0);
+    }
+
+    let scale = ((length - dead_zone) / (1.0 - dead_zone)).min(1.0) / length;
+    (x * scale, y * scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MovementKeys, apply_dead_zone};
+    use crate::constants::{WORLD_FORWARDS, WORLD_RIGHT};
+    use math::{Vec3, Zero};
+    use winit::keyboard::KeyCode;
+
+    #[test]
+    fn movement_keys() {
+        let mut keys = MovementKeys::default();
+        assert!(!keys.set(KeyCode::KeyQ, true));
+        assert_eq!(keys.direction(), Vec3::ZERO);
+
+        assert!(keys.set(KeyCode::KeyW, true));
+        assert_eq!(keys.direction(), WORLD_FORWARDS);
+
+        // diagonals are normalized
+        keys.set(KeyCode::KeyD, true);
+        let expected = WORLD_FORWARDS.add(WORLD_RIGHT).normalized();
+        assert!(keys.direction().sub(expected).length_squared() < 1e-6);
+
+        // opposite keys cancel out
+        keys.set(KeyCode::KeyA, true);
+        assert_eq!(keys.direction(), WORLD_FORWARDS);
+
+        keys.set(KeyCode::KeyW, false);
+        assert_eq!(keys.direction(), Vec3::ZERO);
+
+        keys.clear();
+        keys.set(KeyCode::KeyS, true);
+        assert_eq!(keys.direction(), Vec3::ZERO.sub(WORLD_FORWARDS));
+    }
+
+    #[test]
+    fn dead_zone() {
+        assert_eq!(apply_dead_zone(0.1, -0.1, 0.2), (0.0, 0.0));
+
+        // the edge of the dead zone maps to 0 and full deflection stays at 1
+        let (x, y) = apply_dead_zone(0.0, 0.2, 0.2);
+        assert!(x == 0.0 && y.abs() < 1e-6);
+        assert_eq!(apply_dead_zone(-1.0, 0.0, 0.2), (-1.0, 0.0));
+
+        // halfway through the live range keeps the direction
+        let (x, y) = apply_dead_zone(0.6 / 2f32.sqrt(), 0.6 / 2f32.sqrt(), 0.2);
+        assert!((x - y).abs() < 1e-6);
+        assert!(((x * x + y * y).sqrt() - 0.5).abs() < 1e-6);
+
+        // square gamepads can report corners past 1
+        let (x, y) = apply_dead_zone(1.0, 1.0, 0.2);
+        assert!(((x * x + y * y).sqrt() - 1.0).abs() < 1e-6);
+    }
+}
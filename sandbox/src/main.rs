@@ -1,10 +1,14 @@
 mod camera;
 mod constants;
+#[cfg(feature = "gamepad")]
+mod gamepad;
+mod input;
 mod result;
 
 use camera::Camera;
-use constants::{WORLD_FORWARDS, WORLD_RIGHT, WORLD_UP};
+use constants::{CAMERA_SPEED, CLEAR_COLOR, FIXED_STEP, WORLD_FORWARDS, WORLD_RIGHT, WORLD_UP};
 use image::DynamicImage;
+use input::MovementKeys;
 use renderer::{MaterialUBO, ShaderVertVertex};
 use result::{Error, Result};
 
@@ -36,11 +40,23 @@ enum ApplicationState {
 struct Application {
     state: ApplicationState,
     mouse_sensitivity: f64,
+    movement_keys: MovementKeys,
+    #[cfg(feature = "gamepad")]
+    gamepads: Option<gamepad::Gamepads>,
+    window_config: renderer::WindowConfig,
     focused_window: Option<WindowId>,
     active_window: Option<WindowId>,
-    windows: HashMap<WindowId, (renderer::RenderContext, Window, Camera)>,
+    // each window ticks its own clock, since every window gets its own RedrawRequested
+    windows: HashMap<WindowId, (renderer::RenderContext, Window, Camera, renderer::Clock)>,
     renderer: renderer::Renderer,
-    draw_infos: Box<[(vulkan::VertexBV, vulkan::IndexBV, u32)]>,
+    draw_infos: Box<
+        [(
+            vulkan::BufferView,
+            vulkan::BufferView,
+            vulkan::BufferView,
+            u32,
+        )],
+    >,
     model_transform: math::AffineTransform,
     global_light_direction: Vec3<f32>,
     global_light_color: Vec4<f32>,
@@ -69,6 +85,7 @@ impl Application {
     }
     fn new(
         mouse_sensitivity: f64,
+        window_config: renderer::WindowConfig,
         derive_normals: bool,
         obj_to_world: math::Mat3<f32>,
         model_path: &std::path::Path,
@@ -397,16 +414,44 @@ impl Application {
             .collect();
         mesh_ubo_buffer_data[0] = (plane_transform, 0);
 
-        let renderer = renderer::Renderer::new(
+        let mut renderer = renderer::Renderer::new(
             debug_enabled,
             display_handle,
             mesh_ubo_buffer_data.len() as u64,
             &texture_data,
             &materials,
         )?;
+        renderer
+            .load_pipeline_cache(std::env::temp_dir().join("dlk-model-viewer.pipeline-cache"))?;
+
+        let mut draw_infos = Vec::<(
+            vulkan::BufferView,
+            vulkan::BufferView,
+            vulkan::BufferView,
+            u32,
+        )>::new();
+        // the plane comes first and is tiled PLANE_TILES x PLANE_TILES times, everything
+        // else is drawn once at its own origin
+        const PLANE_TILES: usize = 10;
+        let plane_instances: Box<[renderer::ShaderVertInstance]> = (0..PLANE_TILES * PLANE_TILES)
+            .map(|i| {
+                // the plane spans -1..1 in model space, so tiles are 2 apart
+                let center = (PLANE_TILES - 1) as f32 / 2.0;
+                let x = ((i % PLANE_TILES) as f32 - center) * 2.0;
+                let z = ((i / PLANE_TILES) as f32 - center) * 2.0;
+                renderer::ShaderVertInstance {
+                    instance_offset: WORLD_RIGHT
+                        .scaled(x)
+                        .add(WORLD_FORWARDS.scaled(z))
+                        .into_arr(),
+                }
+            })
+            .collect();
+        let single_instance = [renderer::ShaderVertInstance {
+            instance_offset: [0.0; 3],
+        }];
 
-        let mut draw_infos = Vec::<(vulkan::VertexBV, vulkan::IndexBV, u32)>::new();
-        for (vb_data, ib_data, mesh_idx) in mesh_data.into_iter() {
+        for (i, (vb_data, ib_data, mesh_idx)) in mesh_data.into_iter().enumerate() {
             if vb_data.len() == 0 || ib_data.len() == 0 {
                 continue;
             }
@@ -417,7 +462,17 @@ impl Application {
                 )
             };
 
-            let vb = renderer.create_vertex_buffer(&vb_data_u8, vb_data.len() as u32)?;
+            let instances = renderer.create_instance_buffer(if i == 0 {
+                &plane_instances
+            } else {
+                &single_instance
+            })?;
+
+            let vb = renderer.create_vertex_buffer_device_local(
+                &vb_data_u8,
+                vb_data.len() as u32,
+                instances.instance_count,
+            )?;
 
             let ib_data_u8 = unsafe {
                 std::slice::from_raw_parts(
@@ -426,14 +481,15 @@ impl Application {
                 )
             };
 
-            let ib = renderer.create_index_buffer(
+            let ib = renderer.create_index_buffer_device_local(
                 ib_data_u8,
                 vk::IndexType::UINT32,
                 ib_data.len() as u32,
                 0,
+                instances.instance_count,
             )?;
 
-            draw_infos.push((vb, ib, mesh_idx))
+            draw_infos.push((vb.into(), instances.into(), ib.into(), mesh_idx))
         }
 
         for (i, (transform, material_index)) in mesh_ubo_buffer_data.iter().enumerate() {
@@ -459,6 +515,10 @@ impl Application {
         Ok(Self {
             state,
             mouse_sensitivity,
+            movement_keys: MovementKeys::default(),
+            #[cfg(feature = "gamepad")]
+            gamepads: gamepad::Gamepads::new(),
+            window_config,
             focused_window: None,
             active_window: None,
             renderer,
@@ -480,7 +540,7 @@ impl Application {
     ) -> Result<bool> {
         use winit::event::WindowEvent;
 
-        let (context, window, camera) = self
+        let (context, window, camera, clock) = self
             .windows
             .get_mut(window_id)
             .ok_or(Error::WindowIdInvalid)?;
@@ -491,20 +551,13 @@ impl Application {
                 // unsafe { self.renderer.destroy_render_context(context) };
                 return Ok(true);
             }
-            WindowEvent::Resized(s) => {
-                unsafe { self.renderer.device.device_wait_idle() }
-                    .inspect_err(|e| tracing::error!("{e}"))
-                    .unwrap();
-
-                {
-                    let (w, h) = (s.width as f32, s.height as f32);
-                    let aspect_ratio = w / h;
-
-                    camera.set_aspect_ratio(aspect_ratio);
+            WindowEvent::Resized(_) => {
+                // nothing to do while minimized
+                if !context.resize(window)? {
+                    return Ok(false);
                 }
 
-                let new_context = self.renderer.create_render_context(window)?;
-                *context = new_context;
+                camera.set_aspect_ratio(context.aspect_ratio());
 
                 let camera_ubo = renderer::CameraUBO {
                     view: camera.get_view_matrix().into_2d_arr(),
@@ -513,6 +566,42 @@ impl Application {
                 context.update_camera(camera_ubo)?;
             }
             WindowEvent::RedrawRequested => {
+                clock.tick();
+
+                // only fly the camera of the window that grabbed the cursor
+                let grabbed = matches!(self.state, ApplicationState::CameraMode)
+                    && matches!(self.active_window, Some(id) if id == *window_id);
+                let movement = if grabbed {
+                    self.movement_keys.direction()
+                } else {
+                    Vec3::ZERO
+                };
+
+                // polled even outside camera mode so gilrs keeps draining its events
+                #[cfg(feature = "gamepad")]
+                let movement = match self
+                    .gamepads
+                    .as_mut()
+                    .and_then(|g| g.poll(clock.delta_time()))
+                {
+                    Some(input) if grabbed => {
+                        camera.rotate(input.look.0, input.look.1);
+
+                        let movement = movement.add(input.movement);
+                        if movement.length_squared() > 1.0 {
+                            movement.normalized()
+                        } else {
+                            movement
+                        }
+                    }
+                    _ => movement,
+                };
+
+                clock.step(|dt| {
+                    camera.update(dt);
+                    camera.move_local(movement.scaled(CAMERA_SPEED * dt));
+                });
+
                 let camera_ubo = renderer::CameraUBO {
                     view: camera.get_view_matrix().into_2d_arr(),
                     proj: camera.get_projection_matrix().into_2d_arr(),
@@ -521,53 +610,54 @@ impl Application {
 
                 let pipeline = context.get_pipeline();
 
-                let temp = context.index as u32 * context.per_frame_buffer_element_size;
+                let per_frame_offset = context.per_frame_dynamic_offset();
 
+                // the closure can't return errors, so they're reported after draw
+                let mut bind_result: vulkan::result::Result<()> = Ok(());
                 let record_draw_commands = |cmd: vk::CommandBuffer| unsafe {
                     pipeline.bind(cmd);
-                    {
-                        let sets = [self.renderer.descriptor_sets[0]];
-                        self.renderer.device.cmd_bind_descriptor_sets(
+                    bind_result = (|| {
+                        pipeline.cmd_bind_descriptor_sets(
                             cmd,
-                            self.renderer.pipeline_layout.bind_point,
-                            self.renderer.pipeline_layout.handle,
                             0,
-                            &sets,
-                            &[temp],
-                        );
-                    }
-                    {
-                        let sets = [self.renderer.descriptor_sets[2]];
-                        self.renderer.device.cmd_bind_descriptor_sets(
+                            &[self.renderer.descriptor_sets[0]],
+                            &[per_frame_offset],
+                        )?;
+                        pipeline.cmd_bind_descriptor_sets(
                             cmd,
-                            self.renderer.pipeline_layout.bind_point,
-                            self.renderer.pipeline_layout.handle,
                             2,
-                            &sets,
+                            &[self.renderer.descriptor_sets[2]],
                             &[],
-                        );
-                    }
+                        )?;
 
-                    for (vb, ib, mesh_idx) in self.draw_infos.iter() {
-                        {
-                            let sets = [self.renderer.descriptor_sets[1]];
-                            self.renderer.device.cmd_bind_descriptor_sets(
+                        for (vb, instances, ib, mesh_idx) in self.draw_infos.iter() {
+                            pipeline.cmd_bind_descriptor_sets(
                                 cmd,
-                                self.renderer.pipeline_layout.bind_point,
-                                self.renderer.pipeline_layout.handle,
                                 1,
-                                &sets,
+                                &[self.renderer.descriptor_sets[1]],
                                 &[*mesh_idx
                                     * self.renderer.model_transform_buffer_element_size as u32],
-                            );
+                            )?;
+                            vb.bind(cmd);
+                            instances.bind(cmd);
+                            ib.bind(cmd);
+                            ib.draw(cmd);
                         }
-                        vb.bind(cmd);
-                        ib.bind(cmd);
-                        ib.draw(cmd);
-                    }
+
+                        Ok(())
+                    })();
                 };
 
-                unsafe { context.draw(record_draw_commands) }?;
+                match unsafe { context.draw(record_draw_commands) } {
+                    // the camera is updated with the new aspect ratio on the next redraw
+                    Err(vulkan::result::Error::SwapchainOutOfDate) => {
+                        if context.resize(window)? {
+                            camera.set_aspect_ratio(context.aspect_ratio());
+                        }
+                    }
+                    r => r?,
+                }
+                bind_result?;
 
                 window.request_redraw();
             }
@@ -575,7 +665,7 @@ impl Application {
                 use winit::event::KeyEvent;
                 use winit::keyboard::KeyCode;
 
-                const SPEED: f32 = 0.025;
+                let pressed = event.state == winit::event::ElementState::Pressed;
                 match event {
                     KeyEvent { physical_key, .. } => match physical_key {
                         winit::keyboard::PhysicalKey::Code(c) => match c {
@@ -589,36 +679,7 @@ impl Application {
                                 }
                                 window.set_cursor_visible(true);
                             }
-                            KeyCode::KeyE => {
-                                if let ApplicationState::CameraMode = self.state {
-                                    camera.move_local(WORLD_FORWARDS.scaled(SPEED));
-                                }
-                            }
-                            KeyCode::KeyD => {
-                                if let ApplicationState::CameraMode = self.state {
-                                    camera.move_local(WORLD_FORWARDS.scaled(-SPEED));
-                                }
-                            }
-                            KeyCode::KeyF => {
-                                if let ApplicationState::CameraMode = self.state {
-                                    camera.move_local(WORLD_RIGHT.scaled(SPEED));
-                                }
-                            }
-                            KeyCode::KeyS => {
-                                if let ApplicationState::CameraMode = self.state {
-                                    camera.move_local(WORLD_RIGHT.scaled(-SPEED));
-                                }
-                            }
-                            KeyCode::Space => {
-                                if let ApplicationState::CameraMode = self.state {
-                                    camera.move_local(WORLD_UP.scaled(SPEED));
-                                }
-                            }
-                            KeyCode::ControlLeft => {
-                                if let ApplicationState::CameraMode = self.state {
-                                    camera.move_local(WORLD_UP.scaled(-SPEED));
-                                }
-                            }
+                            c if self.movement_keys.set(c, pressed) => {}
                             KeyCode::KeyO => {
                                 self.state = ApplicationState::ObjectMode;
                             }
@@ -638,6 +699,8 @@ impl Application {
             WindowEvent::Focused(b) => {
                 if b {
                     self.focused_window = Some(*window_id);
+                } else {
+                    self.movement_keys.clear();
                 }
                 // tracing::trace!("Focused!");
             }
@@ -702,8 +765,7 @@ impl ApplicationHandler for Application {
             return;
         }
 
-        let window_attributes =
-            winit::window::WindowAttributes::default().with_title("dlk-objviewer");
+        let window_attributes = self.window_config.window_attributes();
         let window = match event_loop.create_window(window_attributes) {
             Ok(w) => w,
             Err(e) => {
@@ -714,21 +776,18 @@ impl ApplicationHandler for Application {
 
         let window_id = window.id();
 
-        let context = match self.renderer.create_render_context(&window) {
+        let mut context = match self.renderer.create_render_context(&window) {
             Ok(context) => context,
             Err(e) => {
                 tracing::error!("{}", e);
                 return self.exiting(event_loop);
             }
         };
+        context.set_clear_color(CLEAR_COLOR);
         let camera = {
-            let s = window.inner_size();
-            let (w, h) = (s.width as f32, s.height as f32);
-            let aspect_ratio = w / h;
-
             Camera::new(
                 65.0,
-                aspect_ratio,
+                context.aspect_ratio(),
                 self.model_transform
                     .position
                     .add(Vec3::ZERO.sub(WORLD_FORWARDS)),
@@ -753,7 +812,10 @@ impl ApplicationHandler for Application {
             )
             .unwrap();
 
-        self.windows.insert(window_id, (context, window, camera));
+        let clock = renderer::Clock::new(FIXED_STEP);
+
+        self.windows
+            .insert(window_id, (context, window, camera, clock));
     }
 
     #[allow(unused_variables)]
@@ -765,7 +827,7 @@ impl ApplicationHandler for Application {
     ) {
         use winit::event::DeviceEvent;
 
-        let (_, _, camera) = match self.active_window {
+        let (_, _, camera, _) = match self.active_window {
             Some(id) => match self.windows.get_mut(&id) {
                 Some(x) => x,
                 None => {
@@ -801,7 +863,7 @@ impl ApplicationHandler for Application {
 
                         self.model_transform.rotate_local(qx.mul(qy));
 
-                        for (i, (_, _, idx)) in self.draw_infos.iter().enumerate().skip(1) {
+                        for (i, (_, _, _, idx)) in self.draw_infos.iter().enumerate().skip(1) {
                             let src = renderer::MeshUBO {
                                 model: self.model_transform.as_mat4().into_2d_arr(),
                                 material_index: *idx,
@@ -905,6 +967,13 @@ fn main() -> Result<()> {
             "    --mouse-sensitivity Specifies the sensitivity of the mouse. Defaults to 50.0"
         );
         println!("        may be any value from 1 to 100");
+        println!("    --title Specifies the title of the window. Defaults to dlk-objviewer.");
+        println!("    --width Specifies the width of the window. Requires --height.");
+        println!("    --height Specifies the height of the window. Requires --width.");
+        println!("    --resizable Allows the window to be resized. Defaults to true.");
+        println!("        may be one of of: <true|false>");
+        println!("    --decorations Enables window decorations. Defaults to true.");
+        println!("        may be one of of: <true|false>");
 
         return Ok(());
     }
@@ -946,6 +1015,61 @@ fn main() -> Result<()> {
         sensitivity / 50000.0
     };
 
+    let window_config = {
+        let arg_value = |name: &str| -> Option<Option<&str>> {
+            args.iter()
+                .position(|s| s == name)
+                .map(|i| args.get(i + 1).map(|x| x.as_str()))
+        };
+        let parse_bool = |name: &str, default: bool| -> Option<bool> {
+            match arg_value(name) {
+                None => Some(default),
+                Some(Some("true")) => Some(true),
+                Some(Some("false")) => Some(false),
+                _ => None,
+            }
+        };
+        let parse_dimension = |name: &str| -> Option<Option<u32>> {
+            match arg_value(name) {
+                None => Some(None),
+                Some(Some(s)) => s.parse::<u32>().ok().filter(|d| *d > 0).map(Some),
+                Some(None) => None,
+            }
+        };
+
+        let default = renderer::WindowConfig::default();
+
+        let title = match arg_value("--title") {
+            None => default.title,
+            Some(Some(s)) => String::from(s),
+            Some(None) => {
+                return print_usage();
+            }
+        };
+
+        match (
+            parse_dimension("--width"),
+            parse_dimension("--height"),
+            parse_bool("--resizable", default.resizable),
+            parse_bool("--decorations", default.decorations),
+        ) {
+            (Some(width), Some(height), Some(resizable), Some(decorations))
+                if width.is_some() == height.is_some() =>
+            {
+                renderer::WindowConfig {
+                    title,
+                    width,
+                    height,
+                    resizable,
+                    decorations,
+                }
+            }
+            _ => {
+                return print_usage();
+            }
+        }
+    };
+
     let derive_normals = {
         let idx = args.iter().enumerate().find_map(|(i, s)| {
             if s == "--derive-normals" {
@@ -1042,6 +1166,7 @@ fn main() -> Result<()> {
         let display_handle = owned_display_handle.display_handle()?;
         Application::new(
             mouse_sensitivity,
+            window_config,
             derive_normals,
             obj_to_world,
             model_path.as_path(),
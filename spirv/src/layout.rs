@@ -0,0 +1,90 @@
+use crate::module::{Module, UniformInfo};
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderStage {
+    Vertex,
+    TessellationControl,
+    TessellationEvaluation,
+    Geometry,
+    Fragment,
+    Compute,
+}
+
+impl ShaderStage {
+    // the matching VkShaderStageFlagBits
+    pub fn flag(self) -> u32 {
+        match self {
+            Self::Vertex => 0x01,
+            Self::TessellationControl => 0x02,
+            Self::TessellationEvaluation => 0x04,
+            Self::Geometry => 0x08,
+            Self::Fragment => 0x10,
+            Self::Compute => 0x20,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MergedBinding {
+    pub info: UniformInfo,
+    // every stage that uses the binding, as VkShaderStageFlags
+    pub stage_flags: u32,
+}
+
+// the bindings of every stage, sorted by set then binding
+#[derive(Debug)]
+pub struct MergedLayout {
+    pub bindings: Box<[MergedBinding]>,
+}
+
+impl MergedLayout {
+    // one more than the highest set used
+    pub fn set_count(&self) -> u32 {
+        self.bindings.last().map_or(0, |b| b.info.set + 1)
+    }
+    pub fn get_set(&self, set: u32) -> impl Iterator<Item = &MergedBinding> {
+        self.bindings.iter().filter(move |b| b.info.set == set)
+    }
+}
+
+// bindings declared by more than one stage must agree on their type, and a name can only
+// be used for one binding
+pub fn merge_stages(stages: &[(ShaderStage, &Module)]) -> Result<MergedLayout> {
+    let mut bindings = Vec::<MergedBinding>::new();
+
+    for (stage, module) in stages {
+        for info in module.get_uniform_info()? {
+            let renamed = bindings.iter().any(|b| {
+                b.info.name == info.name && (b.info.set, b.info.binding) != (info.set, info.binding)
+            });
+            if renamed {
+                return Err(Error::DuplicateUniformName(info.name));
+            }
+
+            let existing = bindings
+                .iter_mut()
+                .find(|b| b.info.set == info.set && b.info.binding == info.binding);
+
+            match existing {
+                Some(b) if b.info.storage_class != info.storage_class || b.info.ty != info.ty => {
+                    return Err(Error::ConflictingBinding {
+                        set: info.set,
+                        binding: info.binding,
+                    });
+                }
+                Some(b) => b.stage_flags |= stage.flag(),
+                None => bindings.push(MergedBinding {
+                    info,
+                    stage_flags: stage.flag(),
+                }),
+            }
+        }
+    }
+
+    bindings.sort_by_key(|b| (b.info.set, b.info.binding));
+
+    Ok(MergedLayout {
+        bindings: bindings.into_boxed_slice(),
+    })
+}
@@ -1,7 +1,14 @@
+pub mod layout;
 pub mod module;
+pub mod reflection;
 pub mod result;
 
-pub use module::{Module, StructMemberInfo, TypeInfo, UniformInfo};
+pub use layout::{MergedBinding, MergedLayout, ShaderStage, merge_stages};
+pub use module::{
+    Module, PushConstantInfo, PushConstantMember, SpecConstantInfo, SpecConstantValue,
+    StructMemberInfo, TypeInfo, UniformInfo, UniformType, VertexAttribute, VertexLayout,
+};
+pub use reflection::ReflectionReport;
 
 include!(concat!(env!("OUT_DIR"), "/opcode.rs"));
 include!(concat!(env!("OUT_DIR"), "/opkind.rs"));
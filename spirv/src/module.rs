@@ -1,14 +1,21 @@
+use crate::reflection::ReflectionReport;
 use crate::{Error, Result};
+use std::borrow::Cow;
 use std::io::Read;
 
-struct RawInstruction {
+const HEADER_WORD_COUNT: usize = 5;
+
+#[derive(Clone, Copy)]
+struct RawInstruction<'a> {
     opcode: u32,
-    operands: Box<[u32]>,
+    operands: &'a [u32],
 }
 
-pub struct Module {
+// instructions are read straight out of the word stream when needed, so a module made
+// with from_words does not copy anything
+pub struct Module<'a> {
     pub name: Box<str>,
-    instructions: Vec<RawInstruction>,
+    words: Cow<'a, [u32]>,
 }
 
 #[derive(Debug, PartialEq, PartialOrd, Ord, Eq)]
@@ -125,6 +132,47 @@ impl TypeInfo {
             _ => None,
         }
     }
+    // how many interface locations a variable of this type takes up
+    pub fn location_count(&self) -> u32 {
+        match self {
+            TypeInfo::Mat { col_count, .. } => *col_count,
+            TypeInfo::Array {
+                element_type,
+                element_count,
+            } => element_count * element_type.location_count(),
+            _ => 1,
+        }
+    }
+}
+
+// what kind of descriptor a uniform needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UniformType {
+    UniformBuffer,
+    StorageBuffer,
+    Sampler,
+    // `sampler2D`, an OpTypeSampledImage
+    CombinedImageSampler,
+    // `texture2D`, an OpTypeImage used with a separate sampler
+    SampledImage,
+    StorageImage,
+    InputAttachment,
+    Other,
+}
+
+impl std::fmt::Display for UniformType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UniformBuffer => write!(f, "uniform buffer"),
+            Self::StorageBuffer => write!(f, "storage buffer"),
+            Self::Sampler => write!(f, "sampler"),
+            Self::CombinedImageSampler => write!(f, "combined image sampler"),
+            Self::SampledImage => write!(f, "sampled image"),
+            Self::StorageImage => write!(f, "storage image"),
+            Self::InputAttachment => write!(f, "input attachment"),
+            Self::Other => write!(f, "other"),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -132,9 +180,53 @@ impl TypeInfo {
 pub struct UniformInfo {
     pub set: u32,
     pub binding: u32,
+    // the variable name, then the block name, then set{set}_binding{binding}
+    pub name: Box<str>,
     pub ty: TypeInfo,
     pub storage_class: u32,
+    pub uniform_type: UniformType,
     pub descriptor_count: u32,
+    // the size of uniform and storage buffer blocks
+    pub size: Option<u32>,
+    // true when the block ends in a runtime sized array, like `buffer { vec4 data[]; }`
+    pub is_runtime_array: bool,
+    // the stride of that runtime array's elements
+    pub element_size: Option<u32>,
+}
+
+#[derive(Debug)]
+pub struct PushConstantMember {
+    pub name: Box<str>,
+    pub offset: u32,
+    pub size: u32,
+}
+
+#[derive(Debug)]
+pub struct PushConstantInfo {
+    // the variable name, then the block name
+    pub name: Box<str>,
+    // the range the members cover, which is what vk::PushConstantRange wants
+    pub offset: u32,
+    pub size: u32,
+    pub members: Box<[PushConstantMember]>,
+}
+
+// Unknown when the type isn't a bool or a 32 bit int or float
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpecConstantValue {
+    Bool(bool),
+    Int(i32),
+    UInt(u32),
+    Float(f32),
+    Unknown,
+}
+
+#[derive(Debug)]
+pub struct SpecConstantInfo {
+    // the SpecId decoration, i.e. vk::SpecializationMapEntry::constant_id
+    pub constant_id: u32,
+    pub type_info: Option<TypeInfo>,
+    pub default_value: SpecConstantValue,
 }
 
 #[derive(Debug)]
@@ -143,57 +235,104 @@ pub struct ShaderIoInfo {
     pub location: u32,
     pub name: Box<str>,
     pub type_info: TypeInfo,
+    // STORAGE_CLASS_INPUT or STORAGE_CLASS_OUTPUT
+    pub storage_class: u32,
+}
+
+// arrays and matrices stay a single attribute with their Array/Mat type. they take up
+// location_count consecutive locations starting at location, one per element or column
+#[derive(Debug)]
+pub struct VertexAttribute {
+    pub location: u32,
+    pub location_count: u32,
+    pub name: Box<str>,
+    pub type_info: TypeInfo,
+    pub offset: u32,
+}
+
+// all attributes live in a single vertex buffer binding. vertex_layout packs them
+// tightly, vertex_layout_with_offsets uses the offsets of the host vertex struct
+#[derive(Debug)]
+pub struct VertexLayout {
+    pub attributes: Box<[VertexAttribute]>,
+    pub stride: u32,
 }
 
 #[allow(unused)]
-impl Module {
-    pub fn from_code(name: Box<str>, shader_code: &[u8]) -> Result<Self> {
-        if shader_code.len() < 4 * 5 || shader_code.len() % 4 != 0 {
+impl<'a> Module<'a> {
+    pub fn from_code(name: Box<str>, shader_code: &[u8]) -> Result<Module<'static>> {
+        if shader_code.len() < 4 * HEADER_WORD_COUNT || shader_code.len() % 4 != 0 {
             return Err(Error::InvalidFileLength(shader_code.len()));
         }
 
-        let mut chunks = shader_code.chunks_exact(4);
+        // the magic number tells us which byte order every word was written in
+        let first: [u8; 4] = shader_code[..4].try_into().unwrap();
+        let decode: fn([u8; 4]) -> u32 = if u32::from_le_bytes(first) == crate::MAGIC_NUMBER {
+            u32::from_le_bytes
+        } else if u32::from_be_bytes(first) == crate::MAGIC_NUMBER {
+            u32::from_be_bytes
+        } else {
+            return Err(Error::IncorrectMagicWord(u32::from_le_bytes(first)));
+        };
 
-        let magic = u32::from_le_bytes(chunks.next().unwrap().try_into().unwrap());
-        if magic != crate::MAGIC_NUMBER {
-            return Err(Error::IncorrectMagicWord(magic));
+        let words: Vec<u32> = shader_code
+            .chunks_exact(4)
+            .map(|c| decode(c.try_into().unwrap()))
+            .collect();
+
+        Module::new(name, Cow::Owned(words))
+    }
+    // borrows the words when they are in native byte order
+    pub fn from_words(name: Box<str>, words: &'a [u32]) -> Result<Self> {
+        if words.len() < HEADER_WORD_COUNT {
+            return Err(Error::InvalidFileLength(words.len() * 4));
         }
 
+        let words = if words[0] == crate::MAGIC_NUMBER {
+            Cow::Borrowed(words)
+        } else if words[0].swap_bytes() == crate::MAGIC_NUMBER {
+            Cow::Owned(words.iter().map(|w| w.swap_bytes()).collect())
+        } else {
+            return Err(Error::IncorrectMagicWord(words[0]));
+        };
+
+        Self::new(name, words)
+    }
+    fn new(name: Box<str>, words: Cow<'a, [u32]>) -> Result<Self> {
         // TODO: look up if versions are backwards compatible
-        let version = u32::from_le_bytes(chunks.next().unwrap().try_into().unwrap());
+        let version = words[1];
         if version > crate::SPIRV_VERSION {
             return Err(Error::InvalidVersion((version, crate::SPIRV_VERSION)));
         }
 
-        let _generator = chunks.next().unwrap();
-        let _bound = chunks.next().unwrap();
-        let _reserved = chunks.next().unwrap();
-
-        let mut instructions = Vec::<RawInstruction>::new();
-
-        while let Some(word) = chunks.next() {
-            let first_word = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
-            let word_count = (first_word >> 16) as usize;
-            let opcode = first_word & 0xFFFF;
-
-            if word_count == 0 {
-                panic!("Word count should not be 0");
+        // check every word count once so instructions() can't run off the end
+        let mut start = HEADER_WORD_COUNT;
+        while start < words.len() {
+            let word_count = (words[start] >> 16) as usize;
+            let end = start + word_count;
+            if word_count == 0 || end > words.len() {
+                return Err(Error::InvalidOperandEnd((start, end)));
             }
-
-            let mut operands = Vec::with_capacity(word_count - 1);
-            for _ in 1..word_count {
-                let operand = chunks.next().unwrap();
-                let operand = u32::from_le_bytes([operand[0], operand[1], operand[2], operand[3]]);
-                operands.push(operand);
-            }
-            let operands = operands.into_boxed_slice();
-
-            instructions.push(RawInstruction { opcode, operands });
+            start = end;
         }
 
-        Ok(Module { name, instructions })
+        Ok(Module { name, words })
+    }
+    fn instructions(&self) -> impl Iterator<Item = RawInstruction<'_>> {
+        let mut rest = &self.words[HEADER_WORD_COUNT..];
+        std::iter::from_fn(move || {
+            let (&first_word, tail) = rest.split_first()?;
+            let word_count = (first_word >> 16) as usize;
+            let (operands, next) = tail.split_at(word_count - 1);
+            rest = next;
+
+            Some(RawInstruction {
+                opcode: first_word & 0xFFFF,
+                operands,
+            })
+        })
     }
-    pub fn from_file(shader_path: &std::path::Path) -> Result<Self> {
+    pub fn from_file(shader_path: &std::path::Path) -> Result<Module<'static>> {
         let mut file = std::fs::File::open(shader_path).map_err(|e| Error::Io(e))?;
 
         let mut data = Vec::<u8>::new();
@@ -222,20 +361,18 @@ impl Module {
 
         p1.push_str(&p2);
 
-        Self::from_code(p1.into_boxed_str(), data.as_slice())
+        Module::from_code(p1.into_boxed_str(), data.as_slice())
     }
-    fn get_variables(&self) -> impl Iterator<Item = &RawInstruction> {
-        self.instructions
-            .iter()
+    fn get_variables(&self) -> impl Iterator<Item = RawInstruction<'_>> {
+        self.instructions()
             .filter(|i| i.opcode == crate::OP_VARIABLE)
     }
-    fn get_decorations(&self) -> impl Iterator<Item = &RawInstruction> {
-        self.instructions
-            .iter()
+    fn get_decorations(&self) -> impl Iterator<Item = RawInstruction<'_>> {
+        self.instructions()
             .filter(|i| i.opcode == crate::OP_DECORATE)
     }
-    fn get_types(&self) -> impl Iterator<Item = &RawInstruction> {
-        self.instructions.iter().filter(|i| match i.opcode {
+    fn get_types(&self) -> impl Iterator<Item = RawInstruction<'_>> {
+        self.instructions().filter(|i| match i.opcode {
             crate::OP_TYPE_VOID
             | crate::OP_TYPE_INT
             | crate::OP_TYPE_FLOAT
@@ -265,15 +402,15 @@ impl Module {
         String::from_utf8_lossy(&name_bytes).into_owned()
     }
     fn get_type_name_from_id(&self, type_id: u32) -> Option<String> {
-        self.instructions.iter().find_map(|i| {
+        self.instructions().find_map(|i| {
             if i.opcode != crate::OP_NAME || i.operands[0] != type_id {
                 return None;
             }
-            Some(Self::parse_string_literal(&i.operands))
+            Some(Self::parse_string_literal(i.operands))
         })
     }
     fn get_type_from_id(&self, type_id: u32) -> Result<TypeInfo> {
-        for i in self.instructions.iter() {
+        for i in self.instructions() {
             if i.operands.len() == 0 || i.operands[0] != type_id {
                 continue;
             }
@@ -360,7 +497,7 @@ impl Module {
                             .skip(1)
                             .enumerate()
                             .filter_map(|(member_index, member_type_id)| {
-                                let field_name = self.instructions.iter().find_map(|d| {
+                                let field_name = self.instructions().find_map(|d| {
                                     if d.opcode != crate::OP_MEMBER_NAME {
                                         return None;
                                     }
@@ -375,7 +512,7 @@ impl Module {
 
                                 let field_type = self.get_type_from_id(*member_type_id);
 
-                                let field_offset = self.instructions.iter().find_map(|d| {
+                                let field_offset = self.instructions().find_map(|d| {
                                     if d.opcode != crate::OP_MEMBER_DECORATE {
                                         return None;
                                     }
@@ -446,7 +583,10 @@ impl Module {
                 }
                 crate::OP_TYPE_ARRAY => {
                     let element_type_id = i.operands[1];
-                    let element_count = i.operands[2];
+                    // the length operand is the id of a constant, not the count itself
+                    let element_count = self
+                        .get_constant_value(i.operands[2])
+                        .ok_or(Error::InvalidType)?;
 
                     TypeInfo::Array {
                         element_type: Box::new(self.get_type_from_id(element_type_id)?),
@@ -482,7 +622,7 @@ impl Module {
             _ => 1,
         }
     }
-    pub fn get_uniform_info(&self) -> Box<[UniformInfo]> {
+    pub fn get_uniform_info(&self) -> Result<Box<[UniformInfo]>> {
         let mut uniforms = Vec::<UniformInfo>::new();
         for v in self.get_variables() {
             let variable_id = v.operands[1];
@@ -490,105 +630,501 @@ impl Module {
 
             if storage_class != crate::STORAGE_CLASS_UNIFORM
                 && storage_class != crate::STORAGE_CLASS_UNIFORM_CONSTANT
-                && storage_class != crate::ACCESS_QUALIFIER_READ_ONLY
+                && storage_class != crate::STORAGE_CLASS_STORAGE_BUFFER
                 && storage_class != crate::STORAGE_CLASS_IMAGE
             {
                 continue;
             }
 
-            let set = self.get_decorations().find_map(|d| {
-                let id = d.operands[0];
-                if id != variable_id {
-                    return None;
-                }
-
-                let decoration = d.operands[1];
-                if decoration != crate::DECORATION_DESCRIPTOR_SET {
-                    return None;
-                }
+            let set = self
+                .get_decoration(variable_id, crate::DECORATION_DESCRIPTOR_SET)
+                .ok_or(Error::MissingDescriptorSet(variable_id))?;
+            let binding = self
+                .get_decoration(variable_id, crate::DECORATION_BINDING)
+                .ok_or(Error::MissingBinding(variable_id))?;
 
-                Some(d.operands[2])
+            let variable_type_id = v.operands[0];
+            let ty = self.get_type_from_id(variable_type_id)?;
+
+            let descriptor_count = Self::descriptor_count_from_type(&ty);
+            let name = self
+                .get_uniform_name(variable_id, &ty)
+                .unwrap_or_else(|| format!("set{}_binding{}", set, binding))
+                .into_boxed_str();
+            let uniform_type = Self::uniform_type_of(&ty, storage_class);
+            let size = Self::block_size(&ty, storage_class);
+            let runtime_array = self.get_trailing_runtime_array(variable_type_id);
+            let is_runtime_array = runtime_array.is_some();
+            let element_size = runtime_array.and_then(|(array_id, element_type)| {
+                self.get_decoration(array_id, crate::DECORATION_ARRAY_STRIDE)
+                    .or_else(|| element_type.calc_size())
             });
-
-            let binding = self.get_decorations().find_map(|d| {
-                let id = d.operands[0];
-                if id != variable_id {
-                    return None;
-                }
-
-                let decoration = d.operands[1];
-                if decoration != crate::DECORATION_BINDING {
-                    return None;
-                }
-
-                Some(d.operands[2])
+            uniforms.push(UniformInfo {
+                set,
+                binding,
+                name,
+                ty,
+                storage_class,
+                uniform_type,
+                descriptor_count,
+                size,
+                is_runtime_array,
+                element_size,
             });
+        }
 
-            let variable_type_id = v.operands[0];
+        Ok(uniforms.into_boxed_slice())
+    }
+    fn uniform_type_of(ty: &TypeInfo, storage_class: u32) -> UniformType {
+        match ty {
+            TypeInfo::Pointer { ptr_type: ty }
+            | TypeInfo::Array {
+                element_type: ty, ..
+            }
+            | TypeInfo::RuntimeArray { element_type: ty } => {
+                return Self::uniform_type_of(ty, storage_class);
+            }
+            _ => {}
+        }
 
-            let ty = self.get_type_from_id(variable_type_id);
-
-            if let (Some(set), Some(binding), Ok(ty)) = (set, binding, ty) {
-                let descriptor_count = Self::descriptor_count_from_type(&ty);
-                uniforms.push(UniformInfo {
-                    set,
-                    binding,
-                    ty,
-                    storage_class,
-                    descriptor_count,
-                });
-            } else {
-                panic!("TODO: add error type");
+        match (storage_class, ty) {
+            (crate::STORAGE_CLASS_UNIFORM, TypeInfo::Struct { .. }) => UniformType::UniformBuffer,
+            (crate::STORAGE_CLASS_STORAGE_BUFFER, TypeInfo::Struct { .. }) => {
+                UniformType::StorageBuffer
             }
+            (_, TypeInfo::Sampler) => UniformType::Sampler,
+            (_, TypeInfo::SampledImage { .. }) => UniformType::CombinedImageSampler,
+            (_, TypeInfo::Image { dimentionality, .. })
+                if *dimentionality == crate::DIM_SUBPASS_DATA =>
+            {
+                UniformType::InputAttachment
+            }
+            // sampled is 2 for images only used with reads and writes
+            (_, TypeInfo::Image { sampled: 2, .. }) => UniformType::StorageImage,
+            (_, TypeInfo::Image { .. }) => UniformType::SampledImage,
+            _ => UniformType::Other,
+        }
+    }
+    // the byte size of a buffer block. std140 rounds structs in uniform blocks up to a
+    // multiple of 16. None for opaque types and blocks ending in a runtime array
+    fn block_size(ty: &TypeInfo, storage_class: u32) -> Option<u32> {
+        let block = match ty {
+            TypeInfo::Pointer { ptr_type } => ptr_type,
+            _ => return None,
+        };
+        if !matches!(**block, TypeInfo::Struct { .. }) {
+            return None;
         }
 
-        uniforms.into_boxed_slice()
+        let size = block.calc_size()?;
+        match storage_class {
+            crate::STORAGE_CLASS_UNIFORM => Some(size.next_multiple_of(16)),
+            _ => Some(size),
+        }
     }
-    pub fn get_inputs(&self) -> impl Iterator<Item = ShaderIoInfo> {
-        self.instructions.iter().filter_map(|i| {
-            if i.opcode != crate::OP_VARIABLE {
-                return None;
-            }
+    // the id and element type of the runtime array that ends the block pointer_type_id points to
+    fn get_trailing_runtime_array(&self, pointer_type_id: u32) -> Option<(u32, TypeInfo)> {
+        let find = |id: u32, opcode: u32| {
+            self.get_types()
+                .find(|i| i.opcode == opcode && i.operands[0] == id)
+        };
 
-            let storage_class = i.operands[2];
-            if storage_class != crate::STORAGE_CLASS_INPUT {
-                return None;
-            }
+        let pointer = find(pointer_type_id, crate::OP_TYPE_POINTER)?;
+        let block = find(pointer.operands[2], crate::OP_TYPE_STRUCT)?;
+        // only the last member of a block can be a runtime array
+        let last_member_id = *block.operands[1..].last()?;
+        let array = find(last_member_id, crate::OP_TYPE_RUNTIME_ARRAY)?;
+        let element_type = self.get_type_from_id(array.operands[1]).ok()?;
 
-            let variable_type_id = i.operands[0];
-            let variable_type_info = self.get_type_from_id(variable_type_id);
-            if variable_type_info.is_err() {
+        Some((array.operands[0], element_type))
+    }
+    // the first word of an integer constant. spec constants give their default value
+    fn get_constant_value(&self, constant_id: u32) -> Option<u32> {
+        self.instructions().find_map(|i| match i.opcode {
+            crate::OP_CONSTANT | crate::OP_SPEC_CONSTANT if i.operands[1] == constant_id => {
+                Some(i.operands[2])
+            }
+            _ => None,
+        })
+    }
+    // the first operand of a member decoration, 0 for decorations without one like RowMajor
+    fn get_member_decoration(&self, struct_id: u32, member: u32, decoration: u32) -> Option<u32> {
+        self.instructions().find_map(|d| {
+            if d.opcode != crate::OP_MEMBER_DECORATE
+                || d.operands[0] != struct_id
+                || d.operands[1] != member
+                || d.operands[2] != decoration
+            {
                 return None;
             }
+            Some(d.operands.get(3).copied().unwrap_or(0))
+        })
+    }
+    // the bytes a value of type_id covers in an explicitly laid out block. arrays step by
+    // their ArrayStride and structs end at their last member, so padding is included
+    fn decorated_size(&self, type_id: u32) -> Option<u32> {
+        let ty = self.get_types().find(|i| i.operands[0] == type_id)?;
+        match ty.opcode {
+            crate::OP_TYPE_ARRAY => {
+                let count = self.get_constant_value(ty.operands[2])?;
+                let stride = self
+                    .get_decoration(type_id, crate::DECORATION_ARRAY_STRIDE)
+                    .or_else(|| self.decorated_size(ty.operands[1]))?;
+                Some(stride * count)
+            }
+            crate::OP_TYPE_STRUCT => {
+                let last = (0..ty.operands.len() as u32 - 1)
+                    .map(|member| {
+                        let offset =
+                            self.get_member_decoration(type_id, member, crate::DECORATION_OFFSET);
+                        (member, offset)
+                    })
+                    .max_by_key(|(_, offset)| *offset);
+                match last {
+                    Some((member, offset)) => Some(offset? + self.member_size(type_id, member)?),
+                    None => Some(0),
+                }
+            }
+            _ => self.get_type_from_id(type_id).ok()?.calc_size(),
+        }
+    }
+    // like decorated_size, but matrices step by the member's MatrixStride
+    fn member_size(&self, struct_id: u32, member: u32) -> Option<u32> {
+        let block = self
+            .get_types()
+            .find(|i| i.opcode == crate::OP_TYPE_STRUCT && i.operands[0] == struct_id)?;
+        let member_type_id = *block.operands.get(member as usize + 1)?;
+
+        let matrix = self
+            .get_types()
+            .find(|i| i.opcode == crate::OP_TYPE_MATRIX && i.operands[0] == member_type_id);
+        let stride = self.get_member_decoration(struct_id, member, crate::DECORATION_MATRIX_STRIDE);
+        if let (Some(matrix), Some(stride)) = (matrix, stride) {
+            // row major matrices are laid out as one vector per row
+            let row_major = self
+                .get_member_decoration(struct_id, member, crate::DECORATION_ROW_MAJOR)
+                .is_some();
+            let vectors = if row_major {
+                self.get_types()
+                    .find(|i| {
+                        i.opcode == crate::OP_TYPE_VECTOR && i.operands[0] == matrix.operands[1]
+                    })?
+                    .operands[2]
+            } else {
+                matrix.operands[2]
+            };
+            return Some(stride * vectors);
+        }
 
-            let variable_id = i.operands[1];
-            let variable_name = self.get_type_name_from_id(variable_id);
-            if variable_name.is_none() {
+        self.decorated_size(member_type_id)
+    }
+    fn get_decoration(&self, target_id: u32, decoration: u32) -> Option<u32> {
+        self.get_decorations().find_map(|d| {
+            if d.operands[0] != target_id || d.operands[1] != decoration {
                 return None;
             }
+            Some(d.operands[2])
+        })
+    }
+    // gl_VertexIndex and friends are decorated BuiltIn on the variable. gl_PerVertex is a
+    // block whose members are decorated BuiltIn instead
+    fn is_built_in(&self, variable: &RawInstruction<'_>) -> bool {
+        let variable_id = variable.operands[1];
+        if self
+            .get_decoration(variable_id, crate::DECORATION_BUILT_IN)
+            .is_some()
+        {
+            return true;
+        }
 
-            let location = self.get_decorations().find_map(|d| {
-                let target_id = d.operands[0];
-                if target_id != variable_id {
-                    return None;
-                }
+        let find = |id: u32| self.get_types().find(|i| i.operands[0] == id);
+        let mut pointee = find(variable.operands[0])
+            .filter(|p| p.opcode == crate::OP_TYPE_POINTER)
+            .and_then(|p| find(p.operands[2]));
+        // gl_in[] in tessellation and geometry stages
+        if let Some(array) = pointee.filter(|t| t.opcode == crate::OP_TYPE_ARRAY) {
+            pointee = find(array.operands[1]);
+        }
 
-                let decoration = d.operands[1];
-                if decoration != crate::DECORATION_LOCATION {
-                    return None;
+        match pointee {
+            Some(block) if block.opcode == crate::OP_TYPE_STRUCT => self.instructions().any(|d| {
+                d.opcode == crate::OP_MEMBER_DECORATE
+                    && d.operands[0] == block.operands[0]
+                    && d.operands[2] == crate::DECORATION_BUILT_IN
+            }),
+            _ => false,
+        }
+    }
+    fn get_uniform_name(&self, variable_id: u32, ty: &TypeInfo) -> Option<String> {
+        let name = self
+            .get_type_name_from_id(variable_id)
+            .filter(|n| !n.is_empty());
+        if name.is_some() {
+            return name;
+        }
+
+        // glslang often only names the block type, and arrays of blocks name the element type
+        let mut ty = ty;
+        loop {
+            match ty {
+                TypeInfo::Pointer { ptr_type } => ty = ptr_type,
+                TypeInfo::Array { element_type, .. } | TypeInfo::RuntimeArray { element_type } => {
+                    ty = element_type
                 }
+                TypeInfo::Struct { name, .. } if !name.is_empty() => return Some(name.to_string()),
+                _ => return None,
+            }
+        }
+    }
+    pub fn get_inputs(&self) -> impl Iterator<Item = ShaderIoInfo> {
+        self.get_io_variables(crate::STORAGE_CLASS_INPUT)
+    }
+    pub fn get_outputs(&self) -> impl Iterator<Item = ShaderIoInfo> {
+        self.get_io_variables(crate::STORAGE_CLASS_OUTPUT)
+    }
+    // the names of the input variables, not of the entry points (see get_entry_point_names)
+    pub fn get_input_names(&self) -> impl Iterator<Item = Box<str>> {
+        self.get_inputs().map(|i| i.name)
+    }
+    pub fn vertex_layout(&self) -> Result<VertexLayout> {
+        let mut stride = 0;
+        let attributes = self.vertex_attributes(|_, size| {
+            let offset = stride;
+            stride += size;
+            Ok(offset)
+        })?;
+
+        Ok(VertexLayout { attributes, stride })
+    }
+    // offsets maps each input location to its byte offset in a vertex of stride bytes,
+    // e.g. from offset_of! and size_of on the host vertex struct
+    pub fn vertex_layout_with_offsets(
+        &self,
+        offsets: &[(u32, u32)],
+        stride: u32,
+    ) -> Result<VertexLayout> {
+        let attributes = self.vertex_attributes(|location, size| {
+            let offset = offsets
+                .iter()
+                .find(|(l, _)| *l == location)
+                .map(|(_, offset)| *offset)
+                .ok_or(Error::VertexOffsetMissing(location))?;
+
+            match offset.checked_add(size) {
+                Some(end) if end <= stride => Ok(offset),
+                _ => Err(Error::VertexAttributeOutOfBounds {
+                    location,
+                    offset,
+                    size,
+                    stride,
+                }),
+            }
+        })?;
 
-                Some(d.operands[2])
+        Ok(VertexLayout { attributes, stride })
+    }
+    // get_offset is called with the location and size of each input, in location order
+    fn vertex_attributes<F>(&self, mut get_offset: F) -> Result<Box<[VertexAttribute]>>
+    where
+        F: FnMut(u32, u32) -> Result<u32>,
+    {
+        // get_inputs skips anything without a location, which is only fine for built-ins
+        let unlocated = self.get_variables().find(|v| {
+            v.operands[2] == crate::STORAGE_CLASS_INPUT
+                && !self.is_built_in(v)
+                && self
+                    .get_decoration(v.operands[1], crate::DECORATION_LOCATION)
+                    .is_none()
+        });
+        if let Some(v) = unlocated {
+            return Err(Error::LocationMissing(v.operands[1]));
+        }
+
+        let mut inputs: Vec<ShaderIoInfo> = self.get_inputs().collect();
+        inputs.sort_by_key(|i| i.location);
+
+        let mut attributes = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            let type_info = match input.type_info {
+                TypeInfo::Pointer { ptr_type } => *ptr_type,
+                ty => ty,
+            };
+            let size = type_info
+                .calc_size()
+                .ok_or(Error::UnsizedInput(input.location))?;
+
+            attributes.push(VertexAttribute {
+                location: input.location,
+                location_count: type_info.location_count(),
+                name: input.name,
+                type_info,
+                offset: get_offset(input.location, size)?,
             });
-            if location.is_none() {
-                return None;
-            }
+        }
 
-            Some(ShaderIoInfo {
-                location: location.unwrap(),
-                name: variable_name.unwrap().into_boxed_str(),
-                type_info: variable_type_info.unwrap(),
+        Ok(attributes.into_boxed_slice())
+    }
+    fn get_io_variables(&self, storage_class: u32) -> impl Iterator<Item = ShaderIoInfo> {
+        self.get_variables()
+            .filter(move |v| v.operands[2] == storage_class)
+            .filter_map(|v| self.get_io_info(&v))
+    }
+    // None for built-ins and variables without a name or location
+    fn get_io_info(&self, variable: &RawInstruction<'_>) -> Option<ShaderIoInfo> {
+        if self.is_built_in(variable) {
+            return None;
+        }
+
+        let variable_id = variable.operands[1];
+        let type_info = self.get_type_from_id(variable.operands[0]).ok()?;
+        let name = self.get_type_name_from_id(variable_id)?;
+        let location = self.get_decoration(variable_id, crate::DECORATION_LOCATION)?;
+
+        Some(ShaderIoInfo {
+            location,
+            name: name.into_boxed_str(),
+            type_info,
+            storage_class: variable.operands[2],
+        })
+    }
+    // only the inputs and outputs the entry point lists in its interface, unlike
+    // get_inputs and get_outputs which return those of every entry point
+    pub fn get_interface(&self, entry_point: &str) -> Option<Vec<ShaderIoInfo>> {
+        let entry = self.instructions().find(|i| {
+            i.opcode == crate::OP_ENTRY_POINT
+                && Self::parse_string_literal(&i.operands[1..]) == entry_point
+        })?;
+
+        // the name is a nul terminated string starting at the third operand
+        let name_end = entry.operands[2..]
+            .iter()
+            .position(|w| w.to_le_bytes().contains(&0))?;
+        let interface_ids = &entry.operands[2 + name_end + 1..];
+
+        let interface = self
+            .get_variables()
+            .filter(|v| interface_ids.contains(&v.operands[1]))
+            .filter(|v| {
+                v.operands[2] == crate::STORAGE_CLASS_INPUT
+                    || v.operands[2] == crate::STORAGE_CLASS_OUTPUT
             })
+            .filter_map(|v| self.get_io_info(&v))
+            .collect();
+
+        Some(interface)
+    }
+    pub fn get_push_constants(&self) -> Result<Vec<PushConstantInfo>> {
+        self.get_variables()
+            .filter(|v| v.operands[2] == crate::STORAGE_CLASS_PUSH_CONSTANT)
+            .map(|v| {
+                let variable_id = v.operands[1];
+                let block_id = self
+                    .get_types()
+                    .find(|i| i.opcode == crate::OP_TYPE_POINTER && i.operands[0] == v.operands[0])
+                    .ok_or(Error::InvalidType)?
+                    .operands[2];
+                let (name, members) = match self.get_type_from_id(v.operands[0])? {
+                    TypeInfo::Pointer { ptr_type } => match *ptr_type {
+                        TypeInfo::Struct { name, members } => (name, members),
+                        _ => return Err(Error::InvalidType),
+                    },
+                    _ => return Err(Error::InvalidType),
+                };
+                let name = self
+                    .get_type_name_from_id(variable_id)
+                    .filter(|n| !n.is_empty())
+                    .map_or(name, String::into_boxed_str);
+
+                let mut infos = Vec::with_capacity(members.len());
+                for (index, m) in members.iter().enumerate() {
+                    infos.push(PushConstantMember {
+                        name: m.field_name.clone(),
+                        offset: m.field_offset,
+                        size: self
+                            .member_size(block_id, index as u32)
+                            .ok_or(Error::InvalidType)?,
+                    });
+                }
+                infos.sort_by_key(|m| m.offset);
+
+                let offset = infos.first().map_or(0, |m| m.offset);
+                let end = infos.last().map_or(0, |m| m.offset + m.size);
+
+                Ok(PushConstantInfo {
+                    name,
+                    offset,
+                    size: end - offset,
+                    members: infos.into_boxed_slice(),
+                })
+            })
+            .collect()
+    }
+    // constants without a SpecId decoration can't be specialized and are skipped
+    pub fn get_spec_constants(&self) -> Vec<SpecConstantInfo> {
+        self.instructions()
+            .filter_map(|i| {
+                match i.opcode {
+                    crate::OP_SPEC_CONSTANT
+                    | crate::OP_SPEC_CONSTANT_TRUE
+                    | crate::OP_SPEC_CONSTANT_FALSE
+                    | crate::OP_SPEC_CONSTANT_COMPOSITE => {}
+                    _ => return None,
+                }
+                let constant_id = self.get_decoration(i.operands[1], crate::DECORATION_SPEC_ID)?;
+                let type_info = self.get_type_from_id(i.operands[0]).ok();
+
+                let word = i.operands.get(2).copied();
+                let default_value = match (i.opcode, &type_info, word) {
+                    (crate::OP_SPEC_CONSTANT_TRUE, ..) => SpecConstantValue::Bool(true),
+                    (crate::OP_SPEC_CONSTANT_FALSE, ..) => SpecConstantValue::Bool(false),
+                    (
+                        crate::OP_SPEC_CONSTANT,
+                        Some(TypeInfo::Int {
+                            width: 32, signed, ..
+                        }),
+                        Some(w),
+                    ) => {
+                        if *signed {
+                            SpecConstantValue::Int(w as i32)
+                        } else {
+                            SpecConstantValue::UInt(w)
+                        }
+                    }
+                    (crate::OP_SPEC_CONSTANT, Some(TypeInfo::Float { width: 32, .. }), Some(w)) => {
+                        SpecConstantValue::Float(f32::from_bits(w))
+                    }
+                    _ => SpecConstantValue::Unknown,
+                };
+
+                Some(SpecConstantInfo {
+                    constant_id,
+                    type_info,
+                    default_value,
+                })
+            })
+            .collect()
+    }
+    // (major, minor) of the SPIR-V version the module was written for
+    #[inline]
+    pub fn version(&self) -> (u32, u32) {
+        ((self.words[1] >> 16) & 0xFF, (self.words[1] >> 8) & 0xFF)
+    }
+    // the tool id in the high 16 bits, the tool's own version in the low 16
+    #[inline]
+    pub fn generator(&self) -> u32 {
+        self.words[2]
+    }
+    pub fn reflect(&self) -> Result<ReflectionReport> {
+        Ok(ReflectionReport {
+            name: self.name.clone(),
+            version: self.version(),
+            generator: self.generator(),
+            entry_points: self.get_entry_point_names().collect(),
+            inputs: self.get_inputs().collect(),
+            outputs: self.get_outputs().collect(),
+            uniforms: self.get_uniform_info()?,
+            push_constants: self.get_push_constants()?.into_boxed_slice(),
+            spec_constants: self.get_spec_constants().into_boxed_slice(),
         })
     }
     pub fn get_variable_types(&self) -> impl Iterator<Item = TypeInfo> {
@@ -598,7 +1134,7 @@ impl Module {
         })
     }
     pub fn get_struct_types(&self) -> impl Iterator<Item = TypeInfo> {
-        self.instructions.iter().filter_map(|ty| {
+        self.instructions().filter_map(|ty| {
             if ty.opcode == crate::OP_TYPE_STRUCT {
                 let type_id = ty.operands[0];
                 self.get_type_from_id(type_id).ok()
@@ -607,8 +1143,43 @@ impl Module {
             }
         })
     }
-    pub fn get_entry_points(&self) -> impl Iterator<Item = String> {
-        self.instructions.iter().filter_map(|i| {
+    // the workgroup size of a compute entry point. sizes given through LocalSizeId are
+    // resolved to their constant values when possible, otherwise the raw ids are returned
+    pub fn get_local_size(&self, entry_point: &str) -> Option<[u32; 3]> {
+        let entry_point_id = self.get_entry_point_id(entry_point)?;
+
+        self.instructions().find_map(|i| {
+            if i.operands.len() < 5 || i.operands[0] != entry_point_id {
+                return None;
+            }
+
+            match (i.opcode, i.operands[1]) {
+                (crate::OP_EXECUTION_MODE, crate::EXECUTION_MODE_LOCAL_SIZE) => {
+                    Some([i.operands[2], i.operands[3], i.operands[4]])
+                }
+                (crate::OP_EXECUTION_MODE_ID, crate::EXECUTION_MODE_LOCAL_SIZE_ID) => {
+                    Some([2, 3, 4].map(|j| {
+                        let id = i.operands[j];
+                        self.get_constant_value(id).unwrap_or(id)
+                    }))
+                }
+                _ => None,
+            }
+        })
+    }
+    fn get_entry_point_id(&self, entry_point: &str) -> Option<u32> {
+        self.instructions().find_map(|i| {
+            if i.opcode != crate::OP_ENTRY_POINT {
+                return None;
+            }
+            if Self::parse_string_literal(&i.operands[1..]) != entry_point {
+                return None;
+            }
+            Some(i.operands[1])
+        })
+    }
+    pub fn get_entry_point_names(&self) -> impl Iterator<Item = String> {
+        self.instructions().filter_map(|i| {
             if i.opcode != crate::OP_ENTRY_POINT {
                 return None;
             }
@@ -625,8 +1196,1077 @@ impl Module {
 
 #[cfg(test)]
 mod tests {
-    // use crate::module::Module;
-    // use crate::module::ShaderIoInfo;
+    use crate::Error;
+    use crate::layout::{ShaderStage, merge_stages};
+    use crate::module::{Module, SpecConstantValue, UniformType};
+
+    fn string_operands(s: &str) -> Vec<u32> {
+        let mut bytes = s.as_bytes().to_vec();
+        bytes.resize((bytes.len() / 4 + 1) * 4, 0);
+        bytes
+            .chunks_exact(4)
+            .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect()
+    }
+
+    fn assemble(instructions: &[(u32, Vec<u32>)]) -> Vec<u8> {
+        let mut words = vec![crate::MAGIC_NUMBER, 0x00010000, 0, 64, 0];
+        for (opcode, operands) in instructions {
+            words.push(((operands.len() as u32 + 1) << 16) | opcode);
+            words.extend(operands);
+        }
+        words.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    fn name(id: u32, s: &str) -> (u32, Vec<u32>) {
+        let mut operands = vec![id];
+        operands.extend(string_operands(s));
+        (crate::OP_NAME, operands)
+    }
+
+    fn member_name(id: u32, member: u32, s: &str) -> (u32, Vec<u32>) {
+        let mut operands = vec![id, member];
+        operands.extend(string_operands(s));
+        (crate::OP_MEMBER_NAME, operands)
+    }
+
+    fn uniform_block(set: u32, binding: u32, block_name: &str) -> Vec<(u32, Vec<u32>)> {
+        vec![
+            name(3, block_name),
+            member_name(3, 0, "color"),
+            name(5, ""),
+            (
+                crate::OP_DECORATE,
+                vec![5, crate::DECORATION_DESCRIPTOR_SET, set],
+            ),
+            (
+                crate::OP_DECORATE,
+                vec![5, crate::DECORATION_BINDING, binding],
+            ),
+            (
+                crate::OP_MEMBER_DECORATE,
+                vec![3, 0, crate::DECORATION_OFFSET, 0],
+            ),
+            (crate::OP_TYPE_FLOAT, vec![1, 32]),
+            (crate::OP_TYPE_VECTOR, vec![2, 1, 4]),
+            (crate::OP_TYPE_STRUCT, vec![3, 2]),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![4, crate::STORAGE_CLASS_UNIFORM, 3],
+            ),
+            (crate::OP_VARIABLE, vec![4, 5, crate::STORAGE_CLASS_UNIFORM]),
+        ]
+    }
+
+    fn sampler(set: u32, binding: u32) -> Vec<(u32, Vec<u32>)> {
+        vec![
+            name(5, "tex_sampler"),
+            (
+                crate::OP_DECORATE,
+                vec![5, crate::DECORATION_DESCRIPTOR_SET, set],
+            ),
+            (
+                crate::OP_DECORATE,
+                vec![5, crate::DECORATION_BINDING, binding],
+            ),
+            (crate::OP_TYPE_SAMPLER, vec![3]),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![4, crate::STORAGE_CLASS_UNIFORM_CONSTANT, 3],
+            ),
+            (
+                crate::OP_VARIABLE,
+                vec![4, 5, crate::STORAGE_CLASS_UNIFORM_CONSTANT],
+            ),
+        ]
+    }
+
+    fn storage_buffer(set: u32, binding: u32) -> Vec<(u32, Vec<u32>)> {
+        vec![
+            name(3, "Particles"),
+            member_name(3, 0, "data"),
+            name(5, "particles"),
+            (
+                crate::OP_DECORATE,
+                vec![5, crate::DECORATION_DESCRIPTOR_SET, set],
+            ),
+            (
+                crate::OP_DECORATE,
+                vec![5, crate::DECORATION_BINDING, binding],
+            ),
+            (
+                crate::OP_DECORATE,
+                vec![6, crate::DECORATION_ARRAY_STRIDE, 16],
+            ),
+            (
+                crate::OP_MEMBER_DECORATE,
+                vec![3, 0, crate::DECORATION_OFFSET, 0],
+            ),
+            (crate::OP_TYPE_FLOAT, vec![1, 32]),
+            (crate::OP_TYPE_VECTOR, vec![2, 1, 4]),
+            (crate::OP_TYPE_RUNTIME_ARRAY, vec![6, 2]),
+            (crate::OP_TYPE_STRUCT, vec![3, 6]),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![4, crate::STORAGE_CLASS_STORAGE_BUFFER, 3],
+            ),
+            (
+                crate::OP_VARIABLE,
+                vec![4, 5, crate::STORAGE_CLASS_STORAGE_BUFFER],
+            ),
+        ]
+    }
+
+    fn module(name: &str, instructions: &[(u32, Vec<u32>)]) -> Module<'static> {
+        Module::from_code(name.into(), &assemble(instructions)).unwrap()
+    }
+
+    // a vertex and a fragment stage
+    fn stages<'a>(modules: &'a [Module<'a>; 2]) -> [(ShaderStage, &'a Module<'a>); 2] {
+        [
+            (ShaderStage::Vertex, &modules[0]),
+            (ShaderStage::Fragment, &modules[1]),
+        ]
+    }
+
+    #[test]
+    fn compatible_stages() {
+        let modules = [
+            module("ShaderVert", &uniform_block(0, 0, "MeshUBO")),
+            module("ShaderFrag", &uniform_block(0, 0, "MeshUBO")),
+        ];
+
+        assert!(merge_stages(&stages(&modules)).is_ok());
+    }
+
+    #[test]
+    fn conflicting_binding() {
+        let modules = [
+            module("ShaderVert", &uniform_block(0, 0, "MeshUBO")),
+            module("ShaderFrag", &sampler(0, 0)),
+        ];
+
+        match merge_stages(&stages(&modules)) {
+            Err(Error::ConflictingBinding { set, binding }) => {
+                assert_eq!(set, 0);
+                assert_eq!(binding, 0);
+            }
+            r => panic!("expected ConflictingBinding, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn uniform_names() {
+        let named = module("ShaderVert", &uniform_block(0, 0, "MeshUBO"))
+            .get_uniform_info()
+            .unwrap();
+        assert_eq!(named[0].name.as_ref(), "MeshUBO");
+
+        let unnamed = module("ShaderVert", &uniform_block(1, 2, ""))
+            .get_uniform_info()
+            .unwrap();
+        assert_eq!(unnamed[0].name.as_ref(), "set1_binding2");
+
+        let sampler = module("ShaderFrag", &sampler(0, 1))
+            .get_uniform_info()
+            .unwrap();
+        assert_eq!(sampler[0].name.as_ref(), "tex_sampler");
+    }
+
+    #[test]
+    fn storage_buffer_runtime_array() {
+        let uniforms = module("ShaderComp", &storage_buffer(0, 3))
+            .get_uniform_info()
+            .unwrap();
+
+        assert_eq!(uniforms.len(), 1);
+        assert_eq!(uniforms[0].name.as_ref(), "particles");
+        assert_eq!(uniforms[0].binding, 3);
+        assert_eq!(
+            uniforms[0].storage_class,
+            crate::STORAGE_CLASS_STORAGE_BUFFER
+        );
+        assert!(uniforms[0].is_runtime_array);
+        assert_eq!(uniforms[0].element_size, Some(16));
+
+        let ubo = module("ShaderVert", &uniform_block(0, 0, "MeshUBO"))
+            .get_uniform_info()
+            .unwrap();
+        assert!(!ubo[0].is_runtime_array);
+        assert_eq!(ubo[0].element_size, None);
+    }
+
+    #[test]
+    fn duplicate_uniform_name() {
+        let modules = [
+            module("ShaderVert", &uniform_block(0, 0, "MeshUBO")),
+            module("ShaderFrag", &uniform_block(1, 0, "MeshUBO")),
+        ];
+
+        match merge_stages(&stages(&modules)) {
+            Err(Error::DuplicateUniformName(name)) => assert_eq!(name.as_ref(), "MeshUBO"),
+            r => panic!("expected DuplicateUniformName, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn reflection_report() {
+        let mut instructions = vec![(
+            crate::OP_ENTRY_POINT,
+            [
+                vec![crate::EXECUTION_MODEL_FRAGMENT, 20],
+                string_operands("main"),
+                vec![7, 9],
+            ]
+            .concat(),
+        )];
+        instructions.extend(uniform_block(0, 0, "MeshUBO"));
+        instructions.extend([
+            name(7, "in_color"),
+            name(9, "out_color"),
+            name(10, "Push"),
+            member_name(10, 0, "tint"),
+            name(12, "push"),
+            (crate::OP_DECORATE, vec![7, crate::DECORATION_LOCATION, 0]),
+            (crate::OP_DECORATE, vec![9, crate::DECORATION_LOCATION, 0]),
+            (
+                crate::OP_MEMBER_DECORATE,
+                vec![10, 0, crate::DECORATION_OFFSET, 0],
+            ),
+            (crate::OP_DECORATE, vec![14, crate::DECORATION_SPEC_ID, 3]),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![6, crate::STORAGE_CLASS_INPUT, 2],
+            ),
+            (crate::OP_VARIABLE, vec![6, 7, crate::STORAGE_CLASS_INPUT]),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![8, crate::STORAGE_CLASS_OUTPUT, 2],
+            ),
+            (crate::OP_VARIABLE, vec![8, 9, crate::STORAGE_CLASS_OUTPUT]),
+            (crate::OP_TYPE_STRUCT, vec![10, 2]),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![11, crate::STORAGE_CLASS_PUSH_CONSTANT, 10],
+            ),
+            (
+                crate::OP_VARIABLE,
+                vec![11, 12, crate::STORAGE_CLASS_PUSH_CONSTANT],
+            ),
+            (crate::OP_SPEC_CONSTANT, vec![1, 14, 0]),
+        ]);
+
+        let report = module("ShaderFrag", &instructions).reflect().unwrap();
+
+        assert_eq!(report.entry_points.as_ref(), ["main"]);
+        assert_eq!(report.version, (1, 0));
+        assert_eq!(report.inputs.len(), 1);
+        assert_eq!(report.outputs.len(), 1);
+        assert_eq!(report.outputs[0].name.as_ref(), "out_color");
+        assert_eq!(report.uniforms.len(), 1);
+        assert_eq!(report.uniforms[0].name.as_ref(), "MeshUBO");
+        assert_eq!(report.push_constants.len(), 1);
+        assert_eq!(report.push_constants[0].name.as_ref(), "push");
+        assert_eq!(report.push_constants[0].offset, 0);
+        assert_eq!(report.push_constants[0].size, 16);
+        assert_eq!(report.spec_constants.len(), 1);
+        assert_eq!(report.spec_constants[0].constant_id, 3);
+        assert_eq!(
+            report.spec_constants[0].default_value,
+            SpecConstantValue::Float(0.0)
+        );
+
+        let text = report.to_string();
+        assert!(text.contains("location 0: vec4 in_color"));
+        assert!(text.contains("set 0 binding 0: MeshUBO (count 1)"));
+        assert!(text.contains("SPIR-V 1.0, generator 0x00000000"));
+        assert!(text.contains("push: offset 0, 16 bytes"));
+        assert!(text.contains("tint: offset 0, 16 bytes"));
+        assert!(text.contains("constant_id 3: float = 0.0"));
+    }
+
+    #[test]
+    fn vertex_layout() {
+        let instructions = [
+            name(5, "position"),
+            name(6, "tex_coord"),
+            (crate::OP_DECORATE, vec![5, crate::DECORATION_LOCATION, 1]),
+            (crate::OP_DECORATE, vec![6, crate::DECORATION_LOCATION, 0]),
+            (crate::OP_TYPE_FLOAT, vec![1, 32]),
+            (crate::OP_TYPE_VECTOR, vec![2, 1, 3]),
+            (crate::OP_TYPE_VECTOR, vec![3, 1, 2]),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![4, crate::STORAGE_CLASS_INPUT, 2],
+            ),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![7, crate::STORAGE_CLASS_INPUT, 3],
+            ),
+            (crate::OP_VARIABLE, vec![4, 5, crate::STORAGE_CLASS_INPUT]),
+            (crate::OP_VARIABLE, vec![7, 6, crate::STORAGE_CLASS_INPUT]),
+        ];
+
+        let module = module("ShaderVert", &instructions);
+        let layout = module.vertex_layout().unwrap();
+
+        let attributes: Vec<(u32, &str, u32)> = layout
+            .attributes
+            .iter()
+            .map(|a| (a.location, a.name.as_ref(), a.offset))
+            .collect();
+        assert_eq!(attributes, [(0, "tex_coord", 0), (1, "position", 8)]);
+        assert_eq!(layout.stride, 20);
+
+        // a host struct with position first, padded to 16 bytes
+        let layout = module
+            .vertex_layout_with_offsets(&[(1, 0), (0, 16)], 24)
+            .unwrap();
+        let attributes: Vec<(u32, u32)> = layout
+            .attributes
+            .iter()
+            .map(|a| (a.location, a.offset))
+            .collect();
+        assert_eq!(attributes, [(0, 16), (1, 0)]);
+        assert_eq!(layout.stride, 24);
+
+        match module.vertex_layout_with_offsets(&[(1, 0)], 24) {
+            Err(Error::VertexOffsetMissing(location)) => assert_eq!(location, 0),
+            r => panic!("expected VertexOffsetMissing, got {:?}", r),
+        }
+        match module.vertex_layout_with_offsets(&[(1, 0), (0, 16)], 20) {
+            Err(Error::VertexAttributeOutOfBounds { location, .. }) => assert_eq!(location, 0),
+            r => panic!("expected VertexAttributeOutOfBounds, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn fragment_outputs() {
+        let instructions = [
+            name(5, "out_color"),
+            name(6, "out_normal"),
+            name(7, "in_color"),
+            name(8, "undecorated"),
+            (crate::OP_DECORATE, vec![5, crate::DECORATION_LOCATION, 0]),
+            (crate::OP_DECORATE, vec![6, crate::DECORATION_LOCATION, 1]),
+            (crate::OP_DECORATE, vec![7, crate::DECORATION_LOCATION, 0]),
+            (crate::OP_TYPE_FLOAT, vec![1, 32]),
+            (crate::OP_TYPE_VECTOR, vec![2, 1, 4]),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![3, crate::STORAGE_CLASS_OUTPUT, 2],
+            ),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![4, crate::STORAGE_CLASS_INPUT, 2],
+            ),
+            (crate::OP_VARIABLE, vec![3, 5, crate::STORAGE_CLASS_OUTPUT]),
+            (crate::OP_VARIABLE, vec![3, 6, crate::STORAGE_CLASS_OUTPUT]),
+            (crate::OP_VARIABLE, vec![4, 7, crate::STORAGE_CLASS_INPUT]),
+            (crate::OP_VARIABLE, vec![3, 8, crate::STORAGE_CLASS_OUTPUT]),
+        ];
+
+        let m = module("ShaderFrag", &instructions);
+
+        let mut outputs: Vec<(u32, Box<str>)> =
+            m.get_outputs().map(|o| (o.location, o.name)).collect();
+        outputs.sort();
+        assert_eq!(outputs, [(0, "out_color".into()), (1, "out_normal".into())]);
+        assert_eq!(m.get_inputs().count(), 1);
+    }
+
+    #[test]
+    fn built_in_inputs() {
+        let mut instructions = vec![
+            name(5, "position"),
+            name(6, "gl_VertexIndex"),
+            name(9, "gl_PerVertex"),
+            member_name(9, 0, "gl_Position"),
+            name(11, ""),
+            (crate::OP_DECORATE, vec![5, crate::DECORATION_LOCATION, 0]),
+            // BuiltIn VertexIndex
+            (crate::OP_DECORATE, vec![6, crate::DECORATION_BUILT_IN, 42]),
+            // BuiltIn Position
+            (
+                crate::OP_MEMBER_DECORATE,
+                vec![9, 0, crate::DECORATION_BUILT_IN, 0],
+            ),
+            (crate::OP_TYPE_INT, vec![1, 32, 1]),
+            (crate::OP_TYPE_FLOAT, vec![2, 32]),
+            (crate::OP_TYPE_VECTOR, vec![3, 2, 3]),
+            (crate::OP_TYPE_VECTOR, vec![8, 2, 4]),
+            (crate::OP_TYPE_STRUCT, vec![9, 8]),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![4, crate::STORAGE_CLASS_INPUT, 3],
+            ),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![7, crate::STORAGE_CLASS_INPUT, 1],
+            ),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![10, crate::STORAGE_CLASS_OUTPUT, 9],
+            ),
+            (crate::OP_VARIABLE, vec![4, 5, crate::STORAGE_CLASS_INPUT]),
+            (crate::OP_VARIABLE, vec![7, 6, crate::STORAGE_CLASS_INPUT]),
+            (
+                crate::OP_VARIABLE,
+                vec![10, 11, crate::STORAGE_CLASS_OUTPUT],
+            ),
+        ];
+
+        let m = module("ShaderVert", &instructions);
+        let inputs: Vec<_> = m.get_input_names().collect();
+        assert_eq!(inputs, ["position".into()]);
+        assert_eq!(m.get_outputs().count(), 0);
+        assert_eq!(m.vertex_layout().unwrap().attributes.len(), 1);
+
+        // a user input without a location is still an error
+        instructions.extend([
+            name(12, "normal"),
+            (crate::OP_VARIABLE, vec![4, 12, crate::STORAGE_CLASS_INPUT]),
+        ]);
+        match module("ShaderVert", &instructions).vertex_layout() {
+            Err(Error::LocationMissing(id)) => assert_eq!(id, 12),
+            r => panic!("expected LocationMissing, got {:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn push_constants() {
+        let instructions = [
+            name(10, "Push"),
+            member_name(10, 0, "tint"),
+            member_name(10, 1, "light"),
+            member_name(10, 2, "weights"),
+            name(11, "Light"),
+            member_name(11, 0, "color"),
+            member_name(11, 1, "intensity"),
+            name(12, "push"),
+            (
+                crate::OP_MEMBER_DECORATE,
+                vec![10, 0, crate::DECORATION_OFFSET, 0],
+            ),
+            (
+                crate::OP_MEMBER_DECORATE,
+                vec![10, 1, crate::DECORATION_OFFSET, 16],
+            ),
+            (
+                crate::OP_MEMBER_DECORATE,
+                vec![10, 2, crate::DECORATION_OFFSET, 36],
+            ),
+            (
+                crate::OP_MEMBER_DECORATE,
+                vec![11, 0, crate::DECORATION_OFFSET, 0],
+            ),
+            (
+                crate::OP_MEMBER_DECORATE,
+                vec![11, 1, crate::DECORATION_OFFSET, 16],
+            ),
+            (crate::OP_TYPE_FLOAT, vec![1, 32]),
+            (crate::OP_TYPE_VECTOR, vec![2, 1, 4]),
+            (crate::OP_TYPE_INT, vec![3, 32, 0]),
+            (crate::OP_CONSTANT, vec![3, 4, 3]),
+            (crate::OP_TYPE_ARRAY, vec![5, 1, 4]),
+            (crate::OP_TYPE_STRUCT, vec![11, 2, 1]),
+            (crate::OP_TYPE_STRUCT, vec![10, 1, 11, 5]),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![13, crate::STORAGE_CLASS_PUSH_CONSTANT, 10],
+            ),
+            (
+                crate::OP_VARIABLE,
+                vec![13, 12, crate::STORAGE_CLASS_PUSH_CONSTANT],
+            ),
+        ];
+
+        let push_constants = module("ShaderVert", &instructions)
+            .get_push_constants()
+            .unwrap();
+        assert_eq!(push_constants.len(), 1);
+
+        let block = &push_constants[0];
+        assert_eq!(block.name.as_ref(), "push");
+        assert_eq!(block.offset, 0);
+        // float[3] at 36
+        assert_eq!(block.size, 48);
+
+        let members: Vec<(&str, u32, u32)> = block
+            .members
+            .iter()
+            .map(|m| (m.name.as_ref(), m.offset, m.size))
+            .collect();
+        assert_eq!(
+            members,
+            [("tint", 0, 4), ("light", 16, 20), ("weights", 36, 12)]
+        );
+    }
+
+    #[test]
+    fn push_constant_strides() {
+        let member_decorate = |id: u32, member: u32, decoration: u32, value: u32| {
+            (
+                crate::OP_MEMBER_DECORATE,
+                vec![id, member, decoration, value],
+            )
+        };
+        let instructions = [
+            name(10, "Push"),
+            member_name(10, 0, "weights"),
+            member_name(10, 1, "basis"),
+            member_name(10, 2, "light"),
+            name(11, "Light"),
+            member_name(11, 0, "color"),
+            member_name(11, 1, "falloff"),
+            name(12, "push"),
+            (
+                crate::OP_DECORATE,
+                vec![5, crate::DECORATION_ARRAY_STRIDE, 16],
+            ),
+            (
+                crate::OP_DECORATE,
+                vec![8, crate::DECORATION_ARRAY_STRIDE, 16],
+            ),
+            member_decorate(10, 0, crate::DECORATION_OFFSET, 0),
+            member_decorate(10, 1, crate::DECORATION_OFFSET, 48),
+            member_decorate(10, 1, crate::DECORATION_MATRIX_STRIDE, 16),
+            member_decorate(10, 2, crate::DECORATION_OFFSET, 96),
+            member_decorate(11, 0, crate::DECORATION_OFFSET, 0),
+            member_decorate(11, 1, crate::DECORATION_OFFSET, 16),
+            (crate::OP_TYPE_FLOAT, vec![1, 32]),
+            (crate::OP_TYPE_VECTOR, vec![2, 1, 3]),
+            (crate::OP_TYPE_INT, vec![3, 32, 0]),
+            (crate::OP_CONSTANT, vec![3, 4, 3]),
+            (crate::OP_CONSTANT, vec![3, 7, 2]),
+            (crate::OP_TYPE_ARRAY, vec![5, 1, 4]),
+            (crate::OP_TYPE_MATRIX, vec![6, 2, 3]),
+            (crate::OP_TYPE_ARRAY, vec![8, 1, 7]),
+            (crate::OP_TYPE_STRUCT, vec![11, 2, 8]),
+            (crate::OP_TYPE_STRUCT, vec![10, 5, 6, 11]),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![13, crate::STORAGE_CLASS_PUSH_CONSTANT, 10],
+            ),
+            (
+                crate::OP_VARIABLE,
+                vec![13, 12, crate::STORAGE_CLASS_PUSH_CONSTANT],
+            ),
+        ];
+
+        let push_constants = module("ShaderVert", &instructions)
+            .get_push_constants()
+            .unwrap();
+
+        let members: Vec<(&str, u32, u32)> = push_constants[0]
+            .members
+            .iter()
+            .map(|m| (m.name.as_ref(), m.offset, m.size))
+            .collect();
+        // float[3] and float[2] padded to 16 byte elements, mat3 to 16 byte columns
+        assert_eq!(
+            members,
+            [("weights", 0, 48), ("basis", 48, 48), ("light", 96, 48)]
+        );
+        assert_eq!(push_constants[0].size, 144);
+    }
+
+    #[test]
+    fn local_size() {
+        let entry_point = (
+            crate::OP_ENTRY_POINT,
+            [
+                vec![crate::EXECUTION_MODEL_GLCOMPUTE, 20],
+                string_operands("main"),
+            ]
+            .concat(),
+        );
+
+        let literal = module(
+            "ShaderComp",
+            &[
+                entry_point.clone(),
+                (
+                    crate::OP_EXECUTION_MODE,
+                    vec![20, crate::EXECUTION_MODE_LOCAL_SIZE, 8, 8, 1],
+                ),
+            ],
+        );
+        assert_eq!(literal.get_local_size("main"), Some([8, 8, 1]));
+        assert_eq!(literal.get_local_size("other"), None);
+
+        // y and z have no constant to resolve through
+        let by_id = module(
+            "ShaderComp",
+            &[
+                entry_point,
+                (
+                    crate::OP_EXECUTION_MODE_ID,
+                    vec![20, crate::EXECUTION_MODE_LOCAL_SIZE_ID, 4, 5, 6],
+                ),
+                (crate::OP_TYPE_INT, vec![3, 32, 0]),
+                (crate::OP_CONSTANT, vec![3, 4, 64]),
+            ],
+        );
+        assert_eq!(by_id.get_local_size("main"), Some([64, 5, 6]));
+    }
+
+    #[test]
+    fn array_vertex_input() {
+        let instructions = [
+            name(5, "position"),
+            name(6, "weights"),
+            name(7, "tex_coord"),
+            (crate::OP_DECORATE, vec![5, crate::DECORATION_LOCATION, 0]),
+            (crate::OP_DECORATE, vec![6, crate::DECORATION_LOCATION, 1]),
+            (crate::OP_DECORATE, vec![7, crate::DECORATION_LOCATION, 5]),
+            (crate::OP_TYPE_FLOAT, vec![1, 32]),
+            (crate::OP_TYPE_VECTOR, vec![2, 1, 3]),
+            (crate::OP_TYPE_VECTOR, vec![3, 1, 4]),
+            (crate::OP_TYPE_VECTOR, vec![4, 1, 2]),
+            (crate::OP_TYPE_INT, vec![8, 32, 0]),
+            (crate::OP_CONSTANT, vec![8, 9, 4]),
+            (crate::OP_TYPE_ARRAY, vec![10, 3, 9]),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![11, crate::STORAGE_CLASS_INPUT, 2],
+            ),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![12, crate::STORAGE_CLASS_INPUT, 10],
+            ),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![13, crate::STORAGE_CLASS_INPUT, 4],
+            ),
+            (crate::OP_VARIABLE, vec![11, 5, crate::STORAGE_CLASS_INPUT]),
+            (crate::OP_VARIABLE, vec![12, 6, crate::STORAGE_CLASS_INPUT]),
+            (crate::OP_VARIABLE, vec![13, 7, crate::STORAGE_CLASS_INPUT]),
+        ];
+
+        let layout = module("ShaderVert", &instructions).vertex_layout().unwrap();
+
+        let attributes: Vec<(u32, u32, &str, u32)> = layout
+            .attributes
+            .iter()
+            .map(|a| (a.location, a.location_count, a.name.as_ref(), a.offset))
+            .collect();
+        assert_eq!(
+            attributes,
+            [
+                (0, 1, "position", 0),
+                (1, 4, "weights", 12),
+                (5, 1, "tex_coord", 76)
+            ]
+        );
+        assert_eq!(layout.stride, 84);
+    }
+
+    #[test]
+    fn spec_constants() {
+        let instructions = [
+            (crate::OP_DECORATE, vec![10, crate::DECORATION_SPEC_ID, 0]),
+            (crate::OP_DECORATE, vec![11, crate::DECORATION_SPEC_ID, 1]),
+            (crate::OP_DECORATE, vec![12, crate::DECORATION_SPEC_ID, 2]),
+            (crate::OP_DECORATE, vec![13, crate::DECORATION_SPEC_ID, 3]),
+            (crate::OP_DECORATE, vec![14, crate::DECORATION_SPEC_ID, 4]),
+            (crate::OP_TYPE_BOOL, vec![1]),
+            (crate::OP_TYPE_INT, vec![2, 32, 1]),
+            (crate::OP_TYPE_FLOAT, vec![3, 32]),
+            (crate::OP_TYPE_FLOAT, vec![4, 64]),
+            (crate::OP_SPEC_CONSTANT_TRUE, vec![1, 10]),
+            (crate::OP_SPEC_CONSTANT, vec![2, 11, -7i32 as u32]),
+            (crate::OP_SPEC_CONSTANT, vec![3, 12, 1.5f32.to_bits()]),
+            (crate::OP_SPEC_CONSTANT, vec![4, 13, 0, 0]),
+            // the type id doesn't resolve
+            (crate::OP_SPEC_CONSTANT, vec![99, 14, 1]),
+            // no SpecId
+            (crate::OP_SPEC_CONSTANT, vec![2, 15, 3]),
+        ];
+
+        let constants: Vec<(u32, SpecConstantValue)> = module("ShaderFrag", &instructions)
+            .get_spec_constants()
+            .into_iter()
+            .map(|c| (c.constant_id, c.default_value))
+            .collect();
+
+        assert_eq!(
+            constants,
+            [
+                (0, SpecConstantValue::Bool(true)),
+                (1, SpecConstantValue::Int(-7)),
+                (2, SpecConstantValue::Float(1.5)),
+                (3, SpecConstantValue::Unknown),
+                (4, SpecConstantValue::Unknown),
+            ]
+        );
+    }
+
+    #[test]
+    fn big_endian() {
+        let little = assemble(&uniform_block(0, 2, "MeshUBO"));
+        let big: Vec<u8> = little
+            .chunks_exact(4)
+            .flat_map(|w| [w[3], w[2], w[1], w[0]])
+            .collect();
+        assert_eq!(big[..4], [0x07, 0x23, 0x02, 0x03]);
+
+        let m = Module::from_code("ShaderVert".into(), &big).unwrap();
+        let uniforms = m.get_uniform_info().unwrap();
+        assert_eq!(uniforms[0].name.as_ref(), "MeshUBO");
+        assert_eq!(uniforms[0].binding, 2);
+
+        let mut garbage = little;
+        garbage[..4].copy_from_slice(&[1, 2, 3, 4]);
+        match Module::from_code("ShaderVert".into(), &garbage) {
+            Err(Error::IncorrectMagicWord(word)) => assert_eq!(word, 0x04030201),
+            r => panic!("expected IncorrectMagicWord, got {:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn arrayed_sampler_descriptor_count() {
+        let instructions = [
+            name(5, "textures"),
+            (
+                crate::OP_DECORATE,
+                vec![5, crate::DECORATION_DESCRIPTOR_SET, 0],
+            ),
+            (crate::OP_DECORATE, vec![5, crate::DECORATION_BINDING, 1]),
+            (crate::OP_TYPE_SAMPLER, vec![3]),
+            (crate::OP_TYPE_INT, vec![6, 32, 0]),
+            // the array length is the id of this constant, not its value
+            (crate::OP_CONSTANT, vec![6, 7, 8]),
+            (crate::OP_TYPE_ARRAY, vec![8, 3, 7]),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![4, crate::STORAGE_CLASS_UNIFORM_CONSTANT, 8],
+            ),
+            (
+                crate::OP_VARIABLE,
+                vec![4, 5, crate::STORAGE_CLASS_UNIFORM_CONSTANT],
+            ),
+        ];
+
+        let uniforms = module("ShaderFrag", &instructions)
+            .get_uniform_info()
+            .unwrap();
+        assert_eq!(uniforms[0].descriptor_count, 8);
+
+        let single = module("ShaderFrag", &sampler(0, 1))
+            .get_uniform_info()
+            .unwrap();
+        assert_eq!(single[0].descriptor_count, 1);
+    }
+
+    #[test]
+    fn uniform_block_size() {
+        let instructions = [
+            name(3, "CameraUBO"),
+            member_name(3, 0, "view_proj"),
+            member_name(3, 1, "position"),
+            (
+                crate::OP_DECORATE,
+                vec![5, crate::DECORATION_DESCRIPTOR_SET, 0],
+            ),
+            (crate::OP_DECORATE, vec![5, crate::DECORATION_BINDING, 0]),
+            (
+                crate::OP_MEMBER_DECORATE,
+                vec![3, 0, crate::DECORATION_OFFSET, 0],
+            ),
+            (
+                crate::OP_MEMBER_DECORATE,
+                vec![3, 1, crate::DECORATION_OFFSET, 64],
+            ),
+            (crate::OP_TYPE_FLOAT, vec![1, 32]),
+            (crate::OP_TYPE_VECTOR, vec![2, 1, 4]),
+            (crate::OP_TYPE_MATRIX, vec![6, 2, 4]),
+            (crate::OP_TYPE_VECTOR, vec![7, 1, 3]),
+            (crate::OP_TYPE_STRUCT, vec![3, 6, 7]),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![4, crate::STORAGE_CLASS_UNIFORM, 3],
+            ),
+            (crate::OP_VARIABLE, vec![4, 5, crate::STORAGE_CLASS_UNIFORM]),
+        ];
+
+        // mat4 + vec3 is 76 bytes, padded to 80
+        let uniforms = module("ShaderVert", &instructions)
+            .get_uniform_info()
+            .unwrap();
+        assert_eq!(uniforms[0].size, Some(80));
+
+        let sampler = module("ShaderFrag", &sampler(0, 1))
+            .get_uniform_info()
+            .unwrap();
+        assert_eq!(sampler[0].size, None);
+
+        let storage = module("ShaderComp", &storage_buffer(0, 0))
+            .get_uniform_info()
+            .unwrap();
+        assert_eq!(storage[0].size, None);
+    }
+
+    #[test]
+    fn input_attachment() {
+        let instructions = [
+            name(5, "g_albedo"),
+            (
+                crate::OP_DECORATE,
+                vec![5, crate::DECORATION_DESCRIPTOR_SET, 0],
+            ),
+            (crate::OP_DECORATE, vec![5, crate::DECORATION_BINDING, 0]),
+            (crate::OP_TYPE_FLOAT, vec![1, 32]),
+            // Dim SubpassData, sampled 2, format Unknown
+            (
+                crate::OP_TYPE_IMAGE,
+                vec![3, 1, crate::DIM_SUBPASS_DATA, 0, 0, 0, 2, 0],
+            ),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![4, crate::STORAGE_CLASS_UNIFORM_CONSTANT, 3],
+            ),
+            (
+                crate::OP_VARIABLE,
+                vec![4, 5, crate::STORAGE_CLASS_UNIFORM_CONSTANT],
+            ),
+        ];
+
+        let uniforms = module("ShaderFrag", &instructions)
+            .get_uniform_info()
+            .unwrap();
+        assert_eq!(uniforms[0].uniform_type, UniformType::InputAttachment);
+        assert_eq!(uniforms[0].uniform_type.to_string(), "input attachment");
+
+        let uniform_type = |instructions: &[(u32, Vec<u32>)]| {
+            module("Shader", instructions).get_uniform_info().unwrap()[0].uniform_type
+        };
+        assert_eq!(
+            uniform_type(&uniform_block(0, 0, "MeshUBO")),
+            UniformType::UniformBuffer
+        );
+        assert_eq!(
+            uniform_type(&storage_buffer(0, 0)),
+            UniformType::StorageBuffer
+        );
+        assert_eq!(uniform_type(&sampler(0, 0)), UniformType::Sampler);
+    }
+
+    #[test]
+    fn entry_point_interface() {
+        let entry_point = |execution_model: u32, id: u32, name: &str, interface: &[u32]| {
+            (
+                crate::OP_ENTRY_POINT,
+                [
+                    vec![execution_model, id],
+                    string_operands(name),
+                    interface.to_vec(),
+                ]
+                .concat(),
+            )
+        };
+        let instructions = [
+            entry_point(crate::EXECUTION_MODEL_VERTEX, 20, "vs_main", &[5, 6, 9]),
+            entry_point(crate::EXECUTION_MODEL_FRAGMENT, 21, "fs_main", &[7, 8]),
+            name(5, "position"),
+            name(6, "v_color"),
+            name(7, "f_color"),
+            name(8, "out_color"),
+            name(9, "gl_VertexIndex"),
+            (crate::OP_DECORATE, vec![5, crate::DECORATION_LOCATION, 0]),
+            (crate::OP_DECORATE, vec![6, crate::DECORATION_LOCATION, 0]),
+            (crate::OP_DECORATE, vec![7, crate::DECORATION_LOCATION, 0]),
+            (crate::OP_DECORATE, vec![8, crate::DECORATION_LOCATION, 0]),
+            (crate::OP_DECORATE, vec![9, crate::DECORATION_BUILT_IN, 42]),
+            (crate::OP_TYPE_INT, vec![10, 32, 1]),
+            (crate::OP_TYPE_FLOAT, vec![1, 32]),
+            (crate::OP_TYPE_VECTOR, vec![2, 1, 4]),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![3, crate::STORAGE_CLASS_INPUT, 2],
+            ),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![4, crate::STORAGE_CLASS_OUTPUT, 2],
+            ),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![11, crate::STORAGE_CLASS_INPUT, 10],
+            ),
+            (crate::OP_VARIABLE, vec![3, 5, crate::STORAGE_CLASS_INPUT]),
+            (crate::OP_VARIABLE, vec![4, 6, crate::STORAGE_CLASS_OUTPUT]),
+            (crate::OP_VARIABLE, vec![3, 7, crate::STORAGE_CLASS_INPUT]),
+            (crate::OP_VARIABLE, vec![4, 8, crate::STORAGE_CLASS_OUTPUT]),
+            (crate::OP_VARIABLE, vec![11, 9, crate::STORAGE_CLASS_INPUT]),
+        ];
+
+        let m = module("Shader", &instructions);
+        assert_eq!(m.get_inputs().count(), 2);
+
+        let names = |entry_point: &str| -> Vec<(Box<str>, u32)> {
+            m.get_interface(entry_point)
+                .unwrap()
+                .into_iter()
+                .map(|i| (i.name, i.storage_class))
+                .collect()
+        };
+        assert_eq!(
+            names("vs_main"),
+            [
+                ("position".into(), crate::STORAGE_CLASS_INPUT),
+                ("v_color".into(), crate::STORAGE_CLASS_OUTPUT)
+            ]
+        );
+        assert_eq!(
+            names("fs_main"),
+            [
+                ("f_color".into(), crate::STORAGE_CLASS_INPUT),
+                ("out_color".into(), crate::STORAGE_CLASS_OUTPUT)
+            ]
+        );
+        assert!(m.get_interface("cs_main").is_none());
+    }
+
+    fn image_uniform(binding: u32, combined: bool) -> Vec<(u32, Vec<u32>)> {
+        let pointee = if combined { 7 } else { 6 };
+        vec![
+            name(5, "tex"),
+            (
+                crate::OP_DECORATE,
+                vec![5, crate::DECORATION_DESCRIPTOR_SET, 0],
+            ),
+            (
+                crate::OP_DECORATE,
+                vec![5, crate::DECORATION_BINDING, binding],
+            ),
+            (crate::OP_TYPE_FLOAT, vec![1, 32]),
+            // Dim 2D, sampled 1
+            (crate::OP_TYPE_IMAGE, vec![6, 1, 1, 0, 0, 0, 1, 0]),
+            (crate::OP_TYPE_SAMPLED_IMAGE, vec![7, 6]),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![4, crate::STORAGE_CLASS_UNIFORM_CONSTANT, pointee],
+            ),
+            (
+                crate::OP_VARIABLE,
+                vec![4, 5, crate::STORAGE_CLASS_UNIFORM_CONSTANT],
+            ),
+        ]
+    }
+
+    #[test]
+    fn combined_image_sampler() {
+        // uniform sampler2D tex;
+        let combined = module("ShaderFrag", &image_uniform(0, true))
+            .get_uniform_info()
+            .unwrap();
+        assert_eq!(combined[0].uniform_type, UniformType::CombinedImageSampler);
+
+        // uniform texture2D tex; uniform sampler s;
+        let mut instructions = image_uniform(0, false);
+        instructions.extend([
+            name(15, "s"),
+            (
+                crate::OP_DECORATE,
+                vec![15, crate::DECORATION_DESCRIPTOR_SET, 0],
+            ),
+            (crate::OP_DECORATE, vec![15, crate::DECORATION_BINDING, 1]),
+            (crate::OP_TYPE_SAMPLER, vec![13]),
+            (
+                crate::OP_TYPE_POINTER,
+                vec![14, crate::STORAGE_CLASS_UNIFORM_CONSTANT, 13],
+            ),
+            (
+                crate::OP_VARIABLE,
+                vec![14, 15, crate::STORAGE_CLASS_UNIFORM_CONSTANT],
+            ),
+        ]);
+        let separate = module("ShaderFrag", &instructions)
+            .get_uniform_info()
+            .unwrap();
+        let types: Vec<(u32, UniformType)> = separate
+            .iter()
+            .map(|u| (u.binding, u.uniform_type))
+            .collect();
+        assert_eq!(
+            types,
+            [(0, UniformType::SampledImage), (1, UniformType::Sampler)]
+        );
+    }
+
+    #[test]
+    fn borrowed_words() {
+        let words: Vec<u32> = assemble(&storage_buffer(1, 4))
+            .chunks_exact(4)
+            .map(|c| u32::from_le_bytes(c.try_into().unwrap()))
+            .collect();
+
+        let borrowed = Module::from_words("ShaderComp".into(), &words).unwrap();
+        assert!(matches!(borrowed.words, std::borrow::Cow::Borrowed(_)));
+
+        let owned = module("ShaderComp", &storage_buffer(1, 4));
+        let describe = |m: &Module| -> Vec<(Box<str>, u32, u32, Option<u32>)> {
+            m.get_uniform_info()
+                .unwrap()
+                .into_iter()
+                .map(|u| (u.name, u.set, u.binding, u.element_size))
+                .collect()
+        };
+        assert_eq!(describe(&borrowed), describe(&owned));
+
+        // the last instruction claims one more word than there is
+        let mut truncated = words.clone();
+        let last = truncated.len() - 4;
+        truncated[last] += 1 << 16;
+        match Module::from_words("ShaderComp".into(), &truncated) {
+            Err(Error::InvalidOperandEnd((start, end))) => {
+                assert_eq!((start, end), (last, words.len() + 1))
+            }
+            r => panic!("expected InvalidOperandEnd, got {:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn missing_binding() {
+        let mut instructions = uniform_block(0, 0, "MeshUBO");
+        instructions.retain(|(opcode, operands)| {
+            *opcode != crate::OP_DECORATE || operands[1] != crate::DECORATION_BINDING
+        });
+
+        match module("ShaderVert", &instructions).get_uniform_info() {
+            Err(Error::MissingBinding(id)) => assert_eq!(id, 5),
+            r => panic!("expected MissingBinding, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn merged_stages() {
+        let vert = module("ShaderVert", &uniform_block(0, 0, "MeshUBO"));
+        let frag = module("ShaderFrag", &uniform_block(0, 0, "MeshUBO"));
+        let comp = module("ShaderComp", &storage_buffer(1, 2));
+
+        let layout = merge_stages(&[
+            (ShaderStage::Vertex, &vert),
+            (ShaderStage::Fragment, &frag),
+            (ShaderStage::Compute, &comp),
+        ])
+        .unwrap();
+
+        let bindings: Vec<(u32, u32, u32)> = layout
+            .bindings
+            .iter()
+            .map(|b| (b.info.set, b.info.binding, b.stage_flags))
+            .collect();
+        assert_eq!(bindings, [(0, 0, 0x11), (1, 2, 0x20)]);
+        assert_eq!(layout.set_count(), 2);
+        assert_eq!(layout.get_set(1).count(), 1);
+
+        let geom = module("ShaderGeom", &sampler(0, 0));
+        match merge_stages(&[(ShaderStage::Vertex, &vert), (ShaderStage::Geometry, &geom)]) {
+            Err(Error::ConflictingBinding { set, binding }) => assert_eq!((set, binding), (0, 0)),
+            r => panic!("expected ConflictingBinding, got {:?}", r),
+        }
+    }
 
     #[test]
     fn test1() {
@@ -0,0 +1,115 @@
+use crate::module::{
+    PushConstantInfo, ShaderIoInfo, SpecConstantInfo, SpecConstantValue, TypeInfo, UniformInfo,
+};
+
+#[derive(Debug)]
+pub struct ReflectionReport {
+    pub name: Box<str>,
+    pub version: (u32, u32),
+    pub generator: u32,
+    pub entry_points: Box<[String]>,
+    pub inputs: Box<[ShaderIoInfo]>,
+    pub outputs: Box<[ShaderIoInfo]>,
+    pub uniforms: Box<[UniformInfo]>,
+    pub push_constants: Box<[PushConstantInfo]>,
+    pub spec_constants: Box<[SpecConstantInfo]>,
+}
+
+fn spec_value(value: &SpecConstantValue) -> String {
+    match value {
+        SpecConstantValue::Bool(b) => b.to_string(),
+        SpecConstantValue::Int(i) => i.to_string(),
+        SpecConstantValue::UInt(u) => u.to_string(),
+        SpecConstantValue::Float(x) => format!("{:?}", x),
+        SpecConstantValue::Unknown => String::from("?"),
+    }
+}
+
+fn type_name(ty: &TypeInfo) -> String {
+    match ty {
+        TypeInfo::Void => String::from("void"),
+        TypeInfo::Bool => String::from("bool"),
+        TypeInfo::Int { name, .. }
+        | TypeInfo::Float { name, .. }
+        | TypeInfo::Vec { name, .. }
+        | TypeInfo::Mat { name, .. }
+        | TypeInfo::Struct { name, .. } => name.to_string(),
+        TypeInfo::Pointer { ptr_type } => type_name(ptr_type),
+        TypeInfo::Image { .. } => String::from("image"),
+        TypeInfo::Sampler => String::from("sampler"),
+        TypeInfo::SampledImage { .. } => String::from("sampled_image"),
+        TypeInfo::Array {
+            element_type,
+            element_count,
+        } => format!("{}[{}]", type_name(element_type), element_count),
+        TypeInfo::RuntimeArray { element_type } => format!("{}[]", type_name(element_type)),
+    }
+}
+
+impl std::fmt::Display for ReflectionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.name)?;
+        writeln!(
+            f,
+            "  SPIR-V {}.{}, generator {:#010x}",
+            self.version.0, self.version.1, self.generator
+        )?;
+        writeln!(f, "  entry points: {}", self.entry_points.join(", "))?;
+
+        writeln!(f, "  inputs ({}):", self.inputs.len())?;
+        for i in self.inputs.iter() {
+            writeln!(
+                f,
+                "    location {}: {} {}",
+                i.location,
+                type_name(&i.type_info),
+                i.name
+            )?;
+        }
+
+        writeln!(f, "  outputs ({}):", self.outputs.len())?;
+        for o in self.outputs.iter() {
+            writeln!(
+                f,
+                "    location {}: {} {}",
+                o.location,
+                type_name(&o.type_info),
+                o.name
+            )?;
+        }
+
+        writeln!(f, "  uniforms ({}):", self.uniforms.len())?;
+        for u in self.uniforms.iter() {
+            writeln!(
+                f,
+                "    set {} binding {}: {} (count {})",
+                u.set,
+                u.binding,
+                type_name(&u.ty),
+                u.descriptor_count
+            )?;
+        }
+
+        writeln!(f, "  push constants ({}):", self.push_constants.len())?;
+        for p in self.push_constants.iter() {
+            writeln!(f, "    {}: offset {}, {} bytes", p.name, p.offset, p.size)?;
+            for m in p.members.iter() {
+                writeln!(f, "      {}: offset {}, {} bytes", m.name, m.offset, m.size)?;
+            }
+        }
+
+        writeln!(f, "  spec constants ({}):", self.spec_constants.len())?;
+        for c in self.spec_constants.iter() {
+            let ty = c.type_info.as_ref().map_or(String::from("?"), type_name);
+            writeln!(
+                f,
+                "    constant_id {}: {} = {}",
+                c.constant_id,
+                ty,
+                spec_value(&c.default_value)
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -10,6 +10,21 @@ pub enum Error {
     LocationMissing(u32),
     NameMissing(u32),
     DecorationMissing(u32),
+    MissingDescriptorSet(u32),
+    MissingBinding(u32),
+    ConflictingBinding {
+        set: u32,
+        binding: u32,
+    },
+    DuplicateUniformName(Box<str>),
+    UnsizedInput(u32),
+    VertexOffsetMissing(u32),
+    VertexAttributeOutOfBounds {
+        location: u32,
+        offset: u32,
+        size: u32,
+        stride: u32,
+    },
 }
 
 impl std::fmt::Display for Error {
@@ -47,6 +62,42 @@ impl std::fmt::Display for Error {
             Self::LocationMissing(id) => write!(f, "Missing location for id {id}"),
             Self::NameMissing(id) => write!(f, "Missing name for id {id}"),
             Self::DecorationMissing(id) => write!(f, "Missing decoration for id {id}"),
+            Self::MissingDescriptorSet(id) => {
+                write!(f, "Uniform variable {id} has no DescriptorSet decoration")
+            }
+            Self::MissingBinding(id) => {
+                write!(f, "Uniform variable {id} has no Binding decoration")
+            }
+            Self::ConflictingBinding { set, binding } => {
+                write!(
+                    f,
+                    "Conflicting uniform definitions for set {set}, binding {binding}"
+                )
+            }
+            Self::DuplicateUniformName(name) => {
+                write!(f, "Uniform name {name} is used by more than one binding")
+            }
+            Self::UnsizedInput(location) => {
+                write!(f, "Input at location {location} does not have a known size")
+            }
+            Self::VertexOffsetMissing(location) => {
+                write!(
+                    f,
+                    "No vertex offset given for the input at location {location}"
+                )
+            }
+            Self::VertexAttributeOutOfBounds {
+                location,
+                offset,
+                size,
+                stride,
+            } => {
+                write!(
+                    f,
+                    "Input at location {location} spans {offset}..{} which does not fit in a stride of {stride}",
+                    offset + size
+                )
+            }
         }
     }
 }
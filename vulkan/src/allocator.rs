@@ -1,3 +1,8 @@
+use crate::device::{SharedDeviceRef, SharedRef};
+use crate::result::{Error, Result};
+
+use ash::vk;
+
 pub fn find_memory_index(
     memory_properties: ash::vk::PhysicalDeviceMemoryProperties,
     memory_requirements: ash::vk::MemoryRequirements,
@@ -14,3 +19,286 @@ pub fn find_memory_index(
     }
     return None;
 }
+
+pub const DEFAULT_BLOCK_SIZE: vk::DeviceSize = 64 * 1024 * 1024;
+
+// the free ranges of a block as (offset, size), sorted by offset and never adjacent
+struct FreeList {
+    ranges: Vec<(vk::DeviceSize, vk::DeviceSize)>,
+}
+
+impl FreeList {
+    fn new(size: vk::DeviceSize) -> Self {
+        Self {
+            ranges: vec![(0, size)],
+        }
+    }
+
+    // first fit. returns the offset of the allocation
+    fn allocate(
+        &mut self,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+    ) -> Option<vk::DeviceSize> {
+        let (i, offset) = self
+            .ranges
+            .iter()
+            .enumerate()
+            .find_map(|(i, &(start, len))| {
+                let offset = start.next_multiple_of(alignment);
+                (offset + size <= start + len).then_some((i, offset))
+            })?;
+
+        let (start, len) = self.ranges.remove(i);
+        let end = offset + size;
+        if end < start + len {
+            self.ranges.insert(i, (end, start + len - end));
+        }
+        if start < offset {
+            self.ranges.insert(i, (start, offset - start));
+        }
+
+        Some(offset)
+    }
+
+    fn free(&mut self, offset: vk::DeviceSize, size: vk::DeviceSize) {
+        let i = self.ranges.partition_point(|&(start, _)| start < offset);
+        self.ranges.insert(i, (offset, size));
+
+        // merge with the following range, then with the previous one
+        if i + 1 < self.ranges.len() && offset + size == self.ranges[i + 1].0 {
+            self.ranges[i].1 += self.ranges.remove(i + 1).1;
+        }
+        if i > 0 && self.ranges[i - 1].0 + self.ranges[i - 1].1 == offset {
+            self.ranges[i - 1].1 += self.ranges.remove(i).1;
+        }
+    }
+}
+
+struct Block {
+    memory: vk::DeviceMemory,
+    memory_type_index: u32,
+    // host visible blocks stay mapped for their whole life, since a vk::DeviceMemory
+    // can't be mapped by two resources at once. null otherwise
+    mapped: *mut std::ffi::c_void,
+    free_list: FreeList,
+}
+
+// mapped points into a block that stays mapped until the allocator is dropped and is never
+// remapped. blocks are only touched under the allocator's mutex
+unsafe impl Send for Block {}
+
+// finds room in an existing block of the given memory type. returns (block index, offset)
+fn suballocate(
+    blocks: &mut [Block],
+    memory_type_index: u32,
+    size: vk::DeviceSize,
+    alignment: vk::DeviceSize,
+) -> Option<(usize, vk::DeviceSize)> {
+    blocks.iter_mut().enumerate().find_map(|(i, block)| {
+        if block.memory_type_index != memory_type_index {
+            return None;
+        }
+        let offset = block.free_list.allocate(size, alignment)?;
+        Some((i, offset))
+    })
+}
+
+pub struct Allocation {
+    pub memory: vk::DeviceMemory,
+    pub offset: vk::DeviceSize,
+    pub size: vk::DeviceSize,
+    // null unless the memory is host visible
+    pub mapped: *mut std::ffi::c_void,
+    block: usize,
+}
+
+// like Block, mapped stays valid for as long as the allocator lives. writing through it is
+// up to the owner of the allocation, which has the range to itself
+unsafe impl Send for Allocation {}
+unsafe impl Sync for Allocation {}
+
+pub type SharedAllocatorRef = SharedRef<Allocator>;
+
+// hands out ranges of large vk::DeviceMemory blocks so resources don't each need their own
+// allocation. blocks are only freed when the allocator is dropped
+pub struct Allocator {
+    device: SharedDeviceRef,
+    block_size: vk::DeviceSize,
+    // linear and optimal resources in one block have to be this far apart
+    buffer_image_granularity: vk::DeviceSize,
+    blocks: std::sync::Mutex<Vec<Block>>,
+}
+
+impl Allocator {
+    #[inline]
+    pub fn new(device: SharedDeviceRef) -> SharedAllocatorRef {
+        Self::with_block_size(device, DEFAULT_BLOCK_SIZE)
+    }
+
+    pub fn with_block_size(
+        device: SharedDeviceRef,
+        block_size: vk::DeviceSize,
+    ) -> SharedAllocatorRef {
+        let properties = unsafe { device.get_physical_device_properties() };
+
+        Allocator {
+            device,
+            block_size,
+            buffer_image_granularity: properties.limits.buffer_image_granularity,
+            blocks: std::sync::Mutex::new(Vec::new()),
+        }
+        .into()
+    }
+
+    pub fn allocate(
+        &self,
+        requirements: vk::MemoryRequirements,
+        properties: vk::MemoryPropertyFlags,
+    ) -> Result<Allocation> {
+        let memory_properties = unsafe { self.device.get_physical_device_memory_properties() };
+        let memory_type_index = find_memory_index(memory_properties, requirements, properties)
+            .ok_or(Error::CouldNotFindMemoryTypeIndex(properties))?;
+        // blocks are shared by every request for this type, so mapping depends on the type
+        // itself rather than on what this request asked for
+        let type_flags = memory_properties.memory_types[memory_type_index as usize].property_flags;
+
+        // aligning everything to the granularity keeps buffers and images from conflicting
+        // without tracking which kind of resource each range holds
+        let alignment = requirements
+            .alignment
+            .max(self.buffer_image_granularity)
+            .max(1);
+
+        let mut blocks = self.blocks.lock().unwrap();
+
+        let existing = suballocate(&mut blocks, memory_type_index, requirements.size, alignment);
+
+        let (block_index, offset) = match existing {
+            Some(found) => found,
+            None => {
+                let size = self.block_size.max(requirements.size);
+                let mut block = self.create_block(memory_type_index, type_flags, size)?;
+                let offset = block
+                    .free_list
+                    .allocate(requirements.size, alignment)
+                    .expect("a new block fits the allocation");
+                blocks.push(block);
+                (blocks.len() - 1, offset)
+            }
+        };
+
+        let block = &blocks[block_index];
+        Ok(Allocation {
+            memory: block.memory,
+            offset,
+            size: requirements.size,
+            mapped: if block.mapped.is_null() {
+                std::ptr::null_mut()
+            } else {
+                unsafe { block.mapped.byte_add(offset as usize) }
+            },
+            block: block_index,
+        })
+    }
+
+    pub fn free(&self, allocation: Allocation) {
+        let mut blocks = self.blocks.lock().unwrap();
+
+        blocks[allocation.block]
+            .free_list
+            .free(allocation.offset, allocation.size);
+    }
+
+    fn create_block(
+        &self,
+        memory_type_index: u32,
+        type_flags: vk::MemoryPropertyFlags,
+        size: vk::DeviceSize,
+    ) -> Result<Block> {
+        let allocate_info = vk::MemoryAllocateInfo {
+            allocation_size: size,
+            memory_type_index,
+            ..Default::default()
+        };
+        let memory = unsafe { self.device.allocate_memory(&allocate_info) }?;
+
+        let mapped = if type_flags.contains(vk::MemoryPropertyFlags::HOST_VISIBLE) {
+            unsafe {
+                self.device
+                    .map_memory(memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty())
+            }
+            .inspect_err(|_| unsafe { self.device.free_memory(memory) })?
+        } else {
+            std::ptr::null_mut()
+        };
+
+        Ok(Block {
+            memory,
+            memory_type_index,
+            mapped,
+            free_list: FreeList::new(size),
+        })
+    }
+}
+
+impl Drop for Allocator {
+    fn drop(&mut self) {
+        for block in self.blocks.get_mut().unwrap().iter() {
+            unsafe { self.device.free_memory(block.memory) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Block, FreeList, suballocate};
+    use ash::vk::{self, Handle};
+
+    #[test]
+    fn free_list() {
+        let mut list = FreeList::new(1024);
+
+        // two small allocations come out of the same range
+        let a = list.allocate(100, 64).unwrap();
+        let b = list.allocate(100, 64).unwrap();
+        assert_eq!((a, b), (0, 128));
+        assert_eq!(list.ranges, [(100, 28), (228, 796)]);
+
+        assert_eq!(list.allocate(2048, 1), None);
+
+        // freeing merges neighboring ranges back together
+        list.free(a, 100);
+        assert_eq!(list.ranges, [(0, 128), (228, 796)]);
+        list.free(b, 100);
+        assert_eq!(list.ranges, [(0, 1024)]);
+
+        let c = list.allocate(1024, 256).unwrap();
+        assert_eq!(c, 0);
+        assert!(list.ranges.is_empty());
+    }
+
+    #[test]
+    fn block_reuse() {
+        let block = |memory: u64, memory_type_index: u32| Block {
+            memory: vk::DeviceMemory::from_raw(memory),
+            memory_type_index,
+            mapped: std::ptr::null_mut(),
+            free_list: FreeList::new(1024),
+        };
+        let mut blocks = vec![block(1, 0), block(2, 1)];
+
+        // two small buffers end up in the same vk::DeviceMemory
+        let a = suballocate(&mut blocks, 1, 256, 64).unwrap();
+        let b = suballocate(&mut blocks, 1, 256, 64).unwrap();
+        assert_eq!((a, b), ((1, 0), (1, 256)));
+        assert_eq!(blocks[a.0].memory, blocks[b.0].memory);
+
+        // other memory types never share a block
+        assert_eq!(suballocate(&mut blocks, 2, 256, 64), None);
+
+        // a full block means a new one has to be created
+        assert_eq!(suballocate(&mut blocks, 1, 1024, 64), None);
+        assert_eq!(suballocate(&mut blocks, 0, 1024, 64), Some((0, 0)));
+    }
+}
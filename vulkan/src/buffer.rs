@@ -1,4 +1,4 @@
-use crate::allocator::find_memory_index;
+use crate::allocator::{Allocation, SharedAllocatorRef, find_memory_index};
 use crate::device::SharedDeviceRef;
 use crate::result::{Error, Result};
 
@@ -16,10 +16,50 @@ pub struct Buffer {
     pub handle: vk::Buffer,
     pub memory: vk::DeviceMemory,
     pub size: vk::DeviceSize,
+    // where the buffer starts in memory
     pub offset: vk::DeviceSize,
+    // set if the memory came from an allocator instead of its own vkAllocateMemory
+    allocation: Option<(SharedAllocatorRef, Allocation)>,
 }
 
 impl Buffer {
+    // suballocates the buffer's memory from allocator
+    pub fn new_in(
+        device: SharedDeviceRef,
+        create_info: &BufferCreateInfo,
+        allocator: &SharedAllocatorRef,
+    ) -> Result<Self> {
+        let buffer_create_info = vk::BufferCreateInfo {
+            size: create_info.size,
+            usage: create_info.usage,
+            sharing_mode: vk::SharingMode::EXCLUSIVE,
+            ..Default::default()
+        };
+
+        let buffer = unsafe { device.create_buffer(&buffer_create_info) }?;
+
+        let memory_requirements = unsafe { device.get_buffer_memory_requirements(buffer) };
+        let allocation = allocator
+            .allocate(memory_requirements, create_info.memory_property_flags)
+            .inspect_err(|_| unsafe {
+                device.destroy_buffer(buffer);
+            })?;
+
+        unsafe { device.bind_buffer_memory(buffer, allocation.memory, allocation.offset) }
+            .inspect_err(|_| unsafe {
+                device.destroy_buffer(buffer);
+            })?;
+
+        Ok(Buffer {
+            device,
+            handle: buffer,
+            memory: allocation.memory,
+            size: create_info.size,
+            offset: allocation.offset,
+            allocation: Some((allocator.clone(), allocation)),
+        })
+    }
+
     pub fn new(device: SharedDeviceRef, create_info: &BufferCreateInfo) -> Result<Self> {
         let buffer_create_info = vk::BufferCreateInfo {
             size: create_info.size,
@@ -66,32 +106,81 @@ impl Buffer {
             memory,
             size: create_info.size,
             offset,
+            allocation: None,
         })
     }
 
-    #[inline]
+    // creates a host visible buffer holding a copy of data. vulkan doesn't allow 0 sized
+    // buffers, so empty data is an error
+    pub fn from_slice(
+        device: SharedDeviceRef,
+        data: &[u8],
+        usage: vk::BufferUsageFlags,
+    ) -> Result<Self> {
+        if data.is_empty() {
+            return Err(Error::EmptyBuffer);
+        }
+
+        let size = data.len();
+        let create_info = BufferCreateInfo {
+            size: size as vk::DeviceSize,
+            usage,
+            memory_property_flags: vk::MemoryPropertyFlags::HOST_VISIBLE
+                | vk::MemoryPropertyFlags::HOST_COHERENT,
+        };
+
+        let buffer = Self::new(device, &create_info)?;
+
+        unsafe {
+            let dst = buffer.map_memory(0, buffer.size)?;
+
+            std::ptr::copy_nonoverlapping(data.as_ptr(), dst as *mut u8, size);
+
+            buffer.unmap();
+        }
+
+        Ok(buffer)
+    }
+
+    // offset is relative to the start of the buffer
     pub unsafe fn map_memory(
         &self,
         offset: vk::DeviceSize,
         size: vk::DeviceSize,
     ) -> ash::prelude::VkResult<*mut std::ffi::c_void> {
-        unsafe {
-            self.device
-                .map_memory(self.memory, offset, size, vk::MemoryMapFlags::empty())
+        match &self.allocation {
+            // allocator memory is always mapped
+            Some((_, allocation)) if allocation.mapped.is_null() => {
+                Err(vk::Result::ERROR_MEMORY_MAP_FAILED)
+            }
+            Some((_, allocation)) => Ok(unsafe { allocation.mapped.byte_add(offset as usize) }),
+            None => unsafe {
+                self.device.map_memory(
+                    self.memory,
+                    self.offset + offset,
+                    size,
+                    vk::MemoryMapFlags::empty(),
+                )
+            },
         }
     }
 
     #[inline]
     pub unsafe fn unmap(&self) {
-        unsafe { self.device.unmap_memory(self.memory) }
+        if self.allocation.is_none() {
+            unsafe { self.device.unmap_memory(self.memory) }
+        }
     }
 }
 
 impl Drop for Buffer {
     fn drop(&mut self) {
         unsafe {
-            self.device.free_memory(self.memory);
             self.device.destroy_buffer(self.handle);
+            match self.allocation.take() {
+                Some((allocator, allocation)) => allocator.free(allocation),
+                None => self.device.free_memory(self.memory),
+            }
         }
     }
 }
@@ -183,6 +272,13 @@ pub struct DynamicUniformBV {
     pub size: vk::DeviceSize,
 }
 
+// read and written by shaders, e.g. compute
+pub struct StorageBV {
+    pub buffer: Rc<Buffer>,
+    pub offset: vk::DeviceSize,
+    pub size: vk::DeviceSize,
+}
+
 impl std::fmt::Display for DynamicUniformBV {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -192,3 +288,99 @@ impl std::fmt::Display for DynamicUniformBV {
         )
     }
 }
+
+// any of the views above, so vertex, index, uniform and storage buffers can be kept together
+pub enum BufferView {
+    Vertex(VertexBV),
+    Index(IndexBV),
+    Uniform(UniformBV),
+    DynamicUniform(DynamicUniformBV),
+    Storage(StorageBV),
+}
+
+impl BufferView {
+    #[inline]
+    pub fn buffer(&self) -> &Rc<Buffer> {
+        match self {
+            Self::Vertex(view) => &view.buffer,
+            Self::Index(view) => &view.buffer,
+            Self::Uniform(view) => &view.buffer,
+            Self::DynamicUniform(view) => &view.buffer,
+            Self::Storage(view) => &view.buffer,
+        }
+    }
+
+    // (offset, size) of the uniform range. None for vertex and index views
+    #[inline]
+    pub fn uniform_range(&self) -> Option<(vk::DeviceSize, vk::DeviceSize)> {
+        match self {
+            Self::Uniform(view) => Some((view.offset, view.size)),
+            Self::DynamicUniform(view) => Some((view.offset, view.size)),
+            _ => None,
+        }
+    }
+
+    // (offset, size) of the range a descriptor can point at. None for vertex and index views
+    #[inline]
+    pub fn descriptor_range(&self) -> Option<(vk::DeviceSize, vk::DeviceSize)> {
+        match self {
+            Self::Storage(view) => Some((view.offset, view.size)),
+            _ => self.uniform_range(),
+        }
+    }
+
+    // uniform and storage buffers are bound through descriptor sets, so this does nothing
+    // for them
+    pub unsafe fn bind(&self, cmd: vk::CommandBuffer) {
+        match self {
+            Self::Vertex(view) => unsafe { view.bind(cmd) },
+            Self::Index(view) => unsafe { view.bind(cmd) },
+            Self::Uniform(_) | Self::DynamicUniform(_) | Self::Storage(_) => {}
+        }
+    }
+
+    // an index view draws indexed, so the vertex buffers have to be bound first.
+    // does nothing for uniform and storage views
+    pub unsafe fn draw(&self, cmd: vk::CommandBuffer) {
+        match self {
+            Self::Vertex(view) => unsafe { view.draw(cmd) },
+            Self::Index(view) => unsafe { view.draw(cmd) },
+            Self::Uniform(_) | Self::DynamicUniform(_) | Self::Storage(_) => {}
+        }
+    }
+}
+
+impl From<VertexBV> for BufferView {
+    #[inline]
+    fn from(value: VertexBV) -> Self {
+        Self::Vertex(value)
+    }
+}
+
+impl From<IndexBV> for BufferView {
+    #[inline]
+    fn from(value: IndexBV) -> Self {
+        Self::Index(value)
+    }
+}
+
+impl From<UniformBV> for BufferView {
+    #[inline]
+    fn from(value: UniformBV) -> Self {
+        Self::Uniform(value)
+    }
+}
+
+impl From<DynamicUniformBV> for BufferView {
+    #[inline]
+    fn from(value: DynamicUniformBV) -> Self {
+        Self::DynamicUniform(value)
+    }
+}
+
+impl From<StorageBV> for BufferView {
+    #[inline]
+    fn from(value: StorageBV) -> Self {
+        Self::Storage(value)
+    }
+}
@@ -0,0 +1,217 @@
+use crate::device::{SharedDeviceRef, SharedRef};
+use crate::result::{Error, Result};
+
+use ash::vk;
+
+// command buffers from one pool can only be recorded by one thread at a time, so each
+// recording thread needs its own pool
+pub struct CommandPool {
+    device: SharedDeviceRef,
+    pub handle: vk::CommandPool,
+}
+
+impl CommandPool {
+    pub fn new(
+        device: SharedDeviceRef,
+        flags: vk::CommandPoolCreateFlags,
+    ) -> Result<SharedRef<Self>> {
+        let create_info = vk::CommandPoolCreateInfo {
+            flags,
+            queue_family_index: device.get_queue_family_index(),
+            ..Default::default()
+        };
+
+        let handle = unsafe { device.create_command_pool(&create_info) }?;
+
+        Ok(CommandPool { device, handle }.into())
+    }
+
+    // resets every command buffer allocated from the pool. none of them can be pending
+    pub unsafe fn reset(&self) -> Result<()> {
+        unsafe {
+            self.device
+                .reset_command_pool(self.handle, vk::CommandPoolResetFlags::empty())
+        }?;
+
+        Ok(())
+    }
+}
+
+impl Drop for CommandPool {
+    fn drop(&mut self) {
+        unsafe { self.device.destroy_command_pool(self.handle) };
+    }
+}
+
+// what a secondary command buffer needs to know about the dynamic rendering it's executed in.
+// has to match the vk::RenderingInfo of the primary's cmd_begin_rendering
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderingInheritance {
+    pub color_formats: Box<[vk::Format]>,
+    pub depth_format: vk::Format,
+    pub stencil_format: vk::Format,
+    pub samples: vk::SampleCountFlags,
+}
+
+impl RenderingInheritance {
+    // borrows color_formats
+    pub fn create_info(&self) -> vk::CommandBufferInheritanceRenderingInfo<'_> {
+        vk::CommandBufferInheritanceRenderingInfo {
+            color_attachment_count: self.color_formats.len() as u32,
+            p_color_attachment_formats: self.color_formats.as_ptr(),
+            depth_attachment_format: self.depth_format,
+            stencil_attachment_format: self.stencil_format,
+            rasterization_samples: self.samples,
+            ..Default::default()
+        }
+    }
+}
+
+pub struct CommandBuffer {
+    pool: SharedRef<CommandPool>,
+    pub handle: vk::CommandBuffer,
+    level: vk::CommandBufferLevel,
+}
+
+impl CommandBuffer {
+    pub fn allocate(
+        pool: &SharedRef<CommandPool>,
+        level: vk::CommandBufferLevel,
+        count: u32,
+    ) -> Result<Box<[Self]>> {
+        let allocate_info = vk::CommandBufferAllocateInfo {
+            command_pool: pool.handle,
+            level,
+            command_buffer_count: count,
+            ..Default::default()
+        };
+
+        let handles = unsafe { pool.device.allocate_command_buffers(&allocate_info) }?;
+
+        Ok(handles
+            .into_iter()
+            .map(|handle| CommandBuffer {
+                pool: pool.clone(),
+                handle,
+                level,
+            })
+            .collect())
+    }
+
+    #[inline]
+    pub fn primary(pool: &SharedRef<CommandPool>) -> Result<Self> {
+        Ok(Self::allocate(pool, vk::CommandBufferLevel::PRIMARY, 1)?
+            .into_vec()
+            .remove(0))
+    }
+
+    #[inline]
+    pub fn secondary(pool: &SharedRef<CommandPool>) -> Result<Self> {
+        Ok(Self::allocate(pool, vk::CommandBufferLevel::SECONDARY, 1)?
+            .into_vec()
+            .remove(0))
+    }
+
+    #[inline]
+    pub fn level(&self) -> vk::CommandBufferLevel {
+        self.level
+    }
+
+    fn check_level(&self, expected: vk::CommandBufferLevel) -> Result<()> {
+        if self.level != expected {
+            return Err(Error::CommandBufferLevelMismatch {
+                expected,
+                actual: self.level,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub unsafe fn begin(&self, flags: vk::CommandBufferUsageFlags) -> Result<()> {
+        self.check_level(vk::CommandBufferLevel::PRIMARY)?;
+
+        let begin_info = vk::CommandBufferBeginInfo {
+            flags,
+            ..Default::default()
+        };
+        unsafe {
+            self.pool
+                .device
+                .begin_command_buffer(self.handle, &begin_info)
+        }?;
+
+        Ok(())
+    }
+
+    // for recording draws that are executed inside of a primary's dynamic rendering.
+    // viewport and scissor aren't inherited, so they have to be set again
+    pub unsafe fn begin_secondary(
+        &self,
+        flags: vk::CommandBufferUsageFlags,
+        rendering: &RenderingInheritance,
+    ) -> Result<()> {
+        self.check_level(vk::CommandBufferLevel::SECONDARY)?;
+
+        let rendering_info = rendering.create_info();
+        let inheritance_info = vk::CommandBufferInheritanceInfo {
+            p_next: &rendering_info as *const _ as *const std::ffi::c_void,
+            ..Default::default()
+        };
+        let begin_info = vk::CommandBufferBeginInfo {
+            flags: flags | vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE,
+            p_inheritance_info: &inheritance_info,
+            ..Default::default()
+        };
+        unsafe {
+            self.pool
+                .device
+                .begin_command_buffer(self.handle, &begin_info)
+        }?;
+
+        Ok(())
+    }
+
+    #[inline]
+    pub unsafe fn end(&self) -> Result<()> {
+        unsafe { self.pool.device.end_command_buffer(self.handle) }?;
+
+        Ok(())
+    }
+}
+
+impl Drop for CommandBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            self.pool
+                .device
+                .free_command_buffers(self.pool.handle, &[self.handle])
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendering_inheritance() {
+        let inheritance = RenderingInheritance {
+            color_formats: Box::new([vk::Format::B8G8R8A8_SRGB]),
+            depth_format: vk::Format::D32_SFLOAT_S8_UINT,
+            // the render context begins rendering without a stencil attachment
+            stencil_format: vk::Format::UNDEFINED,
+            samples: vk::SampleCountFlags::TYPE_4,
+        };
+
+        let info = inheritance.create_info();
+        assert_eq!(info.color_attachment_count, 1);
+        assert_eq!(
+            unsafe { *info.p_color_attachment_formats },
+            vk::Format::B8G8R8A8_SRGB
+        );
+        assert_eq!(info.depth_attachment_format, vk::Format::D32_SFLOAT_S8_UINT);
+        assert_eq!(info.stencil_attachment_format, vk::Format::UNDEFINED);
+        assert_eq!(info.rasterization_samples, vk::SampleCountFlags::TYPE_4);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::buffer::{Buffer, DynamicUniformBV};
 use crate::device::SharedDeviceRef;
 
 use ash::prelude::VkResult;
@@ -13,6 +14,25 @@ pub struct DescriptorSetLayoutBindingInfo {
     pub size: Option<u32>,
 }
 
+impl DescriptorSetLayoutBindingInfo {
+    #[inline]
+    pub fn is_dynamic(&self) -> bool {
+        matches!(
+            self.descriptor_type,
+            vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC | vk::DescriptorType::STORAGE_BUFFER_DYNAMIC
+        )
+    }
+}
+
+// binding a set takes one dynamic offset per element of every dynamic binding, in binding order
+pub fn dynamic_offset_count(bindings: &[DescriptorSetLayoutBindingInfo]) -> u32 {
+    bindings
+        .iter()
+        .filter(|b| b.is_dynamic())
+        .map(|b| b.descriptor_count)
+        .sum()
+}
+
 impl std::fmt::Display for DescriptorSetLayoutBindingInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -61,6 +81,14 @@ impl DescriptorSetLayout {
             handle,
         })
     }
+    #[inline]
+    pub fn get_binding(&self, binding: u32) -> Option<&DescriptorSetLayoutBindingInfo> {
+        self.bindings.iter().find(|b| b.binding == binding)
+    }
+    #[inline]
+    pub fn dynamic_offset_count(&self) -> u32 {
+        dynamic_offset_count(&self.bindings)
+    }
 }
 
 impl Drop for DescriptorSetLayout {
@@ -88,3 +116,214 @@ impl std::fmt::Display for DescriptorSetLayout {
         write!(f, "], handle: {:?}}}", self.handle)
     }
 }
+
+// every binding's descriptors are counted max_sets times, so any max_sets sets allocated
+// from the layouts the bindings came from fit in the pool
+pub fn tally_pool_sizes<'a>(
+    bindings: impl IntoIterator<Item = &'a DescriptorSetLayoutBindingInfo>,
+    max_sets: u32,
+) -> Vec<vk::DescriptorPoolSize> {
+    let mut pool_sizes = Vec::<vk::DescriptorPoolSize>::new();
+
+    for binding in bindings {
+        let descriptor_count = binding.descriptor_count * max_sets;
+        match pool_sizes
+            .iter_mut()
+            .find(|s| s.ty == binding.descriptor_type)
+        {
+            Some(size) => size.descriptor_count += descriptor_count,
+            None => pool_sizes.push(vk::DescriptorPoolSize {
+                ty: binding.descriptor_type,
+                descriptor_count,
+            }),
+        }
+    }
+
+    // a size of 0 is invalid
+    pool_sizes.retain(|s| s.descriptor_count > 0);
+    pool_sizes
+}
+
+pub struct DescriptorPool {
+    device: SharedDeviceRef,
+    pub handle: vk::DescriptorPool,
+}
+
+impl DescriptorPool {
+    pub fn new(
+        device: SharedDeviceRef,
+        create_info: &vk::DescriptorPoolCreateInfo,
+    ) -> VkResult<DescriptorPool> {
+        let handle = unsafe { device.create_descriptor_pool(create_info) }?;
+
+        Ok(DescriptorPool { device, handle })
+    }
+    // sized by tally_pool_sizes
+    pub fn from_layouts(
+        device: SharedDeviceRef,
+        layouts: &[&DescriptorSetLayout],
+        max_sets: u32,
+    ) -> VkResult<DescriptorPool> {
+        let pool_sizes = tally_pool_sizes(layouts.iter().flat_map(|l| l.bindings.iter()), max_sets);
+        let create_info = vk::DescriptorPoolCreateInfo {
+            max_sets,
+            pool_size_count: pool_sizes.len() as u32,
+            p_pool_sizes: pool_sizes.as_ptr(),
+            ..Default::default()
+        };
+
+        Self::new(device, &create_info)
+    }
+}
+
+impl Drop for DescriptorPool {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_descriptor_pool(self.handle);
+        }
+    }
+}
+
+// the set is owned by the pool it came from, so dropping this does not free it
+pub struct DescriptorSet {
+    device: SharedDeviceRef,
+    pub handle: vk::DescriptorSet,
+}
+
+impl DescriptorSet {
+    pub fn allocate(
+        device: SharedDeviceRef,
+        pool: vk::DescriptorPool,
+        layouts: &[vk::DescriptorSetLayout],
+    ) -> VkResult<Box<[DescriptorSet]>> {
+        let allocate_info = vk::DescriptorSetAllocateInfo {
+            descriptor_pool: pool,
+            descriptor_set_count: layouts.len() as u32,
+            p_set_layouts: layouts.as_ptr(),
+            ..Default::default()
+        };
+
+        let handles = unsafe { device.allocate_descriptor_sets(&allocate_info) }?;
+
+        Ok(handles
+            .into_iter()
+            .map(|handle| DescriptorSet {
+                device: device.clone(),
+                handle,
+            })
+            .collect())
+    }
+    pub fn write_buffer(
+        &self,
+        binding: u32,
+        buffer: &Buffer,
+        offset: vk::DeviceSize,
+        range: vk::DeviceSize,
+        descriptor_type: vk::DescriptorType,
+    ) {
+        let buffer_info = vk::DescriptorBufferInfo {
+            buffer: buffer.handle,
+            offset,
+            range,
+        };
+        let write = vk::WriteDescriptorSet {
+            dst_set: self.handle,
+            dst_binding: binding,
+            dst_array_element: 0,
+            descriptor_count: 1,
+            descriptor_type,
+            p_buffer_info: &buffer_info,
+            ..Default::default()
+        };
+
+        unsafe { self.device.update_descriptor_sets(&[write], &[]) };
+    }
+    // the descriptor covers one element of uniform_bv. which element is picked by the dynamic
+    // offset given when the set is bound
+    pub fn write_dynamic_buffer(&self, binding: u32, uniform_bv: &DynamicUniformBV) {
+        self.write_buffer(
+            binding,
+            &uniform_bv.buffer,
+            uniform_bv.offset,
+            uniform_bv.size,
+            vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC,
+        );
+    }
+    // writes a COMBINED_IMAGE_SAMPLER, or a SAMPLED_IMAGE when sampler is null
+    pub fn write_image(
+        &self,
+        binding: u32,
+        image_view: vk::ImageView,
+        sampler: vk::Sampler,
+        image_layout: vk::ImageLayout,
+    ) {
+        let descriptor_type = if sampler == vk::Sampler::null() {
+            vk::DescriptorType::SAMPLED_IMAGE
+        } else {
+            vk::DescriptorType::COMBINED_IMAGE_SAMPLER
+        };
+        let image_info = vk::DescriptorImageInfo {
+            sampler,
+            image_view,
+            image_layout,
+        };
+        let write = vk::WriteDescriptorSet {
+            dst_set: self.handle,
+            dst_binding: binding,
+            dst_array_element: 0,
+            descriptor_count: 1,
+            descriptor_type,
+            p_image_info: &image_info,
+            ..Default::default()
+        };
+
+        unsafe { self.device.update_descriptor_sets(&[write], &[]) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pool_sizes() {
+        let binding = |binding, descriptor_type, descriptor_count| DescriptorSetLayoutBindingInfo {
+            binding,
+            descriptor_type,
+            descriptor_count,
+            stage_flags: vk::ShaderStageFlags::ALL,
+            p_immutable_shader: std::ptr::null(),
+            size: None,
+        };
+        let per_frame = [binding(0, vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC, 1)];
+        let other = [
+            binding(0, vk::DescriptorType::UNIFORM_BUFFER, 1),
+            binding(1, vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 16),
+            binding(2, vk::DescriptorType::UNIFORM_BUFFER, 2),
+            binding(3, vk::DescriptorType::STORAGE_BUFFER, 0),
+        ];
+
+        // vk::DescriptorPoolSize doesn't implement PartialEq
+        let sizes: Vec<_> = tally_pool_sizes(per_frame.iter().chain(other.iter()), 2)
+            .into_iter()
+            .map(|s| (s.ty, s.descriptor_count))
+            .collect();
+
+        assert_eq!(
+            sizes,
+            [
+                (vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC, 2),
+                (vk::DescriptorType::UNIFORM_BUFFER, 6),
+                (vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 32),
+            ]
+        );
+
+        assert_eq!(dynamic_offset_count(&per_frame), 1);
+        assert_eq!(dynamic_offset_count(&other), 0);
+        let arrayed = [
+            binding(0, vk::DescriptorType::STORAGE_BUFFER_DYNAMIC, 3),
+            binding(1, vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC, 1),
+        ];
+        assert_eq!(dynamic_offset_count(&arrayed), 4);
+    }
+}
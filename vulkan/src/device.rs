@@ -12,10 +12,18 @@ pub struct Device {
     debug_messenger: Option<vk::DebugUtilsMessengerEXT>,
     device: ash::Device,
     swapchain_loader: ash::khr::swapchain::Device,
+    // only loaded when the instance has debug utils enabled
+    debug_utils: Option<ash::ext::debug_utils::Device>,
     pub queue: vk::Queue, // TODO: rework queues
     queue_family_index: u32,
+    depth_stencil_format: vk::Format,
+    // used by submit_immediate. The lock keeps two uploads from recording into it at once.
+    immediate_command_pool: std::sync::Mutex<vk::CommandPool>,
 }
 
+// every resource holds one of these to keep the device alive. it's an Arc so resources can
+// be created and dropped on other threads (Device is Send + Sync), but recording into a
+// command buffer still needs external synchronization as usual
 pub type SharedDeviceRef = std::sync::Arc<Device>;
 
 macro_rules! vk_delegate_create {
@@ -65,109 +73,158 @@ macro_rules! vk_delegate_forward {
 
 pub type SharedRef<T> = std::sync::Arc<T>;
 
+// names can't contain nul, so anything after one is dropped
+fn object_name(name: &str) -> std::ffi::CString {
+    let name = name.split('\0').next().unwrap_or_default();
+    std::ffi::CString::new(name).unwrap_or_default()
+}
+
+// in order of preference
+const DEPTH_STENCIL_FORMATS: [vk::Format; 3] = [
+    vk::Format::D32_SFLOAT_S8_UINT,
+    vk::Format::D24_UNORM_S8_UINT,
+    vk::Format::D16_UNORM_S8_UINT,
+];
+
+// the first format that can be an optimally tiled depth/stencil attachment
+fn select_depth_stencil_format(
+    formats: &[(vk::Format, vk::FormatProperties)],
+) -> Option<vk::Format> {
+    formats
+        .iter()
+        .find(|(_, properties)| {
+            properties
+                .optimal_tiling_features
+                .contains(vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT)
+        })
+        .map(|(format, _)| *format)
+}
+
+fn find_viable_depth_stencil_format(
+    instance: &SharedInstanceRef,
+    physical_device: vk::PhysicalDevice,
+) -> Option<vk::Format> {
+    let formats = DEPTH_STENCIL_FORMATS.map(|f| {
+        let properties = unsafe {
+            instance
+                .raw()
+                .get_physical_device_format_properties(physical_device, f)
+        };
+        (f, properties)
+    });
+
+    select_depth_stencil_format(&formats)
+}
+
+#[derive(Debug, Clone)]
+pub struct PhysicalDeviceInfo {
+    pub handle: vk::PhysicalDevice,
+    pub name: String,
+    pub device_type: vk::PhysicalDeviceType,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    // the first queue family that supports graphics
+    pub graphics_queue_family_index: u32,
+}
+
+// the device with the best device_type, discrete over integrated over virtual over cpu
+pub fn select_default_physical_device(devices: &[PhysicalDeviceInfo]) -> usize {
+    devices
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, info)| match info.device_type {
+            vk::PhysicalDeviceType::CPU => 1,
+            vk::PhysicalDeviceType::VIRTUAL_GPU => 2,
+            vk::PhysicalDeviceType::INTEGRATED_GPU => 3,
+            vk::PhysicalDeviceType::DISCRETE_GPU => 4,
+            _ => 0,
+        })
+        .map_or(0, |(i, _)| i)
+}
+
+// the highest count in supported that isn't above requested. an empty request means 1 sample
+pub fn clamp_sample_count(
+    requested: vk::SampleCountFlags,
+    supported: vk::SampleCountFlags,
+) -> vk::SampleCountFlags {
+    // requested should be a single count, but use the highest one if it isn't
+    let mut count = match requested.as_raw() {
+        0 => 1,
+        raw => 1 << (31 - raw.leading_zeros()),
+    };
+    while count > 1 && !supported.contains(vk::SampleCountFlags::from_raw(count)) {
+        count >>= 1;
+    }
+
+    vk::SampleCountFlags::from_raw(count)
+}
+
 #[allow(dead_code)]
 impl Device {
+    // picks the physical device with select_default_physical_device
+    #[inline]
     pub fn new(
         instance: SharedInstanceRef,
         pfn_debug_utils_callback: vk::PFN_vkDebugUtilsMessengerCallbackEXT,
-    ) -> Result<SharedRef<Device>> {
+    ) -> Result<SharedDeviceRef> {
+        Self::new_with_selector(
+            instance,
+            pfn_debug_utils_callback,
+            select_default_physical_device,
+        )
+    }
+
+    // selector is given the viable physical devices and returns the index of the one to use
+    pub fn new_with_selector(
+        instance: SharedInstanceRef,
+        pfn_debug_utils_callback: vk::PFN_vkDebugUtilsMessengerCallbackEXT,
+        selector: impl Fn(&[PhysicalDeviceInfo]) -> usize,
+    ) -> Result<SharedDeviceRef> {
         let debug_messenger = instance.create_debug_utils_messenger(pfn_debug_utils_callback)?;
 
         let queue_priority: f32 = 1.0;
 
-        let (queue_create_info, physical_device) = {
-            let all_physical_devices = unsafe { instance.raw().enumerate_physical_devices() }?;
-
-            let viable_physical_devices: Box<[(usize, vk::PhysicalDevice)]> = all_physical_devices
-                .into_iter()
-                .enumerate()
-                .filter(|(_, pd)| {
-                    let mut properties = vk::PhysicalDeviceProperties2::default();
-                    unsafe {
-                        instance
-                            .raw()
-                            .get_physical_device_properties2(*pd, &mut properties);
-                    }
-
-                    if properties.properties.api_version < vk::API_VERSION_1_3 {
-                        return false;
-                    }
-
-                    let queue_family_properties = unsafe {
-                        let count = instance
-                            .raw()
-                            .get_physical_device_queue_family_properties2_len(*pd);
-                        let mut properties =
-                            vec![vk::QueueFamilyProperties2::default(); count].into_boxed_slice();
-                        instance
-                            .raw()
-                            .get_physical_device_queue_family_properties2(*pd, properties.as_mut());
-                        properties
-                    };
-
-                    if queue_family_properties
-                        .iter()
-                        .find(|qfp| {
-                            qfp.queue_family_properties
-                                .queue_flags
-                                .contains(vk::QueueFlags::GRAPHICS)
-                        })
-                        .is_none()
-                    {
-                        return false;
-                    }
-
-                    true
-                })
-                .collect();
+        let physical_device_info = Self::enumerate_physical_devices(&instance)
+            .and_then(|devices| {
+                let count = devices.len();
+                if count == 0 {
+                    return Err(Error::NoViablePhysicalDevices);
+                }
 
-            if viable_physical_devices.len() == 0 {
+                let index = selector(&devices);
+                devices
+                    .into_iter()
+                    .nth(index)
+                    .ok_or(Error::PhysicalDeviceIndexOutOfRange { index, count })
+            })
+            .inspect_err(|_| {
                 if let Some(messenger) = debug_messenger {
                     unsafe {
                         instance.destroy_debug_utils_messenger(messenger);
                     }
                 }
-                return Err(Error::NoViablePhysicalDevices);
-            }
-
-            match viable_physical_devices.into_iter().max_by_key(|(_, pd)| {
-                let mut properties = vk::PhysicalDeviceProperties2::default();
-                unsafe {
-                    instance
-                        .raw()
-                        .get_physical_device_properties2(*pd, &mut properties);
-                }
+            })?;
+        let physical_device = physical_device_info.handle;
+
+        let queue_create_info = vk::DeviceQueueCreateInfo {
+            queue_family_index: physical_device_info.graphics_queue_family_index,
+            queue_count: 1,
+            p_queue_priorities: &queue_priority,
+            ..Default::default()
+        };
 
-                match properties.properties.device_type {
-                    vk::PhysicalDeviceType::CPU => 1,
-                    vk::PhysicalDeviceType::VIRTUAL_GPU => 2,
-                    vk::PhysicalDeviceType::INTEGRATED_GPU => 3,
-                    vk::PhysicalDeviceType::DISCRETE_GPU => 4,
-                    _ => 0,
-                }
-            }) {
-                Some((qfi, pd)) => (
-                    vk::DeviceQueueCreateInfo {
-                        queue_family_index: qfi.clone() as u32,
-                        queue_count: 1,
-                        p_queue_priorities: &queue_priority,
-                        ..Default::default()
-                    },
-                    pd,
-                ),
+        let depth_stencil_format =
+            match find_viable_depth_stencil_format(&instance, physical_device) {
+                Some(format) => format,
                 None => {
-                    unsafe {
-                        if let Some(messenger) = debug_messenger {
+                    if let Some(messenger) = debug_messenger {
+                        unsafe {
                             instance.destroy_debug_utils_messenger(messenger);
                         }
-                        instance
-                            .raw()
-                            .destroy_instance(instance.allocation_callbacks_ref());
                     }
-                    return Err(Error::NoViablePhysicalDevices);
+                    return Err(Error::CouldNotDetermineFormat);
                 }
-            }
-        };
+            };
 
         let device = {
             let enabled_device_extension_names = vec![ash::khr::swapchain::NAME.as_ptr()];
@@ -219,6 +276,9 @@ impl Device {
         };
 
         let swapchain_loader = ash::khr::swapchain::Device::new(instance.raw(), &device);
+        let debug_utils = instance
+            .debug_utils_enabled()
+            .then(|| ash::ext::debug_utils::Device::new(instance.raw(), &device));
 
         let queue = {
             let get_queue_info = vk::DeviceQueueInfo2 {
@@ -229,18 +289,155 @@ impl Device {
             unsafe { device.get_device_queue2(&get_queue_info) }
         };
 
+        let immediate_command_pool = {
+            let create_info = vk::CommandPoolCreateInfo {
+                flags: vk::CommandPoolCreateFlags::TRANSIENT,
+                queue_family_index: queue_create_info.queue_family_index,
+                ..Default::default()
+            };
+
+            unsafe { device.create_command_pool(&create_info, instance.allocation_callbacks_ref()) }
+                .inspect_err(|_| unsafe {
+                    device.destroy_device(instance.allocation_callbacks_ref());
+                    if let Some(messenger) = debug_messenger {
+                        instance.destroy_debug_utils_messenger(messenger);
+                    }
+                })?
+        };
+
         Ok(Device {
             instance,
             debug_messenger,
             physical_device,
             device,
             swapchain_loader,
+            debug_utils,
             queue,
             queue_family_index: queue_create_info.queue_family_index,
+            depth_stencil_format,
+            immediate_command_pool: std::sync::Mutex::new(immediate_command_pool),
         }
         .into())
     }
 
+    // records a command buffer with record_commands, submits it and waits for it to finish.
+    // Meant for uploads and other one off work outside of the frame loop.
+    pub fn submit_immediate<F>(&self, record_commands: F) -> Result<()>
+    where
+        F: FnOnce(vk::CommandBuffer),
+    {
+        let pool = self
+            .immediate_command_pool
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        let allocate_info = vk::CommandBufferAllocateInfo {
+            command_pool: *pool,
+            level: vk::CommandBufferLevel::PRIMARY,
+            command_buffer_count: 1,
+            ..Default::default()
+        };
+        let command_buffer = unsafe { self.device.allocate_command_buffers(&allocate_info) }?[0];
+
+        let result = (|| {
+            let begin_info = vk::CommandBufferBeginInfo {
+                flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
+                ..Default::default()
+            };
+            unsafe {
+                self.device
+                    .begin_command_buffer(command_buffer, &begin_info)
+            }?;
+
+            record_commands(command_buffer);
+
+            unsafe { self.device.end_command_buffer(command_buffer) }?;
+
+            let fence = unsafe {
+                self.device
+                    .create_fence(&vk::FenceCreateInfo::default(), self.get_alloc_callbacks())
+            }?;
+
+            let submit_info = [vk::SubmitInfo {
+                command_buffer_count: 1,
+                p_command_buffers: &command_buffer,
+                ..Default::default()
+            }];
+            let result = unsafe {
+                self.device
+                    .queue_submit(self.queue, &submit_info, fence)
+                    .and_then(|_| self.device.wait_for_fences(&[fence], true, u64::MAX))
+            };
+
+            unsafe { self.device.destroy_fence(fence, self.get_alloc_callbacks()) };
+
+            result
+        })();
+
+        unsafe { self.device.free_command_buffers(*pool, &[command_buffer]) };
+
+        Ok(result?)
+    }
+
+    // the physical devices that support vulkan 1.3 and have a graphics queue
+    pub fn enumerate_physical_devices(
+        instance: &SharedInstanceRef,
+    ) -> Result<Vec<PhysicalDeviceInfo>> {
+        let all_physical_devices = unsafe { instance.raw().enumerate_physical_devices() }?;
+
+        let infos = all_physical_devices
+            .into_iter()
+            .filter_map(|pd| {
+                let mut properties = vk::PhysicalDeviceProperties2::default();
+                unsafe {
+                    instance
+                        .raw()
+                        .get_physical_device_properties2(pd, &mut properties);
+                }
+                let properties = properties.properties;
+
+                if properties.api_version < vk::API_VERSION_1_3 {
+                    return None;
+                }
+
+                let queue_family_properties = unsafe {
+                    let count = instance
+                        .raw()
+                        .get_physical_device_queue_family_properties2_len(pd);
+                    let mut properties =
+                        vec![vk::QueueFamilyProperties2::default(); count].into_boxed_slice();
+                    instance
+                        .raw()
+                        .get_physical_device_queue_family_properties2(pd, properties.as_mut());
+                    properties
+                };
+
+                let graphics_queue_family_index =
+                    queue_family_properties.iter().position(|qfp| {
+                        qfp.queue_family_properties
+                            .queue_flags
+                            .contains(vk::QueueFlags::GRAPHICS)
+                    })? as u32;
+
+                let name = properties
+                    .device_name_as_c_str()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+
+                Some(PhysicalDeviceInfo {
+                    handle: pd,
+                    name,
+                    device_type: properties.device_type,
+                    vendor_id: properties.vendor_id,
+                    device_id: properties.device_id,
+                    graphics_queue_family_index,
+                })
+            })
+            .collect();
+
+        Ok(infos)
+    }
+
     #[inline]
     unsafe fn get_alloc_callbacks(&self) -> Option<&vk::AllocationCallbacks<'_>> {
         self.instance.allocation_callbacks_ref()
@@ -328,6 +525,31 @@ impl Device {
         }
     }
 
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn cmd_blit_image(
+        &self,
+        buffer: vk::CommandBuffer,
+        src_image: vk::Image,
+        src_image_layout: vk::ImageLayout,
+        dst_image: vk::Image,
+        dst_image_layout: vk::ImageLayout,
+        regions: &[vk::ImageBlit],
+        filter: vk::Filter,
+    ) {
+        unsafe {
+            self.device.cmd_blit_image(
+                buffer,
+                src_image,
+                src_image_layout,
+                dst_image,
+                dst_image_layout,
+                regions,
+                filter,
+            )
+        }
+    }
+
     #[inline]
     pub unsafe fn create_swapchain(
         &self,
@@ -355,34 +577,53 @@ impl Device {
         unsafe { self.swapchain_loader.get_swapchain_images(swapchain) }
     }
 
+    // shows up instead of the raw handle in validation messages. does nothing unless the
+    // instance was created with debug enabled
+    pub fn set_object_name<T: vk::Handle>(&self, handle: T, name: &str) {
+        let Some(debug_utils) = self.debug_utils.as_ref() else {
+            return;
+        };
+
+        let name = object_name(name);
+        let name_info = vk::DebugUtilsObjectNameInfoEXT {
+            object_type: T::TYPE,
+            object_handle: handle.as_raw(),
+            p_object_name: name.as_ptr(),
+            ..Default::default()
+        };
+
+        // a missing name only makes validation messages harder to read
+        let _ = unsafe { debug_utils.set_debug_utils_object_name(&name_info) };
+    }
+
     #[inline]
     pub fn get_queue_family_index(&self) -> u32 {
         self.queue_family_index
     }
 
     #[inline]
-    pub fn find_viable_depth_stencil_format(&self) -> Option<vk::Format> {
-        let formats = [
-            ash::vk::Format::D32_SFLOAT_S8_UINT,
-            ash::vk::Format::D24_UNORM_S8_UINT,
-            ash::vk::Format::D16_UNORM_S8_UINT,
-        ];
+    pub fn depth_stencil_format(&self) -> vk::Format {
+        self.depth_stencil_format
+    }
 
-        formats
-            .into_iter()
-            .filter_map(|f| {
-                let properties = unsafe { self.get_physical_device_format_properties(f) };
-
-                if properties
-                    .optimal_tiling_features
-                    .contains(ash::vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT)
-                {
-                    Some(f)
-                } else {
-                    None
-                }
-            })
-            .next()
+    // bits of resolution timestamps written to the queue have. 0 if it doesn't support them
+    pub fn timestamp_valid_bits(&self) -> u32 {
+        let queue_family_properties = unsafe {
+            self.instance
+                .raw()
+                .get_physical_device_queue_family_properties(self.physical_device)
+        };
+
+        queue_family_properties
+            .get(self.queue_family_index as usize)
+            .map_or(0, |p| p.timestamp_valid_bits)
+    }
+
+    // the sample counts both color and depth attachments support
+    pub fn supported_sample_counts(&self) -> vk::SampleCountFlags {
+        let limits = unsafe { self.get_physical_device_properties() }.limits;
+
+        limits.framebuffer_color_sample_counts & limits.framebuffer_depth_sample_counts
     }
 
     #[inline]
@@ -421,12 +662,32 @@ impl Device {
     pub(crate) unsafe fn acquire_next_image(
         &self,
         swapchain: vk::SwapchainKHR,
+        timeout: u64,
         semaphore: vk::Semaphore,
         fence: vk::Fence,
     ) -> VkResult<(u32, bool)> {
         unsafe {
             self.swapchain_loader
-                .acquire_next_image(swapchain, u64::MAX, semaphore, fence)
+                .acquire_next_image(swapchain, timeout, semaphore, fence)
+        }
+    }
+
+    #[inline]
+    pub unsafe fn wait_for_fences(&self, fences: &[vk::Fence], wait_all: bool) -> VkResult<()> {
+        unsafe { self.wait_for_fences_timeout(fences, wait_all, u64::MAX) }.map(|_| ())
+    }
+
+    // returns false if the fences weren't signaled within timeout_ns
+    pub unsafe fn wait_for_fences_timeout(
+        &self,
+        fences: &[vk::Fence],
+        wait_all: bool,
+        timeout_ns: u64,
+    ) -> VkResult<bool> {
+        match unsafe { self.device.wait_for_fences(fences, wait_all, timeout_ns) } {
+            Ok(()) => Ok(true),
+            Err(vk::Result::TIMEOUT) => Ok(false),
+            Err(e) => Err(e),
         }
     }
 
@@ -503,6 +764,12 @@ impl Device {
     );
     vk_delegate_destroy!(destroy_descriptor_set_layout, DescriptorSetLayout);
     vk_delegate_destroy!(destroy_pipeline, Pipeline);
+    vk_delegate_create!(
+        create_pipeline_cache,
+        PipelineCacheCreateInfo,
+        PipelineCache
+    );
+    vk_delegate_destroy!(destroy_pipeline_cache, PipelineCache);
     vk_delegate_create!(create_command_pool, CommandPoolCreateInfo, CommandPool);
     vk_delegate_destroy!(destroy_command_pool, CommandPool);
     vk_delegate_create!(create_fence, FenceCreateInfo, Fence);
@@ -517,6 +784,8 @@ impl Device {
     vk_delegate_destroy!(destroy_semaphore, Semaphore);
     vk_delegate_create!(create_sampler, SamplerCreateInfo, Sampler);
     vk_delegate_destroy!(destroy_sampler, Sampler);
+    vk_delegate_create!(create_query_pool, QueryPoolCreateInfo, QueryPool);
+    vk_delegate_destroy!(destroy_query_pool, QueryPool);
     vk_delegate_create_many!(
         allocate_command_buffers,
         CommandBufferAllocateInfo,
@@ -524,10 +793,15 @@ impl Device {
     );
     vk_delegate_destroy_many!(free_command_buffers, CommandPool, CommandBuffer);
 
+    vk_delegate_forward!(get_pipeline_cache_data, (cache: PipelineCache), VkResult<Vec<u8>>);
     vk_delegate_forward!(update_descriptor_sets, (writes: &[WriteDescriptorSet], copies: &[CopyDescriptorSet]), ());
+    vk_delegate_forward!(cmd_copy_buffer, (buffer: CommandBuffer, src: Buffer, dst: Buffer, regions: &[BufferCopy]), ());
     vk_delegate_forward!(cmd_copy_buffer2, (buffer: CommandBuffer, info: &CopyBufferInfo2), ());
+    vk_delegate_forward!(cmd_copy_buffer_to_image, (buffer: CommandBuffer, src: Buffer, dst: Image, dst_layout: ImageLayout, regions: &[BufferImageCopy]), ());
     vk_delegate_forward!(cmd_copy_buffer_to_image2, (buffer: CommandBuffer, info: &CopyBufferToImageInfo2), ());
+    vk_delegate_forward!(cmd_copy_image_to_buffer2, (buffer: CommandBuffer, info: &CopyImageToBufferInfo2), ());
     vk_delegate_forward!(reset_fences, (fences: &[Fence]), VkResult<()>);
+    vk_delegate_forward!(reset_command_pool, (pool: CommandPool, flags: CommandPoolResetFlags), VkResult<()>);
     vk_delegate_forward!(reset_command_buffer, (buffer: CommandBuffer, flags: CommandBufferResetFlags), VkResult<()>);
     vk_delegate_forward!(cmd_pipeline_barrier2, (cb: CommandBuffer, info: &DependencyInfo), ());
     vk_delegate_forward!(device_wait_idle, (), VkResult<()>);
@@ -542,7 +816,7 @@ impl Device {
     vk_delegate_forward!(end_command_buffer, (buffer: CommandBuffer), VkResult<()>);
     vk_delegate_forward!(cmd_begin_rendering, (buffer: CommandBuffer, info: &RenderingInfo), ());
     vk_delegate_forward!(cmd_end_rendering, (buffer: CommandBuffer), ());
-    vk_delegate_forward!(wait_for_fences, (fences: &[Fence], wait_all: bool, timeout: u64), VkResult<()>);
+    vk_delegate_forward!(cmd_execute_commands, (buffer: CommandBuffer, secondaries: &[CommandBuffer]), ());
     vk_delegate_forward!(queue_submit, (queue: Queue, submits: &[SubmitInfo], fence: Fence), VkResult<()>);
     vk_delegate_forward!(bind_image_memory, (image: Image, memory: DeviceMemory, offset: DeviceSize), VkResult<()>);
     vk_delegate_forward!(bind_buffer_memory, (buffer: Buffer, memory: DeviceMemory, offset: DeviceSize), VkResult<()>);
@@ -550,12 +824,24 @@ impl Device {
     vk_delegate_forward!(get_image_memory_requirements, (image: Image), MemoryRequirements);
     vk_delegate_forward!(map_memory, (memory: DeviceMemory, offset: DeviceSize, size: DeviceSize, flags: MemoryMapFlags), VkResult<*mut std::ffi::c_void>);
     vk_delegate_forward!(unmap_memory, (memory: DeviceMemory), ());
+    vk_delegate_forward!(cmd_reset_query_pool, (buffer: CommandBuffer, pool: QueryPool, first_query: u32, query_count: u32), ());
+    vk_delegate_forward!(cmd_begin_query, (buffer: CommandBuffer, pool: QueryPool, query: u32, flags: QueryControlFlags), ());
+    vk_delegate_forward!(cmd_end_query, (buffer: CommandBuffer, pool: QueryPool, query: u32), ());
+    vk_delegate_forward!(cmd_write_timestamp2, (buffer: CommandBuffer, stage: PipelineStageFlags2, pool: QueryPool, query: u32), ());
+    vk_delegate_forward!(get_query_pool_results, (pool: QueryPool, first_query: u32, data: &mut [[u64; 2]], flags: QueryResultFlags), VkResult<()>);
+    vk_delegate_forward!(cmd_push_constants, (buffer: CommandBuffer, layout: PipelineLayout, stage_flags: ShaderStageFlags, offset: u32, constants: &[u8]), ());
     vk_delegate_forward!(cmd_bind_descriptor_sets,(buffer: CommandBuffer, bind_point: PipelineBindPoint, layout: PipelineLayout, first_set: u32, sets: &[DescriptorSet], dynamic_offsets: &[u32]), ());
 }
 
 impl Drop for Device {
     fn drop(&mut self) {
         unsafe {
+            let pool = *self
+                .immediate_command_pool
+                .get_mut()
+                .unwrap_or_else(|e| e.into_inner());
+            self.device
+                .destroy_command_pool(pool, self.get_alloc_callbacks());
             self.device.destroy_device(self.get_alloc_callbacks());
             if let Some(messenger) = self.debug_messenger {
                 self.instance.destroy_debug_utils_messenger(messenger);
@@ -563,3 +849,141 @@ impl Drop for Device {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // never run, only has to compile: buffers and images take the same device handle
+    #[allow(dead_code)]
+    fn share_device(device: SharedDeviceRef) -> Result<(crate::Buffer, crate::Image)> {
+        let buffer = crate::Buffer::new(
+            device.clone(),
+            &crate::BufferCreateInfo {
+                size: 64,
+                usage: vk::BufferUsageFlags::UNIFORM_BUFFER,
+                memory_property_flags: vk::MemoryPropertyFlags::HOST_VISIBLE,
+            },
+        )?;
+        let image = crate::Image::new(
+            device,
+            &crate::ImageCreateInfo {
+                memory_property_flags: vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                mip_levels: 1,
+                image_type: vk::ImageType::TYPE_2D,
+                format: vk::Format::R8G8B8A8_UNORM,
+                width: 1,
+                height: 1,
+                depth: 1,
+                usage: vk::ImageUsageFlags::SAMPLED,
+                array_layers: 1,
+                samples: vk::SampleCountFlags::TYPE_1,
+            },
+        )?;
+
+        Ok((buffer, image))
+    }
+
+    #[test]
+    fn shared_device_ref() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SharedDeviceRef>();
+        assert_send_sync::<crate::Buffer>();
+        assert_send_sync::<crate::Image>();
+        assert_send_sync::<crate::Sampler>();
+        assert_send_sync::<crate::PipelineCache>();
+    }
+
+    #[test]
+    fn object_names() {
+        assert_eq!(object_name("depth image 0").as_bytes(), b"depth image 0");
+        assert_eq!(object_name("vertex\0buffer").as_bytes(), b"vertex");
+        assert_eq!(object_name("").as_bytes(), b"");
+    }
+
+    #[test]
+    fn sample_count() {
+        let supported = vk::SampleCountFlags::TYPE_1
+            | vk::SampleCountFlags::TYPE_2
+            | vk::SampleCountFlags::TYPE_4
+            | vk::SampleCountFlags::TYPE_8;
+
+        let clamp = |requested| clamp_sample_count(requested, supported);
+        assert_eq!(
+            clamp(vk::SampleCountFlags::TYPE_4),
+            vk::SampleCountFlags::TYPE_4
+        );
+        assert_eq!(
+            clamp(vk::SampleCountFlags::TYPE_64),
+            vk::SampleCountFlags::TYPE_8
+        );
+        assert_eq!(
+            clamp(vk::SampleCountFlags::empty()),
+            vk::SampleCountFlags::TYPE_1
+        );
+
+        // a gap in the supported counts falls back to the next lower one
+        let sparse = vk::SampleCountFlags::TYPE_1 | vk::SampleCountFlags::TYPE_4;
+        assert_eq!(
+            clamp_sample_count(vk::SampleCountFlags::TYPE_16, sparse),
+            vk::SampleCountFlags::TYPE_4
+        );
+        assert_eq!(
+            clamp_sample_count(vk::SampleCountFlags::TYPE_2, sparse),
+            vk::SampleCountFlags::TYPE_1
+        );
+    }
+
+    #[test]
+    fn depth_stencil_format() {
+        let attachment = vk::FormatProperties {
+            optimal_tiling_features: vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT
+                | vk::FormatFeatureFlags::SAMPLED_IMAGE,
+            ..Default::default()
+        };
+        // only usable as an attachment with linear tiling
+        let linear_only = vk::FormatProperties {
+            linear_tiling_features: vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT,
+            ..Default::default()
+        };
+        let formats = |supported: [vk::FormatProperties; 3]| {
+            std::array::from_fn::<_, 3, _>(|i| (DEPTH_STENCIL_FORMATS[i], supported[i]))
+        };
+
+        assert_eq!(
+            select_depth_stencil_format(&formats([attachment; 3])),
+            Some(vk::Format::D32_SFLOAT_S8_UINT)
+        );
+        assert_eq!(
+            select_depth_stencil_format(&formats([linear_only, Default::default(), attachment])),
+            Some(vk::Format::D16_UNORM_S8_UINT)
+        );
+        assert_eq!(
+            select_depth_stencil_format(&formats([linear_only; 3])),
+            None
+        );
+        assert_eq!(select_depth_stencil_format(&[]), None);
+    }
+
+    #[test]
+    fn default_physical_device() {
+        let info = |name: &str, device_type| PhysicalDeviceInfo {
+            handle: vk::PhysicalDevice::null(),
+            name: name.to_string(),
+            device_type,
+            vendor_id: 0,
+            device_id: 0,
+            graphics_queue_family_index: 0,
+        };
+
+        let devices = [
+            info("llvmpipe", vk::PhysicalDeviceType::CPU),
+            info("integrated", vk::PhysicalDeviceType::INTEGRATED_GPU),
+            info("discrete", vk::PhysicalDeviceType::DISCRETE_GPU),
+            info("virtual", vk::PhysicalDeviceType::VIRTUAL_GPU),
+        ];
+        assert_eq!(select_default_physical_device(&devices), 2);
+        assert_eq!(select_default_physical_device(&devices[..2]), 1);
+        assert_eq!(select_default_physical_device(&devices[..1]), 0);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::allocator::find_memory_index;
+use crate::allocator::{Allocation, SharedAllocatorRef, find_memory_index};
 use crate::device::SharedDeviceRef;
 use crate::result::{Error, Result};
 
@@ -12,8 +12,14 @@ pub struct Image {
     pub width: u32,
     pub height: u32,
     pub depth: u32,
+    pub format: ash::vk::Format,
+    pub mip_levels: u32,
+    // set if the memory came from an allocator instead of its own vkAllocateMemory
+    allocation: Option<(SharedAllocatorRef, Allocation)>,
 }
 
+// images that use Image::generate_mipmaps need TRANSFER_SRC | TRANSFER_DST usage, and
+// SAMPLED to be read afterwards. mip_levels can be set with mip_level_count
 #[allow(dead_code)]
 pub struct ImageCreateInfo {
     pub memory_property_flags: ash::vk::MemoryPropertyFlags,
@@ -25,6 +31,7 @@ pub struct ImageCreateInfo {
     pub depth: u32,
     pub usage: ash::vk::ImageUsageFlags,
     pub array_layers: u32,
+    pub samples: ash::vk::SampleCountFlags,
 }
 
 fn is_depth_format(format: ash::vk::Format) -> bool {
@@ -32,7 +39,7 @@ fn is_depth_format(format: ash::vk::Format) -> bool {
         format,
         ash::vk::Format::D16_UNORM
             | ash::vk::Format::X8_D24_UNORM_PACK32
-            | ash::vk::Format::S8_UINT
+            | ash::vk::Format::D32_SFLOAT
             | ash::vk::Format::D16_UNORM_S8_UINT
             | ash::vk::Format::D24_UNORM_S8_UINT
             | ash::vk::Format::D32_SFLOAT_S8_UINT
@@ -48,9 +55,104 @@ fn is_stencil_format(format: ash::vk::Format) -> bool {
     )
 }
 
+fn aspect_mask(format: ash::vk::Format) -> ash::vk::ImageAspectFlags {
+    let mut mask = ash::vk::ImageAspectFlags::empty();
+    if is_depth_format(format) {
+        mask |= ash::vk::ImageAspectFlags::DEPTH;
+    }
+    if is_stencil_format(format) {
+        mask |= ash::vk::ImageAspectFlags::STENCIL;
+    }
+    if mask == ash::vk::ImageAspectFlags::empty() {
+        mask = ash::vk::ImageAspectFlags::COLOR;
+    }
+    mask
+}
+
+// the accesses made to an image while it is in layout
+fn layout_access_mask(layout: vk::ImageLayout) -> vk::AccessFlags2 {
+    match layout {
+        vk::ImageLayout::UNDEFINED
+        | vk::ImageLayout::PREINITIALIZED
+        | vk::ImageLayout::PRESENT_SRC_KHR => vk::AccessFlags2::NONE,
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL => vk::AccessFlags2::TRANSFER_READ,
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL => vk::AccessFlags2::TRANSFER_WRITE,
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL => vk::AccessFlags2::SHADER_READ,
+        vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL => {
+            vk::AccessFlags2::COLOR_ATTACHMENT_READ | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE
+        }
+        vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL => {
+            vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ
+                | vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE
+        }
+        vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL => {
+            vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ | vk::AccessFlags2::SHADER_READ
+        }
+        _ => vk::AccessFlags2::MEMORY_READ | vk::AccessFlags2::MEMORY_WRITE,
+    }
+}
+
+// a barrier moving every level and layer of image from old_layout to new_layout. for images
+// that aren't a vulkan::Image, like the swapchain's. see Image::transition_layout
+pub fn layout_transition_barrier(
+    image: vk::Image,
+    format: vk::Format,
+    old_layout: vk::ImageLayout,
+    new_layout: vk::ImageLayout,
+    src_stage: vk::PipelineStageFlags2,
+    dst_stage: vk::PipelineStageFlags2,
+) -> vk::ImageMemoryBarrier2<'static> {
+    vk::ImageMemoryBarrier2 {
+        src_stage_mask: src_stage,
+        src_access_mask: layout_access_mask(old_layout),
+        dst_stage_mask: dst_stage,
+        dst_access_mask: layout_access_mask(new_layout),
+        old_layout,
+        new_layout,
+        src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+        dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+        image,
+        subresource_range: vk::ImageSubresourceRange {
+            aspect_mask: aspect_mask(format),
+            base_mip_level: 0,
+            level_count: vk::REMAINING_MIP_LEVELS,
+            base_array_layer: 0,
+            layer_count: vk::REMAINING_ARRAY_LAYERS,
+        },
+        ..Default::default()
+    }
+}
+
+// the number of levels in a full mip chain, down to 1x1x1
+pub const fn mip_level_count(width: u32, height: u32, depth: u32) -> u32 {
+    let largest = if width > height { width } else { height };
+    let largest = if largest > depth { largest } else { depth };
+
+    u32::BITS - (largest | 1).leading_zeros()
+}
+
 #[allow(dead_code)]
 impl Image {
+    #[inline]
     pub fn new(device: SharedDeviceRef, create_info: &ImageCreateInfo) -> Result<Self> {
+        Self::create(device, create_info, None)
+    }
+
+    // suballocates the image's memory from allocator
+    #[inline]
+    pub fn new_in(
+        device: SharedDeviceRef,
+        create_info: &ImageCreateInfo,
+        allocator: &SharedAllocatorRef,
+    ) -> Result<Self> {
+        Self::create(device, create_info, Some(allocator))
+    }
+
+    fn create(
+        device: SharedDeviceRef,
+        create_info: &ImageCreateInfo,
+        allocator: Option<&SharedAllocatorRef>,
+    ) -> Result<Self> {
         let tiling = {
             let format_properties =
                 unsafe { device.get_physical_device_format_properties(create_info.format) };
@@ -115,7 +217,7 @@ impl Image {
             },
             usage: create_info.usage,
             array_layers: create_info.array_layers,
-            samples: ash::vk::SampleCountFlags::TYPE_1,
+            samples: create_info.samples,
             tiling,
             sharing_mode: ash::vk::SharingMode::EXCLUSIVE,
             initial_layout: ash::vk::ImageLayout::UNDEFINED,
@@ -152,19 +254,7 @@ impl Image {
                 a: vk::ComponentSwizzle::IDENTITY,
             },
             subresource_range: ash::vk::ImageSubresourceRange {
-                aspect_mask: {
-                    let mut mask = ash::vk::ImageAspectFlags::empty();
-                    if is_depth_format(create_info.format) {
-                        mask |= ash::vk::ImageAspectFlags::DEPTH;
-                    }
-                    if is_stencil_format(create_info.format) {
-                        mask |= ash::vk::ImageAspectFlags::STENCIL;
-                    }
-                    if mask == ash::vk::ImageAspectFlags::empty() {
-                        mask = ash::vk::ImageAspectFlags::COLOR;
-                    }
-                    mask
-                },
+                aspect_mask: aspect_mask(create_info.format),
                 base_mip_level: 0,
                 level_count: create_info.mip_levels,
                 base_array_layer: 0,
@@ -173,44 +263,63 @@ impl Image {
             ..Default::default()
         };
 
-        let allocate_info = {
-            let memory_properties = unsafe { device.get_physical_device_memory_properties() };
-            let memory_requirements = unsafe { device.get_image_memory_requirements(image) };
-            let memory_property_flags = ash::vk::MemoryPropertyFlags::HOST_VISIBLE
-                | ash::vk::MemoryPropertyFlags::HOST_COHERENT;
-            let memory_type_index = find_memory_index(
-                memory_properties,
-                memory_requirements,
-                memory_property_flags,
-            )
-            .ok_or_else(|| {
-                unsafe {
-                    device.destroy_image(image);
-                }
-                Error::NotImplemented
-            })?;
-            ash::vk::MemoryAllocateInfo {
-                allocation_size: memory_requirements.size,
-                memory_type_index,
-                ..Default::default()
+        let memory_requirements = unsafe { device.get_image_memory_requirements(image) };
+
+        let (memory, offset, allocation) = match allocator {
+            Some(allocator) => {
+                let allocation = allocator
+                    .allocate(memory_requirements, create_info.memory_property_flags)
+                    .inspect_err(|_| unsafe { device.destroy_image(image) })?;
+
+                (
+                    allocation.memory,
+                    allocation.offset,
+                    Some((allocator.clone(), allocation)),
+                )
             }
-        };
-        let memory = unsafe { device.allocate_memory(&allocate_info) }.inspect_err(|_| unsafe {
-            device.destroy_image(image);
-        })?;
-
-        unsafe { device.bind_image_memory(image, memory, 0) }.inspect_err(|_| unsafe {
-            device.free_memory(memory);
-            device.destroy_image(image);
-        })?;
-
-        let image_view =
-            unsafe { device.create_image_view(&image_view_create_info) }.inspect_err(|_| {
-                unsafe {
-                    device.free_memory(memory);
-                    device.destroy_image(image)
+            None => {
+                let memory_properties = unsafe { device.get_physical_device_memory_properties() };
+                let memory_type_index = find_memory_index(
+                    memory_properties,
+                    memory_requirements,
+                    create_info.memory_property_flags,
+                )
+                .ok_or_else(|| {
+                    unsafe {
+                        device.destroy_image(image);
+                    }
+                    Error::CouldNotFindMemoryTypeIndex(create_info.memory_property_flags)
+                })?;
+                let allocate_info = ash::vk::MemoryAllocateInfo {
+                    allocation_size: memory_requirements.size,
+                    memory_type_index,
+                    ..Default::default()
                 };
-            })?;
+                let memory = unsafe { device.allocate_memory(&allocate_info) }
+                    .inspect_err(|_| unsafe { device.destroy_image(image) })?;
+
+                (memory, 0, None)
+            }
+        };
+        let free_memory = |allocation: Option<(SharedAllocatorRef, Allocation)>| match allocation {
+            Some((allocator, allocation)) => allocator.free(allocation),
+            None => unsafe { device.free_memory(memory) },
+        };
+
+        if let Err(e) = unsafe { device.bind_image_memory(image, memory, offset) } {
+            unsafe { device.destroy_image(image) };
+            free_memory(allocation);
+            return Err(e.into());
+        }
+
+        let image_view = match unsafe { device.create_image_view(&image_view_create_info) } {
+            Ok(view) => view,
+            Err(e) => {
+                unsafe { device.destroy_image(image) };
+                free_memory(allocation);
+                return Err(e.into());
+            }
+        };
         Ok(Image {
             device,
             handle: image,
@@ -219,16 +328,207 @@ impl Image {
             width: create_info.width,
             height: create_info.height,
             depth: create_info.depth,
+            format: create_info.format,
+            mip_levels: create_info.mip_levels,
+            allocation,
         })
     }
+    pub fn transition_layout(
+        &self,
+        cmd: vk::CommandBuffer,
+        old_layout: vk::ImageLayout,
+        new_layout: vk::ImageLayout,
+        src_stage: vk::PipelineStageFlags2,
+        dst_stage: vk::PipelineStageFlags2,
+    ) {
+        let barriers = [layout_transition_barrier(
+            self.handle,
+            self.format,
+            old_layout,
+            new_layout,
+            src_stage,
+            dst_stage,
+        )];
+
+        let dependency_info = vk::DependencyInfo {
+            image_memory_barrier_count: barriers.len() as u32,
+            p_image_memory_barriers: barriers.as_ptr(),
+            ..Default::default()
+        };
+
+        unsafe { self.device.cmd_pipeline_barrier2(cmd, &dependency_info) };
+    }
+    // fills levels 1.. by blitting each level into the next. every level must be in
+    // TRANSFER_DST_OPTIMAL with level 0 written, and they are all left in
+    // SHADER_READ_ONLY_OPTIMAL for the fragment shader
+    pub fn generate_mipmaps(&self, cmd: vk::CommandBuffer) {
+        let format_properties = unsafe {
+            self.device
+                .get_physical_device_format_properties(self.format)
+        };
+        let filter = if format_properties
+            .optimal_tiling_features
+            .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR)
+        {
+            vk::Filter::LINEAR
+        } else {
+            vk::Filter::NEAREST
+        };
+
+        let aspect_mask = aspect_mask(self.format);
+        let barrier = |level: u32,
+                       old_layout: vk::ImageLayout,
+                       new_layout: vk::ImageLayout,
+                       src_access_mask: vk::AccessFlags2,
+                       dst_stage_mask: vk::PipelineStageFlags2,
+                       dst_access_mask: vk::AccessFlags2| {
+            let barriers = [vk::ImageMemoryBarrier2 {
+                image: self.handle,
+                old_layout,
+                new_layout,
+                src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                subresource_range: vk::ImageSubresourceRange {
+                    aspect_mask,
+                    base_mip_level: level,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    layer_count: vk::REMAINING_ARRAY_LAYERS,
+                },
+                src_stage_mask: vk::PipelineStageFlags2::TRANSFER,
+                dst_stage_mask,
+                src_access_mask,
+                dst_access_mask,
+                ..Default::default()
+            }];
+
+            let dependency_info = vk::DependencyInfo {
+                image_memory_barrier_count: barriers.len() as u32,
+                p_image_memory_barriers: barriers.as_ptr(),
+                ..Default::default()
+            };
+
+            unsafe { self.device.cmd_pipeline_barrier2(cmd, &dependency_info) };
+        };
+        let extent = |level: u32| vk::Offset3D {
+            x: (self.width >> level).max(1) as i32,
+            y: (self.height >> level).max(1) as i32,
+            z: (self.depth >> level).max(1) as i32,
+        };
+
+        for level in 1..self.mip_levels {
+            barrier(
+                level - 1,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::AccessFlags2::TRANSFER_WRITE,
+                vk::PipelineStageFlags2::TRANSFER,
+                vk::AccessFlags2::TRANSFER_READ,
+            );
+
+            let regions = [vk::ImageBlit {
+                src_subresource: vk::ImageSubresourceLayers {
+                    aspect_mask,
+                    mip_level: level - 1,
+                    base_array_layer: 0,
+                    layer_count: vk::REMAINING_ARRAY_LAYERS,
+                },
+                src_offsets: [vk::Offset3D::default(), extent(level - 1)],
+                dst_subresource: vk::ImageSubresourceLayers {
+                    aspect_mask,
+                    mip_level: level,
+                    base_array_layer: 0,
+                    layer_count: vk::REMAINING_ARRAY_LAYERS,
+                },
+                dst_offsets: [vk::Offset3D::default(), extent(level)],
+            }];
+
+            unsafe {
+                self.device.cmd_blit_image(
+                    cmd,
+                    self.handle,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    self.handle,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &regions,
+                    filter,
+                )
+            };
+
+            barrier(
+                level - 1,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                vk::AccessFlags2::TRANSFER_READ,
+                vk::PipelineStageFlags2::FRAGMENT_SHADER,
+                vk::AccessFlags2::SHADER_READ,
+            );
+        }
+
+        // the last level was only ever written to
+        barrier(
+            self.mip_levels - 1,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            vk::AccessFlags2::TRANSFER_WRITE,
+            vk::PipelineStageFlags2::FRAGMENT_SHADER,
+            vk::AccessFlags2::SHADER_READ,
+        );
+    }
 }
 
 impl Drop for Image {
     fn drop(&mut self) {
         unsafe {
-            self.device.free_memory(self.memory);
             self.device.destroy_image_view(self.view);
             self.device.destroy_image(self.handle);
+            match self.allocation.take() {
+                Some((allocator, allocation)) => allocator.free(allocation),
+                None => self.device.free_memory(self.memory),
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::mip_level_count;
+
+    #[test]
+    fn mip_levels() {
+        assert_eq!(mip_level_count(1, 1, 1), 1);
+        assert_eq!(mip_level_count(2, 1, 1), 2);
+        assert_eq!(mip_level_count(256, 256, 1), 9);
+        assert_eq!(mip_level_count(300, 17, 1), 9);
+        assert_eq!(mip_level_count(4, 4, 1024), 11);
+    }
+
+    #[test]
+    fn transition_aspect_mask() {
+        use ash::vk;
+
+        let barrier = |format| {
+            super::layout_transition_barrier(
+                vk::Image::null(),
+                format,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                vk::PipelineStageFlags2::TOP_OF_PIPE,
+                vk::PipelineStageFlags2::EARLY_FRAGMENT_TESTS,
+            )
+            .subresource_range
+            .aspect_mask
+        };
+
+        assert_eq!(
+            barrier(vk::Format::R8G8B8A8_SRGB),
+            vk::ImageAspectFlags::COLOR
+        );
+        assert_eq!(barrier(vk::Format::D32_SFLOAT), vk::ImageAspectFlags::DEPTH);
+        assert_eq!(barrier(vk::Format::S8_UINT), vk::ImageAspectFlags::STENCIL);
+        assert_eq!(
+            barrier(vk::Format::D24_UNORM_S8_UINT),
+            vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+        );
+    }
+}
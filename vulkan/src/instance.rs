@@ -118,10 +118,20 @@ impl Instance {
     pub const fn raw(&self) -> &ash::Instance {
         &self.instance
     }
+    #[inline]
+    pub fn debug_utils_enabled(&self) -> bool {
+        self.debug_utils.is_some()
+    }
+    // None unless debug is enabled and there is a callback to report to. object names still
+    // work without a messenger
     pub fn create_debug_utils_messenger(
         &self,
         pfn_user_callback: vk::PFN_vkDebugUtilsMessengerCallbackEXT,
     ) -> VkResult<Option<vk::DebugUtilsMessengerEXT>> {
+        if pfn_user_callback.is_none() {
+            return Ok(None);
+        }
+
         if let Some(utils) = self.debug_utils.as_ref() {
             let create_info = vk::DebugUtilsMessengerCreateInfoEXT {
                 s_type: vk::StructureType::DEBUG_UTILS_MESSENGER_CREATE_INFO_EXT,
@@ -1,23 +1,31 @@
 pub mod allocator;
 pub mod buffer;
+pub mod command_buffer;
 pub mod constants;
 pub mod descriptor;
 pub mod device;
 pub mod image;
 mod instance;
 pub mod pipeline;
+pub mod pipeline_cache;
+pub mod query;
 pub mod result;
+pub mod sampler;
 pub mod shader_module;
 pub mod swapchain;
 
-// pub use allocator::*;
+pub use allocator::*;
 pub use buffer::*;
+pub use command_buffer::*;
 pub use constants::*;
 pub use descriptor::*;
-pub use device::Device;
+pub use device::{Device, PhysicalDeviceInfo, clamp_sample_count, select_default_physical_device};
 pub use image::*;
 pub use instance::*;
 pub use pipeline::*;
+pub use pipeline_cache::*;
+pub use query::*;
 pub use result::*;
+pub use sampler::*;
 pub use shader_module::*;
 pub use swapchain::*;
@@ -1,5 +1,9 @@
 use crate::device::SharedDeviceRef;
-use crate::{descriptor::DescriptorSetLayout, result::Result};
+use crate::{
+    descriptor::DescriptorSetLayout,
+    pipeline_cache::PipelineCache,
+    result::{Error, Result},
+};
 use ash::vk::{self, GraphicsPipelineCreateInfo};
 use std::rc::Rc;
 
@@ -8,6 +12,7 @@ pub struct PipelineLayout {
     device: SharedDeviceRef,
     pub bind_point: vk::PipelineBindPoint,
     set_layouts: Box<[crate::DescriptorSetLayout]>,
+    push_constant_ranges: Box<[vk::PushConstantRange]>,
     pub handle: vk::PipelineLayout,
 }
 
@@ -23,11 +28,41 @@ impl std::fmt::Display for PipelineLayout {
     }
 }
 
+// a stage may only appear in one push constant range, so ranges that overlap or share a
+// stage are combined into one range covering both, used by the stages of both
+pub fn merge_push_constant_ranges(ranges: &[vk::PushConstantRange]) -> Vec<vk::PushConstantRange> {
+    let mut merged = Vec::<vk::PushConstantRange>::with_capacity(ranges.len());
+
+    for range in ranges {
+        let mut range = *range;
+        // merging can make range overlap one that was already checked, so start over
+        while let Some(i) = merged.iter().position(|m| {
+            m.stage_flags.intersects(range.stage_flags)
+                || (m.offset < range.offset + range.size && range.offset < m.offset + m.size)
+        }) {
+            let m = merged.swap_remove(i);
+            let start = m.offset.min(range.offset);
+            let end = (m.offset + m.size).max(range.offset + range.size);
+            range = vk::PushConstantRange {
+                stage_flags: m.stage_flags | range.stage_flags,
+                offset: start,
+                size: end - start,
+            };
+        }
+        merged.push(range);
+    }
+
+    merged.sort_by_key(|r| r.offset);
+    merged
+}
+
 impl PipelineLayout {
-    // bindings should be sorted such that bindings[0] corresponds to set 0
+    // bindings should be sorted such that bindings[0] corresponds to set 0.
+    // push_constant_ranges may come from several stages, see merge_push_constant_ranges
     pub fn new(
         device: SharedDeviceRef,
         set_bindings: &[&[vk::DescriptorSetLayoutBinding]],
+        push_constant_ranges: &[vk::PushConstantRange],
     ) -> Result<PipelineLayout> {
         let mut set_layouts = Vec::<crate::DescriptorSetLayout>::new();
         for (set, bindings) in set_bindings.iter().enumerate() {
@@ -35,6 +70,7 @@ impl PipelineLayout {
             set_layouts.push(set_layout);
         }
         let set_layouts = set_layouts.into_boxed_slice();
+        let push_constant_ranges = merge_push_constant_ranges(push_constant_ranges);
 
         let handle = {
             let vk_set_layouts: Box<[vk::DescriptorSetLayout]> =
@@ -42,6 +78,8 @@ impl PipelineLayout {
             let create_info = vk::PipelineLayoutCreateInfo {
                 set_layout_count: vk_set_layouts.len() as u32,
                 p_set_layouts: vk_set_layouts.as_ptr(),
+                push_constant_range_count: push_constant_ranges.len() as u32,
+                p_push_constant_ranges: push_constant_ranges.as_ptr(),
                 ..Default::default()
             };
 
@@ -52,6 +90,7 @@ impl PipelineLayout {
             device,
             bind_point: vk::PipelineBindPoint::GRAPHICS,
             set_layouts,
+            push_constant_ranges: push_constant_ranges.into_boxed_slice(),
             handle,
         })
     }
@@ -59,6 +98,10 @@ impl PipelineLayout {
     pub fn get_set_layouts(&self) -> &[DescriptorSetLayout] {
         &self.set_layouts
     }
+    #[inline]
+    pub fn get_push_constant_ranges(&self) -> &[vk::PushConstantRange] {
+        &self.push_constant_ranges
+    }
 }
 
 impl Drop for PipelineLayout {
@@ -69,6 +112,186 @@ impl Drop for PipelineLayout {
     }
 }
 
+// the defaults draw both faces filled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RasterizationConfig {
+    pub cull_mode: vk::CullModeFlags,
+    pub front_face: vk::FrontFace,
+    pub polygon_mode: vk::PolygonMode,
+    // anything other than 1.0 needs the wideLines feature
+    pub line_width: f32,
+}
+
+impl Default for RasterizationConfig {
+    fn default() -> Self {
+        Self {
+            cull_mode: vk::CullModeFlags::NONE,
+            front_face: vk::FrontFace::CLOCKWISE,
+            polygon_mode: vk::PolygonMode::FILL,
+            line_width: 1.0,
+        }
+    }
+}
+
+impl RasterizationConfig {
+    pub fn create_info(&self) -> vk::PipelineRasterizationStateCreateInfo<'static> {
+        vk::PipelineRasterizationStateCreateInfo {
+            depth_clamp_enable: vk::FALSE,
+            rasterizer_discard_enable: vk::FALSE,
+            polygon_mode: self.polygon_mode,
+            cull_mode: self.cull_mode,
+            front_face: self.front_face,
+            depth_bias_enable: vk::FALSE,
+            depth_bias_constant_factor: 0.0,
+            depth_bias_clamp: 0.0,
+            depth_bias_slope_factor: 0.0,
+            line_width: self.line_width,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ColorBlendConfig {
+    #[default]
+    Opaque,
+    // src * src_alpha + dst * (1 - src_alpha)
+    AlphaBlend,
+    // src * src_alpha + dst
+    Additive,
+}
+
+impl ColorBlendConfig {
+    pub fn attachment_state(&self) -> vk::PipelineColorBlendAttachmentState {
+        let (blend_enable, src_color_blend_factor, dst_color_blend_factor) = match self {
+            Self::Opaque => (vk::FALSE, vk::BlendFactor::ONE, vk::BlendFactor::ZERO),
+            Self::AlphaBlend => (
+                vk::TRUE,
+                vk::BlendFactor::SRC_ALPHA,
+                vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+            ),
+            Self::Additive => (vk::TRUE, vk::BlendFactor::SRC_ALPHA, vk::BlendFactor::ONE),
+        };
+
+        vk::PipelineColorBlendAttachmentState {
+            blend_enable,
+            src_color_blend_factor,
+            dst_color_blend_factor,
+            color_blend_op: vk::BlendOp::ADD,
+            src_alpha_blend_factor: vk::BlendFactor::ONE,
+            dst_alpha_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+            alpha_blend_op: vk::BlendOp::ADD,
+            color_write_mask: vk::ColorComponentFlags::RGBA,
+        }
+    }
+}
+
+// bindings are numbered in the order they're added
+#[derive(Debug, Clone, Default)]
+pub struct VertexInputBuilder {
+    bindings: Vec<vk::VertexInputBindingDescription>,
+    attributes: Vec<vk::VertexInputAttributeDescription>,
+}
+
+impl VertexInputBuilder {
+    // returns the new binding's number. INSTANCE rate bindings advance once per instance
+    pub fn add_binding(&mut self, stride: u32, input_rate: vk::VertexInputRate) -> u32 {
+        let binding = self.bindings.len() as u32;
+        self.bindings.push(vk::VertexInputBindingDescription {
+            binding,
+            stride,
+            input_rate,
+        });
+
+        binding
+    }
+
+    pub fn add_attribute(
+        &mut self,
+        binding: u32,
+        location: u32,
+        format: vk::Format,
+        offset: u32,
+    ) -> &mut Self {
+        self.attributes.push(vk::VertexInputAttributeDescription {
+            location,
+            binding,
+            format,
+            offset,
+        });
+
+        self
+    }
+
+    #[inline]
+    pub fn get_bindings(&self) -> &[vk::VertexInputBindingDescription] {
+        &self.bindings
+    }
+
+    #[inline]
+    pub fn get_attributes(&self) -> &[vk::VertexInputAttributeDescription] {
+        &self.attributes
+    }
+
+    // borrows the builder's arrays, so it has to outlive the create info
+    pub fn create_info(&self) -> vk::PipelineVertexInputStateCreateInfo<'_> {
+        vk::PipelineVertexInputStateCreateInfo {
+            vertex_binding_description_count: self.bindings.len() as u32,
+            p_vertex_binding_descriptions: self.bindings.as_ptr(),
+            vertex_attribute_description_count: self.attributes.len() as u32,
+            p_vertex_attribute_descriptions: self.attributes.as_ptr(),
+            ..Default::default()
+        }
+    }
+}
+
+fn validate_vertex_input_state(
+    vertex_input_state: &vk::PipelineVertexInputStateCreateInfo,
+) -> Result<()> {
+    if vertex_input_state.p_vertex_attribute_descriptions.is_null() {
+        return Ok(());
+    }
+
+    let attributes = unsafe {
+        std::slice::from_raw_parts(
+            vertex_input_state.p_vertex_attribute_descriptions,
+            vertex_input_state.vertex_attribute_description_count as usize,
+        )
+    };
+
+    if let Some(a) = attributes
+        .iter()
+        .find(|a| a.format == vk::Format::UNDEFINED)
+    {
+        return Err(Error::UndefinedVertexAttributeFormat {
+            location: a.location,
+            binding: a.binding,
+        });
+    }
+
+    let bindings = if vertex_input_state.p_vertex_binding_descriptions.is_null() {
+        &[][..]
+    } else {
+        unsafe {
+            std::slice::from_raw_parts(
+                vertex_input_state.p_vertex_binding_descriptions,
+                vertex_input_state.vertex_binding_description_count as usize,
+            )
+        }
+    };
+
+    match attributes
+        .iter()
+        .find(|a| !bindings.iter().any(|b| b.binding == a.binding))
+    {
+        Some(a) => Err(Error::VertexAttributeBindingMissing {
+            location: a.location,
+            binding: a.binding,
+        }),
+        None => Ok(()),
+    }
+}
+
 #[allow(dead_code)]
 pub struct Pipeline {
     device: SharedDeviceRef,
@@ -77,24 +300,48 @@ pub struct Pipeline {
 }
 
 impl Pipeline {
-    pub fn new_graphics(
+    // create_info.layout is ignored, the pipeline always uses layout.handle. Pipelines
+    // created from the same Rc<PipelineLayout> are layout compatible, so descriptor sets
+    // bound for one of them can be reused with the others.
+    //
+    // # Safety
+    //
+    // every pointer in create_info (stages, states, p_next chain) must be valid for the
+    // duration of the call, and the counts must match the arrays they describe.
+    pub unsafe fn new_graphics(
         device: SharedDeviceRef,
         layout: Rc<PipelineLayout>,
         create_info: &GraphicsPipelineCreateInfo,
+        pipeline_cache: Option<&PipelineCache>,
     ) -> Result<Self> {
-        let pipeline_create_info = [*create_info];
+        if let Some(vertex_input_state) = unsafe { create_info.p_vertex_input_state.as_ref() } {
+            validate_vertex_input_state(vertex_input_state)?;
+        }
+
+        let pipeline_create_info = [GraphicsPipelineCreateInfo {
+            layout: layout.handle,
+            ..*create_info
+        }];
         let pipelines = unsafe {
-            device.create_graphics_pipelines(vk::PipelineCache::null(), &pipeline_create_info)
+            device.create_graphics_pipelines(
+                pipeline_cache.map_or(vk::PipelineCache::null(), |c| c.handle),
+                &pipeline_create_info,
+            )
         }
         .map_err(|(_, vk_err)| vk_err)?;
 
         Ok(Pipeline {
             device,
-            layout: layout,
+            layout,
             pipeline: pipelines[0],
         })
     }
 
+    #[inline]
+    pub fn get_handle(&self) -> vk::Pipeline {
+        self.pipeline
+    }
+
     pub unsafe fn bind(&self, command_buffer: vk::CommandBuffer) {
         unsafe {
             self.device
@@ -102,10 +349,80 @@ impl Pipeline {
         }
     }
 
+    // dynamic_offsets needs one entry per dynamic descriptor in sets, see dynamic_offset_count
+    pub unsafe fn cmd_bind_descriptor_sets(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        first_set: u32,
+        sets: &[vk::DescriptorSet],
+        dynamic_offsets: &[u32],
+    ) -> Result<()> {
+        let expected = self
+            .layout
+            .set_layouts
+            .iter()
+            .skip(first_set as usize)
+            .take(sets.len())
+            .map(|l| l.dynamic_offset_count())
+            .sum();
+        if dynamic_offsets.len() as u32 != expected {
+            return Err(Error::DynamicOffsetCountMismatch {
+                expected,
+                actual: dynamic_offsets.len() as u32,
+            });
+        }
+
+        unsafe {
+            self.device.cmd_bind_descriptor_sets(
+                command_buffer,
+                self.layout.bind_point,
+                self.layout.handle,
+                first_set,
+                sets,
+                dynamic_offsets,
+            )
+        }
+
+        Ok(())
+    }
+
+    // pushes to every stage whose range overlaps offset..offset + data.len()
+    pub unsafe fn cmd_push_constants(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        offset: u32,
+        data: &[u8],
+    ) {
+        let end = offset + data.len() as u32;
+        let stage_flags = self
+            .layout
+            .push_constant_ranges
+            .iter()
+            .filter(|r| r.offset < end && offset < r.offset + r.size)
+            .fold(vk::ShaderStageFlags::empty(), |flags, r| {
+                flags | r.stage_flags
+            });
+
+        unsafe {
+            self.device.cmd_push_constants(
+                command_buffer,
+                self.layout.handle,
+                stage_flags,
+                offset,
+                data,
+            )
+        }
+    }
+
     #[inline]
     pub fn get_layout(&self) -> &PipelineLayout {
         &self.layout
     }
+
+    #[inline]
+    pub fn get_shared_layout(&self) -> Rc<PipelineLayout> {
+        self.layout.clone()
+    }
 }
 
 impl Drop for Pipeline {
@@ -113,3 +430,157 @@ impl Drop for Pipeline {
         unsafe { self.device.destroy_pipeline(self.pipeline) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_constant_ranges() {
+        let range = |stage_flags, offset, size| vk::PushConstantRange {
+            stage_flags,
+            offset,
+            size,
+        };
+        // vk::PushConstantRange doesn't implement PartialEq
+        let merge = |ranges: &[vk::PushConstantRange]| {
+            merge_push_constant_ranges(ranges)
+                .into_iter()
+                .map(|r| (r.stage_flags, r.offset, r.size))
+                .collect::<Vec<_>>()
+        };
+        let vert = vk::ShaderStageFlags::VERTEX;
+        let frag = vk::ShaderStageFlags::FRAGMENT;
+        let comp = vk::ShaderStageFlags::COMPUTE;
+
+        // the same block declared by two stages
+        assert_eq!(
+            merge(&[range(vert, 0, 64), range(frag, 0, 64)]),
+            [(vert | frag, 0, 64)]
+        );
+
+        // overlapping ranges are combined, disjoint ones from other stages are kept
+        assert_eq!(
+            merge(&[
+                range(vert, 0, 64),
+                range(comp, 128, 16),
+                range(frag, 48, 32),
+            ]),
+            [(vert | frag, 0, 80), (comp, 128, 16)]
+        );
+
+        // a stage can only be in one range, even if its ranges don't overlap
+        assert_eq!(
+            merge(&[range(vert, 0, 16), range(vert, 32, 16)]),
+            [(vert, 0, 48)]
+        );
+    }
+
+    #[test]
+    fn rasterization_config() {
+        let state = RasterizationConfig::default().create_info();
+        assert_eq!(state.cull_mode, vk::CullModeFlags::NONE);
+        assert_eq!(state.front_face, vk::FrontFace::CLOCKWISE);
+        assert_eq!(state.polygon_mode, vk::PolygonMode::FILL);
+        assert_eq!(state.line_width, 1.0);
+
+        let wireframe = RasterizationConfig {
+            cull_mode: vk::CullModeFlags::BACK,
+            polygon_mode: vk::PolygonMode::LINE,
+            ..Default::default()
+        }
+        .create_info();
+        assert_eq!(wireframe.cull_mode, vk::CullModeFlags::BACK);
+        assert_eq!(wireframe.polygon_mode, vk::PolygonMode::LINE);
+        assert_eq!(wireframe.front_face, vk::FrontFace::CLOCKWISE);
+    }
+
+    #[test]
+    fn color_blend_config() {
+        let opaque = ColorBlendConfig::default().attachment_state();
+        assert_eq!(opaque.blend_enable, vk::FALSE);
+        assert_eq!(opaque.color_write_mask, vk::ColorComponentFlags::RGBA);
+
+        let alpha = ColorBlendConfig::AlphaBlend.attachment_state();
+        assert_eq!(alpha.blend_enable, vk::TRUE);
+        assert_eq!(
+            alpha.dst_color_blend_factor,
+            vk::BlendFactor::ONE_MINUS_SRC_ALPHA
+        );
+
+        let additive = ColorBlendConfig::Additive.attachment_state();
+        assert_eq!(additive.dst_color_blend_factor, vk::BlendFactor::ONE);
+    }
+
+    #[test]
+    fn undefined_vertex_attribute_format() {
+        let attributes = [
+            vk::VertexInputAttributeDescription {
+                location: 0,
+                binding: 0,
+                format: vk::Format::R32G32B32_SFLOAT,
+                offset: 0,
+            },
+            vk::VertexInputAttributeDescription {
+                location: 1,
+                binding: 0,
+                format: vk::Format::UNDEFINED,
+                offset: 12,
+            },
+        ];
+        let vertex_input_state = vk::PipelineVertexInputStateCreateInfo {
+            vertex_attribute_description_count: attributes.len() as u32,
+            p_vertex_attribute_descriptions: attributes.as_ptr(),
+            ..Default::default()
+        };
+
+        match validate_vertex_input_state(&vertex_input_state) {
+            Err(Error::UndefinedVertexAttributeFormat { location, binding }) => {
+                assert_eq!(location, 1);
+                assert_eq!(binding, 0);
+            }
+            _ => panic!("expected UndefinedVertexAttributeFormat"),
+        }
+
+        let bindings = [vk::VertexInputBindingDescription {
+            binding: 0,
+            stride: 20,
+            input_rate: vk::VertexInputRate::VERTEX,
+        }];
+        let vertex_input_state = vk::PipelineVertexInputStateCreateInfo {
+            vertex_binding_description_count: bindings.len() as u32,
+            p_vertex_binding_descriptions: bindings.as_ptr(),
+            vertex_attribute_description_count: 1,
+            p_vertex_attribute_descriptions: attributes.as_ptr(),
+            ..Default::default()
+        };
+        assert!(validate_vertex_input_state(&vertex_input_state).is_ok());
+    }
+
+    #[test]
+    fn vertex_input_builder() {
+        let mut builder = VertexInputBuilder::default();
+        let per_vertex = builder.add_binding(32, vk::VertexInputRate::VERTEX);
+        let per_instance = builder.add_binding(12, vk::VertexInputRate::INSTANCE);
+        assert_eq!((per_vertex, per_instance), (0, 1));
+
+        builder
+            .add_attribute(per_vertex, 0, vk::Format::R32G32B32_SFLOAT, 0)
+            .add_attribute(per_instance, 3, vk::Format::R32G32B32_SFLOAT, 0);
+        assert_eq!(
+            builder.get_bindings()[1].input_rate,
+            vk::VertexInputRate::INSTANCE
+        );
+        assert_eq!(builder.get_attributes()[1].binding, 1);
+        assert!(validate_vertex_input_state(&builder.create_info()).is_ok());
+
+        // attributes have to read from a binding that was added
+        builder.add_attribute(2, 4, vk::Format::R32_SFLOAT, 0);
+        match validate_vertex_input_state(&builder.create_info()) {
+            Err(Error::VertexAttributeBindingMissing { location, binding }) => {
+                assert_eq!((location, binding), (4, 2));
+            }
+            _ => panic!("expected VertexAttributeBindingMissing"),
+        }
+    }
+}
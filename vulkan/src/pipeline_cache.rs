@@ -0,0 +1,160 @@
+use crate::device::SharedDeviceRef;
+use crate::result::Result;
+
+use ash::vk;
+use std::path::{Path, PathBuf};
+
+// size of the VK_PIPELINE_CACHE_HEADER_VERSION_ONE header
+const HEADER_SIZE: usize = 16 + vk::UUID_SIZE;
+
+// whether data starts with a cache header written by this driver for this device. drivers
+// are supposed to reject foreign data themselves, but not all of them do. the header fields
+// are little endian regardless of the host
+pub fn is_compatible_cache_data(data: &[u8], properties: &vk::PhysicalDeviceProperties) -> bool {
+    if data.len() < HEADER_SIZE {
+        return false;
+    }
+
+    let read_u32 = |i: usize| u32::from_le_bytes(data[i * 4..i * 4 + 4].try_into().unwrap());
+    let header_size = read_u32(0);
+    let header_version = read_u32(1);
+    let vendor_id = read_u32(2);
+    let device_id = read_u32(3);
+    let uuid = &data[16..HEADER_SIZE];
+
+    header_size as usize >= HEADER_SIZE
+        && header_size as usize <= data.len()
+        && header_version == vk::PipelineCacheHeaderVersion::ONE.as_raw() as u32
+        && vendor_id == properties.vendor_id
+        && device_id == properties.device_id
+        && uuid == properties.pipeline_cache_uuid
+}
+
+pub struct PipelineCache {
+    device: SharedDeviceRef,
+    pub handle: vk::PipelineCache,
+    // written back to on drop
+    path: Option<PathBuf>,
+}
+
+impl PipelineCache {
+    // an empty cache that only lives in memory
+    pub fn new(device: SharedDeviceRef) -> Result<Self> {
+        Self::create(device, &[], None)
+    }
+
+    // loads the cache saved at path. a missing, corrupt, or foreign file gives an empty cache
+    pub fn load(device: SharedDeviceRef, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let properties = unsafe { device.get_physical_device_properties() };
+        let data = if is_compatible_cache_data(&data, &properties) {
+            data.as_slice()
+        } else {
+            &[]
+        };
+
+        match Self::create(device.clone(), data, Some(path.to_path_buf())) {
+            Err(_) if !data.is_empty() => Self::create(device, &[], Some(path.to_path_buf())),
+            result => result,
+        }
+    }
+
+    fn create(device: SharedDeviceRef, data: &[u8], path: Option<PathBuf>) -> Result<Self> {
+        let create_info = vk::PipelineCacheCreateInfo {
+            initial_data_size: data.len(),
+            p_initial_data: data.as_ptr() as *const std::ffi::c_void,
+            ..Default::default()
+        };
+
+        let handle = unsafe { device.create_pipeline_cache(&create_info) }?;
+
+        Ok(PipelineCache {
+            device,
+            handle,
+            path,
+        })
+    }
+
+    #[inline]
+    pub fn get_path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn get_data(&self) -> Result<Vec<u8>> {
+        Ok(unsafe { self.device.get_pipeline_cache_data(self.handle) }?)
+    }
+
+    // does nothing for caches created with new
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let data = self.get_data()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, data)?;
+
+        Ok(())
+    }
+}
+
+impl Drop for PipelineCache {
+    fn drop(&mut self) {
+        // there's no one to report a failed save to. the next run just starts empty
+        let _ = self.save();
+
+        unsafe { self.device.destroy_pipeline_cache(self.handle) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_header() {
+        let properties = vk::PhysicalDeviceProperties {
+            vendor_id: 0x10de,
+            device_id: 0x2684,
+            pipeline_cache_uuid: [7; vk::UUID_SIZE],
+            ..Default::default()
+        };
+
+        let mut data = Vec::new();
+        for v in [HEADER_SIZE as u32, 1, 0x10de, 0x2684] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        data.extend_from_slice(&[7; vk::UUID_SIZE]);
+        data.extend_from_slice(&[0; 64]);
+        assert!(is_compatible_cache_data(&data, &properties));
+
+        // truncated
+        assert!(!is_compatible_cache_data(&data[..20], &properties));
+        assert!(!is_compatible_cache_data(&[], &properties));
+
+        // written for another device or driver version
+        let other_device = vk::PhysicalDeviceProperties {
+            device_id: 0x2685,
+            ..properties
+        };
+        assert!(!is_compatible_cache_data(&data, &other_device));
+        let other_driver = vk::PhysicalDeviceProperties {
+            pipeline_cache_uuid: [8; vk::UUID_SIZE],
+            ..properties
+        };
+        assert!(!is_compatible_cache_data(&data, &other_driver));
+
+        // garbage
+        let mut corrupt = data.clone();
+        corrupt[4] = 0xff;
+        assert!(!is_compatible_cache_data(&corrupt, &properties));
+    }
+}
@@ -0,0 +1,217 @@
+use crate::device::SharedDeviceRef;
+use crate::result::{Error, Result};
+
+use ash::vk;
+
+pub struct QueryPool {
+    device: SharedDeviceRef,
+    pub handle: vk::QueryPool,
+    query_type: vk::QueryType,
+    query_count: u32,
+}
+
+fn check_query_range(first_query: u32, count: u32, query_count: u32) -> Result<()> {
+    match first_query.checked_add(count) {
+        Some(end) if end <= query_count => Ok(()),
+        _ => Err(Error::QueryOutOfRange {
+            first_query,
+            count,
+            query_count,
+        }),
+    }
+}
+
+// milliseconds between two timestamps written to a queue with valid_bits bits of timestamp
+// resolution. period_ns is the device's timestampPeriod. the counter may wrap between them
+pub fn timestamp_elapsed_ms(start: u64, end: u64, valid_bits: u32, period_ns: f32) -> f32 {
+    let mask = if valid_bits >= 64 {
+        u64::MAX
+    } else {
+        (1 << valid_bits) - 1
+    };
+    let ticks = end.wrapping_sub(start) & mask;
+
+    (ticks as f64 * period_ns as f64 / 1_000_000.0) as f32
+}
+
+impl QueryPool {
+    pub fn new(
+        device: SharedDeviceRef,
+        query_type: vk::QueryType,
+        query_count: u32,
+    ) -> Result<Self> {
+        let create_info = vk::QueryPoolCreateInfo {
+            query_type,
+            query_count,
+            ..Default::default()
+        };
+
+        let handle = unsafe { device.create_query_pool(&create_info) }?;
+
+        Ok(QueryPool {
+            device,
+            handle,
+            query_type,
+            query_count,
+        })
+    }
+
+    #[inline]
+    pub fn occlusion(device: SharedDeviceRef, query_count: u32) -> Result<Self> {
+        Self::new(device, vk::QueryType::OCCLUSION, query_count)
+    }
+
+    // only usable if Device::timestamp_valid_bits isn't 0
+    #[inline]
+    pub fn timestamp(device: SharedDeviceRef, query_count: u32) -> Result<Self> {
+        Self::new(device, vk::QueryType::TIMESTAMP, query_count)
+    }
+
+    #[inline]
+    pub fn query_type(&self) -> vk::QueryType {
+        self.query_type
+    }
+
+    #[inline]
+    pub fn query_count(&self) -> u32 {
+        self.query_count
+    }
+
+    // has to be recorded outside of a render pass, before the queries are begun
+    pub unsafe fn cmd_reset(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        first_query: u32,
+        count: u32,
+    ) -> Result<()> {
+        check_query_range(first_query, count, self.query_count)?;
+
+        unsafe {
+            self.device
+                .cmd_reset_query_pool(command_buffer, self.handle, first_query, count)
+        };
+
+        Ok(())
+    }
+
+    pub unsafe fn cmd_begin(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        query: u32,
+        flags: vk::QueryControlFlags,
+    ) -> Result<()> {
+        check_query_range(query, 1, self.query_count)?;
+
+        unsafe {
+            self.device
+                .cmd_begin_query(command_buffer, self.handle, query, flags)
+        };
+
+        Ok(())
+    }
+
+    pub unsafe fn cmd_end(&self, command_buffer: vk::CommandBuffer, query: u32) -> Result<()> {
+        check_query_range(query, 1, self.query_count)?;
+
+        unsafe {
+            self.device
+                .cmd_end_query(command_buffer, self.handle, query)
+        };
+
+        Ok(())
+    }
+
+    // writes the time at which every command before it has finished stage
+    pub unsafe fn cmd_write_timestamp(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        stage: vk::PipelineStageFlags2,
+        query: u32,
+    ) -> Result<()> {
+        check_query_range(query, 1, self.query_count)?;
+
+        unsafe {
+            self.device
+                .cmd_write_timestamp2(command_buffer, stage, self.handle, query)
+        };
+
+        Ok(())
+    }
+
+    // returns None for every query whose result is not available yet. For occlusion
+    // queries the value is the number of samples that passed the depth/stencil tests.
+    pub fn get_results(&self, first_query: u32, count: u32) -> Result<Box<[Option<u64>]>> {
+        check_query_range(first_query, count, self.query_count)?;
+
+        // every result is followed by its availability
+        let mut data = vec![[0u64; 2]; count as usize];
+
+        match unsafe {
+            self.device.get_query_pool_results(
+                self.handle,
+                first_query,
+                &mut data,
+                vk::QueryResultFlags::TYPE_64 | vk::QueryResultFlags::WITH_AVAILABILITY,
+            )
+        } {
+            Ok(()) | Err(vk::Result::NOT_READY) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        Ok(data
+            .iter()
+            .map(|[result, available]| if *available != 0 { Some(*result) } else { None })
+            .collect())
+    }
+}
+
+impl Drop for QueryPool {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_query_pool(self.handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_query_range, timestamp_elapsed_ms};
+    use crate::result::Error;
+
+    #[test]
+    fn query_range() {
+        assert!(check_query_range(0, 4, 4).is_ok());
+        assert!(check_query_range(3, 1, 4).is_ok());
+        assert!(check_query_range(4, 0, 4).is_ok());
+
+        match check_query_range(3, 2, 4) {
+            Err(Error::QueryOutOfRange {
+                first_query,
+                count,
+                query_count,
+            }) => {
+                assert_eq!(first_query, 3);
+                assert_eq!(count, 2);
+                assert_eq!(query_count, 4);
+            }
+            r => panic!("expected QueryOutOfRange, got {:?}", r),
+        }
+
+        assert!(check_query_range(u32::MAX, 1, 4).is_err());
+    }
+
+    #[test]
+    fn timestamp_elapsed() {
+        assert_eq!(timestamp_elapsed_ms(1_000, 3_000_000, 64, 1.0), 2.999);
+        assert_eq!(timestamp_elapsed_ms(0, 1_000, 64, 2.5), 0.0025);
+
+        // a 32 bit counter that wrapped between the two timestamps
+        let start = u32::MAX as u64 - 499_999;
+        assert_eq!(timestamp_elapsed_ms(start, 500_000, 32, 1.0), 1.0);
+        // bits above valid_bits are ignored
+        assert_eq!(
+            timestamp_elapsed_ms(0xdead << 32, (0xbeef << 32) | 2_000_000, 32, 1.0),
+            2.0
+        );
+    }
+}
@@ -6,7 +6,15 @@ pub enum Error {
     CouldNotFindExtension(std::ffi::CString),
     VkError(ash::vk::Result),
     NoViablePhysicalDevices,
+    PhysicalDeviceIndexOutOfRange {
+        index: usize,
+        count: usize,
+    },
     IoError(std::io::Error),
+    FileIoError {
+        path: std::path::PathBuf,
+        error: std::io::Error,
+    },
     TooManyDescriptorSets,
     CouldNotDetermineEntryPointName,
     CouldNotDetermineFormat,
@@ -14,7 +22,37 @@ pub enum Error {
     NoSurfaceFomratsSupported,
     CouldNotFindMemoryTypeIndex(ash::vk::MemoryPropertyFlags),
     InvalidBufferType,
+    UndefinedVertexAttributeFormat {
+        location: u32,
+        binding: u32,
+    },
+    VertexAttributeBindingMissing {
+        location: u32,
+        binding: u32,
+    },
+    CommandBufferLevelMismatch {
+        expected: ash::vk::CommandBufferLevel,
+        actual: ash::vk::CommandBufferLevel,
+    },
+    QueryOutOfRange {
+        first_query: u32,
+        count: u32,
+        query_count: u32,
+    },
+    UnsupportedSwapchainImageUsage {
+        requested: ash::vk::ImageUsageFlags,
+        supported: ash::vk::ImageUsageFlags,
+    },
     WinitHandleError(winit::raw_window_handle::HandleError),
+    // the swapchain no longer matches its surface and has to be recreated
+    SwapchainOutOfDate,
+    // the gpu didn't finish within the requested timeout
+    Timeout,
+    DynamicOffsetCountMismatch {
+        expected: u32,
+        actual: u32,
+    },
+    EmptyBuffer,
     NotImplemented,
 }
 
@@ -29,7 +67,15 @@ impl std::fmt::Display for Error {
             }
             Self::VkError(r) => write!(f, "Vk error: {:?}", r),
             Self::NoViablePhysicalDevices => write!(f, "No viable physical devices found"),
+            Self::PhysicalDeviceIndexOutOfRange { index, count } => write!(
+                f,
+                "Physical device {} was selected, but only {} are viable",
+                index, count
+            ),
             Self::IoError(e) => write!(f, "I/O error: {}", e),
+            Self::FileIoError { path, error } => {
+                write!(f, "I/O error on {}: {}", path.display(), error)
+            }
             Self::TooManyDescriptorSets => write!(f, "Too many descriptor sets allocated"),
             Self::CouldNotDetermineFormat => write!(f, "Could not determine format"),
             Self::CouldNotGetSurfaceFormats(r) => {
@@ -40,6 +86,48 @@ impl std::fmt::Display for Error {
                 write!(f, "Could not find memory type index with flags {:?}", flags)
             }
             Self::InvalidBufferType => write!(f, "Invalid buffer type"),
+            Self::UndefinedVertexAttributeFormat { location, binding } => write!(
+                f,
+                "Vertex attribute at location {} (binding {}) has an undefined format",
+                location, binding
+            ),
+            Self::VertexAttributeBindingMissing { location, binding } => write!(
+                f,
+                "Vertex attribute at location {} reads from binding {}, which wasn't declared",
+                location, binding
+            ),
+            Self::CommandBufferLevelMismatch { expected, actual } => write!(
+                f,
+                "Expected a {:?} command buffer, got a {:?} one",
+                expected, actual
+            ),
+            Self::QueryOutOfRange {
+                first_query,
+                count,
+                query_count,
+            } => write!(
+                f,
+                "Queries {}..{} are out of range for a pool of {} queries",
+                first_query,
+                first_query.saturating_add(*count),
+                query_count
+            ),
+            Self::UnsupportedSwapchainImageUsage {
+                requested,
+                supported,
+            } => write!(
+                f,
+                "Swapchain image usage {:?} is not supported by the surface (supported: {:?})",
+                requested, supported
+            ),
+            Self::SwapchainOutOfDate => write!(f, "Swapchain is out of date"),
+            Self::Timeout => write!(f, "Timed out waiting for the GPU"),
+            Self::DynamicOffsetCountMismatch { expected, actual } => write!(
+                f,
+                "The bound descriptor sets have {} dynamic descriptors, but {} dynamic offsets were given",
+                expected, actual
+            ),
+            Self::EmptyBuffer => write!(f, "Cannot create a buffer from empty data"),
             _ => write!(f, "Not implemented"),
         }
     }
@@ -0,0 +1,56 @@
+use crate::device::SharedDeviceRef;
+use crate::result::Result;
+
+use ash::vk;
+
+pub struct SamplerCreateInfo {
+    pub mag_filter: vk::Filter,
+    pub min_filter: vk::Filter,
+    pub mipmap_mode: vk::SamplerMipmapMode,
+    pub address_mode_u: vk::SamplerAddressMode,
+    pub address_mode_v: vk::SamplerAddressMode,
+    pub address_mode_w: vk::SamplerAddressMode,
+    // None disables anisotropic filtering
+    pub max_anisotropy: Option<f32>,
+    pub min_lod: f32,
+    // vk::LOD_CLAMP_NONE to use every mip level of the image
+    pub max_lod: f32,
+}
+
+pub struct Sampler {
+    device: SharedDeviceRef,
+    pub handle: vk::Sampler,
+}
+
+impl Sampler {
+    pub fn new(device: SharedDeviceRef, create_info: &SamplerCreateInfo) -> Result<Self> {
+        let sampler_create_info = vk::SamplerCreateInfo {
+            mag_filter: create_info.mag_filter,
+            min_filter: create_info.min_filter,
+            mipmap_mode: create_info.mipmap_mode,
+            address_mode_u: create_info.address_mode_u,
+            address_mode_v: create_info.address_mode_v,
+            address_mode_w: create_info.address_mode_w,
+            mip_lod_bias: 0.0,
+            anisotropy_enable: create_info.max_anisotropy.is_some() as vk::Bool32,
+            max_anisotropy: create_info.max_anisotropy.unwrap_or(1.0),
+            compare_enable: vk::FALSE,
+            compare_op: vk::CompareOp::ALWAYS,
+            min_lod: create_info.min_lod,
+            max_lod: create_info.max_lod,
+            ..Default::default()
+        };
+
+        let handle = unsafe { device.create_sampler(&sampler_create_info) }?;
+
+        Ok(Sampler { device, handle })
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_sampler(self.handle);
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use ash::vk;
 
-use crate::Result;
+use crate::{Error, Result};
 
 use std::io::Read;
 
@@ -9,20 +9,27 @@ pub struct ShaderModule {
     device: crate::device::SharedDeviceRef,
 }
 
+fn read_shader_code(shader_path: &std::path::Path) -> Result<Vec<u8>> {
+    let with_path = |error| Error::FileIoError {
+        path: shader_path.to_path_buf(),
+        error,
+    };
+
+    let mut file = std::fs::File::open(shader_path).map_err(with_path)?;
+
+    let mut data = Vec::<u8>::new();
+
+    let _ = file.read_to_end(&mut data).map_err(with_path)?;
+
+    Ok(data)
+}
+
 impl ShaderModule {
     pub fn from_file(
-        shader_path: &std::path::Path,
+        shader_path: impl AsRef<std::path::Path>,
         device: crate::device::SharedDeviceRef,
     ) -> Result<ShaderModule> {
-        let shader_code = {
-            let mut file = std::fs::File::open(shader_path)?;
-
-            let mut data = Vec::<u8>::new();
-
-            let _ = file.read_to_end(&mut data)?;
-
-            data
-        };
+        let shader_code = read_shader_code(shader_path.as_ref())?;
 
         Self::from_compiled_spv(&shader_code, device)
     }
@@ -52,3 +59,24 @@ impl Drop for ShaderModule {
         unsafe { self.device.destroy_shader_module(self.handle) };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_shader_file() {
+        let path = std::path::Path::new("shaders/does_not_exist.vert.spv");
+
+        let e = read_shader_code(path).unwrap_err();
+        assert!(e.to_string().contains("does_not_exist.vert.spv"));
+
+        match e {
+            Error::FileIoError { path: p, error } => {
+                assert_eq!(p, path);
+                assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+            }
+            e => panic!("expected FileIoError, got {:?}", e),
+        }
+    }
+}
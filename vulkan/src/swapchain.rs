@@ -8,13 +8,211 @@ pub struct Swapchain {
     swapchain: vk::SwapchainKHR,
     extent: vk::Extent2D,
     format: vk::Format,
+    color_space: vk::ColorSpaceKHR,
     images: Box<[vk::Image]>,
     image_views: Box<[vk::ImageView]>,
     present_mode: vk::PresentModeKHR,
+    present_policy: PresentPolicy,
+    image_usage: vk::ImageUsageFlags,
+}
+
+// how the present mode is picked. each policy falls back to FIFO, which is always supported
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PresentPolicy {
+    // MAILBOX, then FIFO
+    #[default]
+    LowLatency,
+    // FIFO
+    Vsync,
+    // IMMEDIATE, then MAILBOX, then FIFO
+    NoVsync,
+}
+
+impl PresentPolicy {
+    fn fallback_order(self) -> &'static [vk::PresentModeKHR] {
+        match self {
+            Self::LowLatency => &[vk::PresentModeKHR::MAILBOX, vk::PresentModeKHR::FIFO],
+            Self::Vsync => &[vk::PresentModeKHR::FIFO],
+            Self::NoVsync => &[
+                vk::PresentModeKHR::IMMEDIATE,
+                vk::PresentModeKHR::MAILBOX,
+                vk::PresentModeKHR::FIFO,
+            ],
+        }
+    }
+}
+
+fn choose_present_mode(
+    policy: PresentPolicy,
+    supported: &[vk::PresentModeKHR],
+) -> vk::PresentModeKHR {
+    policy
+        .fallback_order()
+        .iter()
+        .copied()
+        .find(|mode| supported.contains(mode))
+        .unwrap_or(vk::PresentModeKHR::FIFO)
+}
+
+// MAILBOX needs a third image to always have one free to render into
+pub fn frames_in_flight(present_mode: vk::PresentModeKHR) -> usize {
+    match present_mode {
+        vk::PresentModeKHR::MAILBOX => 3,
+        _ => 2,
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SwapchainConfig {
+    pub image_usage: vk::ImageUsageFlags,
+    pub present_policy: PresentPolicy,
+}
+
+impl Default for SwapchainConfig {
+    // TRANSFER_SRC lets swapchain images be copied out for screenshots
+    fn default() -> Self {
+        SwapchainConfig {
+            image_usage: vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+            present_policy: PresentPolicy::default(),
+        }
+    }
+}
+
+fn check_image_usage(requested: vk::ImageUsageFlags, supported: vk::ImageUsageFlags) -> Result<()> {
+    if supported.contains(requested) {
+        Ok(())
+    } else {
+        Err(Error::UnsupportedSwapchainImageUsage {
+            requested,
+            supported,
+        })
+    }
+}
+
+// the extent the swapchain images should have. (0, 0) while the window is minimized
+fn choose_extent(
+    capabilities: &vk::SurfaceCapabilitiesKHR,
+    window: &winit::window::Window,
+) -> vk::Extent2D {
+    if capabilities.current_extent.width == u32::MAX {
+        ash::vk::Extent2D {
+            width: window.inner_size().width,
+            height: window.inner_size().height,
+        }
+    } else {
+        capabilities.current_extent
+    }
+}
+
+struct BuiltSwapchain {
+    handle: vk::SwapchainKHR,
+    present_mode: vk::PresentModeKHR,
+    images: Box<[vk::Image]>,
+    views: Box<[vk::ImageView]>,
+}
+
+// creates the swapchain and a view for each of its images. old_swapchain may be null
+fn build(
+    device: &SharedDeviceRef,
+    surface: vk::SurfaceKHR,
+    surface_format: vk::SurfaceFormatKHR,
+    capabilities: &vk::SurfaceCapabilitiesKHR,
+    image_extent: vk::Extent2D,
+    config: &SwapchainConfig,
+    old_swapchain: vk::SwapchainKHR,
+) -> Result<BuiltSwapchain> {
+    let (min_image_count, max_image_count) =
+        if capabilities.min_image_count > capabilities.max_image_count {
+            (capabilities.min_image_count, capabilities.min_image_count)
+        } else {
+            (capabilities.min_image_count, capabilities.max_image_count)
+        };
+
+    let present_policy = config.present_policy;
+    let present_mode = {
+        let modes = unsafe { device.get_physical_device_surface_present_modes(surface) }?;
+
+        choose_present_mode(present_policy, &modes)
+    };
+    let desired_image_count = frames_in_flight(present_mode) as u32;
+
+    let swapchain = {
+        let swapchain_create_info = ash::vk::SwapchainCreateInfoKHR {
+            surface,
+            min_image_count: desired_image_count.clamp(min_image_count, max_image_count),
+            image_format: surface_format.format,
+            image_color_space: surface_format.color_space,
+            image_extent,
+            image_usage: config.image_usage,
+            image_sharing_mode: ash::vk::SharingMode::EXCLUSIVE,
+            present_mode,
+            composite_alpha: ash::vk::CompositeAlphaFlagsKHR::OPAQUE,
+            pre_transform: ash::vk::SurfaceTransformFlagsKHR::IDENTITY,
+            clipped: ash::vk::FALSE,
+            image_array_layers: 1,
+            old_swapchain,
+            ..Default::default()
+        };
+
+        unsafe { device.create_swapchain(&swapchain_create_info) }?
+    };
+
+    let swapchain_images = unsafe { device.get_swapchain_images(swapchain) }
+        .inspect_err(|_| unsafe { device.destroy_swapchain(swapchain) })?
+        .into_boxed_slice();
+
+    let mut views = Vec::with_capacity(swapchain_images.len());
+    for image in swapchain_images.iter() {
+        let image_view_create_info = ash::vk::ImageViewCreateInfo {
+            image: *image,
+            view_type: ash::vk::ImageViewType::TYPE_2D,
+            format: surface_format.format,
+            components: ash::vk::ComponentMapping {
+                r: ash::vk::ComponentSwizzle::IDENTITY,
+                g: ash::vk::ComponentSwizzle::IDENTITY,
+                b: ash::vk::ComponentSwizzle::IDENTITY,
+                a: ash::vk::ComponentSwizzle::IDENTITY,
+            },
+            subresource_range: ash::vk::ImageSubresourceRange {
+                aspect_mask: ash::vk::ImageAspectFlags::COLOR,
+                base_mip_level: 0,
+                level_count: 1,
+                base_array_layer: 0,
+                layer_count: 1,
+            },
+            ..Default::default()
+        };
+
+        let view = unsafe { device.create_image_view(&image_view_create_info) }.inspect_err(
+            |_| unsafe {
+                for view in views.iter() {
+                    device.destroy_image_view(*view);
+                }
+                device.destroy_swapchain(swapchain);
+            },
+        )?;
+        views.push(view);
+    }
+
+    Ok(BuiltSwapchain {
+        handle: swapchain,
+        present_mode,
+        images: swapchain_images,
+        views: views.into_boxed_slice(),
+    })
 }
 
 impl Swapchain {
+    #[inline]
     pub fn new(device: SharedDeviceRef, window: &winit::window::Window) -> Result<Swapchain> {
+        Self::with_config(device, window, &SwapchainConfig::default())
+    }
+
+    pub fn with_config(
+        device: SharedDeviceRef,
+        window: &winit::window::Window,
+        config: &SwapchainConfig,
+    ) -> Result<Swapchain> {
         let surface = unsafe { device.create_surface(window) }?;
 
         let surface_format = unsafe { device.get_physical_device_surface_formats(surface) }?
@@ -22,105 +220,90 @@ impl Swapchain {
             .next()
             .ok_or(Error::NoSurfaceFomratsSupported)?;
 
-        let (min_image_count, max_image_count, image_extent) = {
-            let capabilities = unsafe { device.get_physical_device_surface_capabilities(surface) }?;
+        let capabilities = unsafe { device.get_physical_device_surface_capabilities(surface) }?;
 
-            let extent = if capabilities.current_extent.width == u32::MAX {
-                ash::vk::Extent2D {
-                    width: window.inner_size().width,
-                    height: window.inner_size().height,
-                }
-            } else {
-                capabilities.current_extent
-            };
-
-            if capabilities.min_image_count > capabilities.max_image_count {
-                (
-                    capabilities.min_image_count,
-                    capabilities.min_image_count,
-                    extent,
-                )
-            } else {
-                (
-                    capabilities.min_image_count,
-                    capabilities.max_image_count,
-                    extent,
-                )
-            }
-        };
+        check_image_usage(config.image_usage, capabilities.supported_usage_flags)
+            .inspect_err(|_| unsafe { device.destroy_surface(surface) })?;
 
-        let (present_mode, desired_image_count) = {
-            let modes = unsafe { device.get_physical_device_surface_present_modes(surface) }?;
+        let image_extent = choose_extent(&capabilities, window);
 
-            if modes.contains(&ash::vk::PresentModeKHR::MAILBOX) {
-                (ash::vk::PresentModeKHR::MAILBOX, 3)
-            } else {
-                (ash::vk::PresentModeKHR::FIFO, 2)
-            }
-        };
-
-        let swapchain = {
-            let swapchain_create_info = ash::vk::SwapchainCreateInfoKHR {
-                surface: surface,
-                min_image_count: desired_image_count.clamp(min_image_count, max_image_count),
-                image_format: surface_format.format,
-                image_color_space: surface_format.color_space,
-                image_extent,
-                image_usage: ash::vk::ImageUsageFlags::COLOR_ATTACHMENT,
-                image_sharing_mode: ash::vk::SharingMode::EXCLUSIVE,
-                present_mode,
-                composite_alpha: ash::vk::CompositeAlphaFlagsKHR::OPAQUE,
-                pre_transform: ash::vk::SurfaceTransformFlagsKHR::IDENTITY,
-                clipped: ash::vk::FALSE,
-                image_array_layers: 1,
-                ..Default::default()
-            };
-
-            unsafe { device.create_swapchain(&swapchain_create_info) }?
-        };
-
-        let swapchain_images =
-            unsafe { device.get_swapchain_images(swapchain) }?.into_boxed_slice();
-
-        let mut views = Vec::with_capacity(swapchain_images.len());
-        for image in swapchain_images.iter() {
-            let image_view_create_info = ash::vk::ImageViewCreateInfo {
-                image: *image,
-                view_type: ash::vk::ImageViewType::TYPE_2D,
-                format: surface_format.format,
-                components: ash::vk::ComponentMapping {
-                    r: ash::vk::ComponentSwizzle::IDENTITY,
-                    g: ash::vk::ComponentSwizzle::IDENTITY,
-                    b: ash::vk::ComponentSwizzle::IDENTITY,
-                    a: ash::vk::ComponentSwizzle::IDENTITY,
-                },
-                subresource_range: ash::vk::ImageSubresourceRange {
-                    aspect_mask: ash::vk::ImageAspectFlags::COLOR,
-                    base_mip_level: 0,
-                    level_count: 1,
-                    base_array_layer: 0,
-                    layer_count: 1,
-                },
-                ..Default::default()
-            };
-
-            let view = unsafe { device.create_image_view(&image_view_create_info) }?;
-            views.push(view);
-        }
-        let views = views.into_boxed_slice();
+        let built = build(
+            &device,
+            surface,
+            surface_format,
+            &capabilities,
+            image_extent,
+            config,
+            vk::SwapchainKHR::null(),
+        )
+        .inspect_err(|_| unsafe { device.destroy_surface(surface) })?;
 
         Ok(Swapchain {
             device,
             surface,
-            swapchain,
+            swapchain: built.handle,
             format: surface_format.format,
+            color_space: surface_format.color_space,
             extent: image_extent,
-            images: swapchain_images,
-            image_views: views,
-            present_mode,
+            images: built.images,
+            image_views: built.views,
+            present_mode: built.present_mode,
+            present_policy: config.present_policy,
+            image_usage: config.image_usage,
         })
     }
 
+    // rebuilds the images for the window's current size, handing the old swapchain to the
+    // driver so it can reuse its resources. the caller has to make sure the old images are no
+    // longer in use. returns false, leaving the swapchain as is, while the window is minimized
+    pub fn recreate(&mut self, window: &winit::window::Window) -> Result<bool> {
+        let capabilities = unsafe {
+            self.device
+                .get_physical_device_surface_capabilities(self.surface)
+        }?;
+
+        let image_extent = choose_extent(&capabilities, window);
+        if image_extent.width == 0 || image_extent.height == 0 {
+            return Ok(false);
+        }
+
+        let built = build(
+            &self.device,
+            self.surface,
+            vk::SurfaceFormatKHR {
+                format: self.format,
+                color_space: self.color_space,
+            },
+            &capabilities,
+            image_extent,
+            &SwapchainConfig {
+                image_usage: self.image_usage,
+                present_policy: self.present_policy,
+            },
+            self.swapchain,
+        )?;
+
+        unsafe {
+            for image_view in self.image_views.iter().rev() {
+                self.device.destroy_image_view(*image_view);
+            }
+            self.device.destroy_swapchain(self.swapchain);
+        }
+
+        self.swapchain = built.handle;
+        self.present_mode = built.present_mode;
+        self.extent = image_extent;
+        self.images = built.images;
+        self.image_views = built.views;
+
+        Ok(true)
+    }
+
+    #[inline]
+    pub fn get_image_usage(&self) -> vk::ImageUsageFlags {
+        self.image_usage
+    }
+
     #[inline]
     pub fn get_extent(&self) -> &vk::Extent2D {
         &self.extent
@@ -155,6 +338,11 @@ impl Swapchain {
         self.present_mode
     }
 
+    #[inline]
+    pub fn get_present_policy(&self) -> PresentPolicy {
+        self.present_policy
+    }
+
     pub unsafe fn acquire_next_image(
         &self,
         semaphore: vk::Semaphore,
@@ -162,7 +350,24 @@ impl Swapchain {
     ) -> ash::prelude::VkResult<(u32, bool)> {
         unsafe {
             self.device
-                .acquire_next_image(self.swapchain, semaphore, fence)
+                .acquire_next_image(self.swapchain, u64::MAX, semaphore, fence)
+        }
+    }
+
+    // returns None if no image became available within timeout_ns
+    pub unsafe fn acquire_next_image_timeout(
+        &self,
+        semaphore: vk::Semaphore,
+        fence: vk::Fence,
+        timeout_ns: u64,
+    ) -> ash::prelude::VkResult<Option<(u32, bool)>> {
+        match unsafe {
+            self.device
+                .acquire_next_image(self.swapchain, timeout_ns, semaphore, fence)
+        } {
+            Ok(r) => Ok(Some(r)),
+            Err(vk::Result::TIMEOUT | vk::Result::NOT_READY) => Ok(None),
+            Err(e) => Err(e),
         }
     }
 
@@ -185,3 +390,57 @@ impl Drop for Swapchain {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsupported_image_usage() {
+        let supported = vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_DST;
+
+        assert!(check_image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT, supported).is_ok());
+
+        match check_image_usage(SwapchainConfig::default().image_usage, supported) {
+            Err(Error::UnsupportedSwapchainImageUsage {
+                requested,
+                supported: s,
+            }) => {
+                assert!(requested.contains(vk::ImageUsageFlags::TRANSFER_SRC));
+                assert_eq!(s, supported);
+            }
+            r => panic!("expected UnsupportedSwapchainImageUsage, got {:?}", r.err()),
+        }
+    }
+
+    #[test]
+    fn present_mode_fallback() {
+        use vk::PresentModeKHR as Mode;
+
+        let all = [Mode::FIFO, Mode::MAILBOX, Mode::IMMEDIATE];
+        let fifo_only = [Mode::FIFO];
+        let no_immediate = [Mode::FIFO, Mode::MAILBOX];
+
+        assert_eq!(
+            choose_present_mode(PresentPolicy::LowLatency, &all),
+            Mode::MAILBOX
+        );
+        assert_eq!(
+            choose_present_mode(PresentPolicy::LowLatency, &fifo_only),
+            Mode::FIFO
+        );
+        assert_eq!(choose_present_mode(PresentPolicy::Vsync, &all), Mode::FIFO);
+        assert_eq!(
+            choose_present_mode(PresentPolicy::NoVsync, &all),
+            Mode::IMMEDIATE
+        );
+        assert_eq!(
+            choose_present_mode(PresentPolicy::NoVsync, &no_immediate),
+            Mode::MAILBOX
+        );
+        assert_eq!(
+            choose_present_mode(PresentPolicy::NoVsync, &fifo_only),
+            Mode::FIFO
+        );
+    }
+}